@@ -0,0 +1,187 @@
+use proc_macro2::{TokenStream as TokenStream2};
+use syn::{DeriveInput, Data, Ident, LitStr, LitInt};
+use quote::quote;
+
+/// Options from the #[descriptor(...)] struct attribute
+struct DescriptorOptions {
+    /// Entity descriptor name
+    name: LitStr,
+    /// Field containing the parent block entity descriptor
+    parent: Option<Ident>,
+    /// Serialized component count override
+    components: Option<LitInt>,
+    /// Register as a block, even without a parent block entity descriptor
+    block: bool,
+}
+
+/// Seed used by Techblox for hashing entity descriptor names
+const HASH_SEED: u32 = 4919;
+
+/// Murmur3 (32 bit) hash of an entity descriptor name, the same as `libfj::techblox::hashname(...)`
+fn hashname(name: &str) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+    let data = name.as_bytes();
+    let mut h1 = HASH_SEED;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let k1 = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+            .wrapping_mul(C1)
+            .rotate_left(15)
+            .wrapping_mul(C2);
+        h1 ^= k1;
+        h1 = h1.rotate_left(13).wrapping_mul(5).wrapping_add(0xe6546b64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k1 = 0u32;
+        for (i, byte) in tail.iter().enumerate() {
+            k1 ^= (*byte as u32) << (8 * i);
+        }
+        h1 ^= k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+    h1 ^= data.len() as u32;
+    h1 ^= h1 >> 16;
+    h1 = h1.wrapping_mul(0x85ebca6b);
+    h1 ^= h1 >> 13;
+    h1 = h1.wrapping_mul(0xc2b2ae35);
+    h1 ^ (h1 >> 16)
+}
+
+fn descriptor_options(ast: &DeriveInput) -> syn::Result<DescriptorOptions> {
+    let mut name = None;
+    let mut parent = None;
+    let mut components = None;
+    let mut block = false;
+    for attr in &ast.attrs {
+        if !attr.path().is_ident("descriptor") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("parent") {
+                parent = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("components") {
+                components = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("block") {
+                block = true;
+                Ok(())
+            } else {
+                Err(meta.error("unrecognised descriptor attribute (expected name, parent, components or block)"))
+            }
+        })?;
+    }
+    match name {
+        Some(name) => Ok(DescriptorOptions { name, parent, components, block }),
+        None => Err(syn::Error::new_spanned(&ast.ident, "missing #[descriptor(name = \"...\")] attribute")),
+    }
+}
+
+pub(crate) fn generate(ast: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let options = descriptor_options(ast)?;
+    let data_struct = match &ast.data {
+        Data::Struct(data_struct) => data_struct,
+        _ => return Err(syn::Error::new_spanned(ast, "Expected EntityDescriptor auto-trait to be applied to struct")),
+    };
+    let mut parent_ty = None;
+    let mut component_fields = Vec::new();
+    for field in &data_struct.fields {
+        let ident = field.ident.as_ref()
+            .ok_or_else(|| syn::Error::new_spanned(field, "Expected named field"))?;
+        if options.parent.as_ref() == Some(ident) {
+            parent_ty = Some(&field.ty);
+        } else {
+            component_fields.push(ident);
+        }
+    }
+    let own_count = component_fields.len() as u8;
+    let (count_gen, components_gen, components_mut_gen, block_gen) = match (&options.parent, parent_ty) {
+        (Some(parent), Some(parent_ty)) => (
+            quote! { <#parent_ty as SerializedEntityDescriptor>::serialized_components() + #own_count },
+            quote! {
+                #[allow(unused_mut)]
+                let mut c = self.#parent.components();
+                #(c.push(&self.#component_fields);)*
+                c
+            },
+            quote! {
+                #[allow(unused_mut)]
+                let mut c = self.#parent.components_mut();
+                #(c.push(&mut self.#component_fields);)*
+                c
+            },
+            quote! {
+                impl #impl_generics std::convert::AsRef<BlockEntity> for #name #ty_generics #where_clause {
+                    fn as_ref(&self) -> &BlockEntity {
+                        self.#parent.as_ref()
+                    }
+                }
+
+                impl #impl_generics Block for #name #ty_generics #where_clause {}
+            },
+        ),
+        (Some(parent), None) => return Err(syn::Error::new_spanned(parent, format!("parent field `{}` does not exist", parent))),
+        (None, _) => (
+            quote! { #own_count },
+            quote! { vec![#(&self.#component_fields),*] },
+            quote! { vec![#(&mut self.#component_fields),*] },
+            quote! {},
+        ),
+    };
+    let count_gen = match &options.components {
+        Some(count) => quote! { #count },
+        None => count_gen,
+    };
+    let descriptor_name = &options.name;
+    let hash = hashname(&descriptor_name.value());
+    // generic blocks cannot be registered, since the registration needs a concrete type
+    let register_gen = if (options.parent.is_some() || options.block) && ast.generics.params.is_empty() {
+        quote! {
+            ::libfj::techblox::blocks::inventory::submit! {
+                ::libfj::techblox::blocks::BuiltinDescriptor {
+                    name: #descriptor_name,
+                    hash: #hash,
+                    parser: ::libfj::techblox::blocks::parse_block::<#name>,
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Entity descriptor name, which is hashed to identify this entity in game saves
+            pub const HASHNAME: &'static str = #descriptor_name;
+            /// Hash of the entity descriptor name
+            pub const HASH: u32 = #hash;
+        }
+
+        impl #impl_generics SerializedEntityDescriptor for #name #ty_generics #where_clause {
+            fn serialized_components() -> u8 {
+                #count_gen
+            }
+
+            fn components(&self) -> Vec<&dyn SerializedEntityComponent> {
+                #components_gen
+            }
+
+            fn components_mut(&mut self) -> Vec<&mut dyn SerializedEntityComponent> {
+                #components_mut_gen
+            }
+
+            fn hash_name(&self) -> u32 {
+                #hash
+            }
+        }
+
+        #block_gen
+
+        #register_gen
+    })
+}
@@ -1,49 +1,477 @@
-//! Macro implementation for using #[derive(Parsable)]
+//! Macro implementation for using #[derive(Parsable)] and #[derive(EntityDescriptor)]
+//!
+//! # Parsable
+//!
+//! Structs (with named or unnamed fields) and enums are supported.
+//! Every field must itself be `Parsable`, unless changed by a field attribute:
+//!
+//! - `#[parsable(skip)]` does not read or write the field; it is set to `Default::default()` when parsing.
+//! - `#[parsable(bool)]` reads and writes a `bool` field as a `u8` (0 is false, anything else is true).
+//! - `#[parsable(len = "field")]` reads a collection (e.g. `Vec<T>`) of `field` items, where `field` is an earlier integer field.
+//!   For tuple structs and variants, use the field index instead (e.g. `len = "0"`).
+//!   Only the items are written, so keep `field` in sync with the collection length.
+//! - `#[parsable(since = "2022-01-01")]` only reads and writes the field for game versions on or after the date,
+//!   when using `parse_versioned` and `dump_versioned`.
+//!
+//! Enums are stored as a discriminant followed by the variant's fields.
+//! The discriminant type is set with `#[parsable(repr = "u32")]` on the enum (default: `u8`).
+//! A variant's discriminant is `#[parsable(tag = 3)]`, or its explicit discriminant, or one more than the previous variant's.
+//!
+//! # EntityDescriptor
+//!
+//! Implements `SerializedEntityDescriptor` for a struct of entity components,
+//! configured by the `#[descriptor(...)]` struct attribute:
+//!
+//! - `name = "EngineBlockEntityDescriptor"` is the descriptor name, which is hashed to identify the entity (required).
+//!   This is also available as the `HASHNAME` associated constant.
+//! - `parent = block` is the field containing the parent block entity descriptor.
+//!   Its components come before the struct's other fields and `AsRef<BlockEntity>` and `Block` are implemented too.
+//! - `components = 2` overrides the serialized component count, for descriptors which claim more components than they store.
+//! - `block` registers a descriptor without a parent as a block.
+//!
+//! The descriptor name's hash is available as the `HASH` associated constant.
+//! Non-generic descriptors with a `parent` (or `block`) are registered as built-in blocks,
+//! so they are parsed by `libfj::techblox::blocks::lookup_hashname(...)` and `GameSave::parse(...)`.
 extern crate proc_macro;
 
+mod entity_descriptor;
+
 use proc_macro::{TokenStream};
-use syn::{DeriveInput, Data};
-use quote::quote;
+use proc_macro2::{TokenStream as TokenStream2, Literal};
+use syn::{DeriveInput, Data, Fields, Field, Ident, LitStr, LitInt, Expr, Lit, ExprLit, ExprUnary, UnOp};
+use quote::{quote, format_ident};
 
 /// Macro generator
-#[proc_macro_derive(Parsable)]
+#[proc_macro_derive(Parsable, attributes(parsable))]
 pub fn derive_parsable(struc: TokenStream) -> TokenStream {
-    let ast: &DeriveInput = &syn::parse(struc).unwrap();
+    let ast: DeriveInput = match syn::parse(struc) {
+        Ok(ast) => ast,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    match generate(&ast) {
+        Ok(gen) => gen.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Macro generator
+#[proc_macro_derive(EntityDescriptor, attributes(descriptor))]
+pub fn derive_entity_descriptor(struc: TokenStream) -> TokenStream {
+    let ast: DeriveInput = match syn::parse(struc) {
+        Ok(ast) => ast,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    match entity_descriptor::generate(&ast) {
+        Ok(gen) => gen.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn generate(ast: &DeriveInput) -> syn::Result<TokenStream2> {
     let name = &ast.ident;
-    if let Data::Struct(data_struct) = &ast.data {
-        let mut p_fields_gen = vec![];
-        let mut d_fields_gen = vec![];
-        for field in &data_struct.fields {
-            let field_ident = &field.ident.clone().expect("Expected named field");
-            let field_type = &field.ty;
-            p_fields_gen.push(
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let (parse_gen, parse_v_gen, dump_gen, dump_v_gen) = match &ast.data {
+        Data::Struct(data_struct) => {
+            let fields = FieldsInfo::new(&data_struct.fields)?;
+            let accessors: Vec<TokenStream2> = fields.fields.iter()
+                .map(|f| {
+                    let member = &f.member;
+                    quote! { self.#member }
+                })
+                .collect();
+            let ctor = fields.with_bindings(quote! { Self });
+            let parse = fields.parse_statements(false);
+            let parse_v = fields.parse_statements(true);
+            let dump = fields.dump_statements(&accessors, false);
+            let dump_v = fields.dump_statements(&accessors, true);
+            (
+                quote! { #(#parse)* Ok(#ctor) },
+                quote! { #(#parse_v)* Ok(#ctor) },
+                quote! { #(#dump)* },
+                quote! { #(#dump_v)* },
+            )
+        },
+        Data::Enum(data_enum) => {
+            let repr = enum_repr(ast)?;
+            let mut next_tag: i128 = 0;
+            let mut parse_arms = Vec::new();
+            let mut parse_v_arms = Vec::new();
+            let mut dump_arms = Vec::new();
+            let mut dump_v_arms = Vec::new();
+            for variant in &data_enum.variants {
+                let tag = variant_tag(variant, next_tag)?;
+                next_tag = tag + 1;
+                let tag_lit = Literal::i128_unsuffixed(tag);
+                let variant_ident = &variant.ident;
+                let fields = FieldsInfo::new(&variant.fields)?;
+                let ctor = fields.with_bindings(quote! { Self::#variant_ident });
+                let accessors: Vec<TokenStream2> = fields.fields.iter()
+                    .map(|f| {
+                        let binding = &f.binding;
+                        quote! { (*#binding) }
+                    })
+                    .collect();
+                let parse = fields.parse_statements(false);
+                let parse_v = fields.parse_statements(true);
+                let dump = fields.dump_statements(&accessors, false);
+                let dump_v = fields.dump_statements(&accessors, true);
+                parse_arms.push(quote! {
+                    #tag_lit => {
+                        #(#parse)*
+                        Ok(#ctor)
+                    }
+                });
+                parse_v_arms.push(quote! {
+                    #tag_lit => {
+                        #(#parse_v)*
+                        Ok(#ctor)
+                    }
+                });
+                dump_arms.push(quote! {
+                    #ctor => {
+                        write_count += Parsable::dump(&(#tag_lit as #repr), writer)?;
+                        #(#dump)*
+                    }
+                });
+                dump_v_arms.push(quote! {
+                    #ctor => {
+                        write_count += Parsable::dump_versioned(&(#tag_lit as #repr), writer, version)?;
+                        #(#dump_v)*
+                    }
+                });
+            }
+            let unknown_tag = quote! {
+                tag => Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                    format!("Unrecognised discriminant {} for {}", tag, stringify!(#name))))
+            };
+            (
                 quote! {
-                    #field_ident: <#field_type>::parse(data)?
-                }
-            );
-            d_fields_gen.push(
+                    match <#repr as Parsable>::parse(reader)? {
+                        #(#parse_arms)*
+                        #unknown_tag
+                    }
+                },
                 quote! {
-                    self.#field_ident.dump(data)?;
-                }
-            );
+                    match <#repr as Parsable>::parse_versioned(reader, version)? {
+                        #(#parse_v_arms)*
+                        #unknown_tag
+                    }
+                },
+                quote! {
+                    match self {
+                        #(#dump_arms)*
+                    }
+                },
+                quote! {
+                    match self {
+                        #(#dump_v_arms)*
+                    }
+                },
+            )
+        },
+        Data::Union(_) => return Err(syn::Error::new_spanned(ast, "Expected Parsable auto-trait to be applied to struct or enum")),
+    };
+    Ok(quote! {
+        impl #impl_generics Parsable for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn parse(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+                #parse_gen
+            }
+
+            #[allow(unused_mut)]
+            fn dump(&self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+                let mut write_count: usize = 0;
+                #dump_gen
+                Ok(write_count)
+            }
+
+            #[allow(unused_variables)]
+            fn parse_versioned(reader: &mut dyn std::io::Read, version: &chrono::NaiveDate) -> std::io::Result<Self> {
+                #parse_v_gen
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn dump_versioned(&self, writer: &mut dyn std::io::Write, version: &chrono::NaiveDate) -> std::io::Result<usize> {
+                let mut write_count: usize = 0;
+                #dump_v_gen
+                Ok(write_count)
+            }
         }
-        let final_gen = quote! {
-            impl Parsable for #name {
-                fn parse(data: &mut dyn std::io::Read) -> std::io::Result<Self> {
-                    Ok(Self{
-                        #(#p_fields_gen),*
+    })
+}
+
+/// Calendar date as (year, month, day)
+type Date = (i32, u32, u32);
+
+/// Field encoding, as specified by #[parsable(...)] field attributes
+enum Encoding {
+    /// Field is Parsable
+    Plain,
+    /// Field is not serialized
+    Skip,
+    /// Field is a bool stored as a u8
+    Bool,
+    /// Field is a collection with length stored in the field with this binding
+    Len(Ident),
+}
+
+/// Information about a single (named or unnamed) field
+struct FieldInfo {
+    /// How to access the field (name or index)
+    member: syn::Member,
+    /// Local variable name for the field while (de)serializing
+    binding: Ident,
+    /// Field type
+    ty: syn::Type,
+    /// Field encoding
+    encoding: Encoding,
+    /// Minimum game version which contains this field
+    since: Option<Date>,
+}
+
+/// Information about all fields of a struct or enum variant
+struct FieldsInfo {
+    fields: Vec<FieldInfo>,
+    named: bool,
+    unit: bool,
+}
+
+impl FieldsInfo {
+    fn new(fields: &Fields) -> syn::Result<Self> {
+        let mut infos: Vec<FieldInfo> = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            let (member, binding) = match &field.ident {
+                Some(ident) => (syn::Member::Named(ident.clone()), format_ident!("field_{}", ident)),
+                None => (syn::Member::Unnamed(syn::Index::from(i)), format_ident!("field_{}", i)),
+            };
+            let (encoding, since) = field_attributes(field, &infos)?;
+            infos.push(FieldInfo {
+                member,
+                binding,
+                ty: field.ty.clone(),
+                encoding,
+                since,
+            });
+        }
+        Ok(Self {
+            fields: infos,
+            named: matches!(fields, Fields::Named(_)),
+            unit: matches!(fields, Fields::Unit),
+        })
+    }
+
+    /// Struct or variant with field bindings.
+    ///
+    /// This constructs the value from parsed bindings, or destructures it into bindings (references) when used as a pattern.
+    fn with_bindings(&self, path: TokenStream2) -> TokenStream2 {
+        let bindings = self.fields.iter().map(|f| &f.binding);
+        if self.unit {
+            path
+        } else if self.named {
+            let members = self.fields.iter().map(|f| &f.member);
+            quote! { #path { #(#members: #bindings),* } }
+        } else {
+            quote! { #path ( #(#bindings),* ) }
+        }
+    }
+
+    fn parse_statements(&self, versioned: bool) -> Vec<TokenStream2> {
+        let mut statements = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let binding = &field.binding;
+            let ty = &field.ty;
+            let value = match (&field.encoding, versioned) {
+                (Encoding::Plain, false) => quote! { <#ty as Parsable>::parse(reader)? },
+                (Encoding::Plain, true) => quote! { <#ty as Parsable>::parse_versioned(reader, version)? },
+                (Encoding::Skip, _) => quote! { <#ty as Default>::default() },
+                (Encoding::Bool, false) => quote! { <u8 as Parsable>::parse(reader)? != 0 },
+                (Encoding::Bool, true) => quote! { <u8 as Parsable>::parse_versioned(reader, version)? != 0 },
+                (Encoding::Len(len_binding), false) => quote! {
+                    (0..(#len_binding as usize))
+                        .map(|_| Parsable::parse(reader))
+                        .collect::<std::io::Result<#ty>>()?
+                },
+                (Encoding::Len(len_binding), true) => quote! {
+                    (0..(#len_binding as usize))
+                        .map(|_| Parsable::parse_versioned(reader, version))
+                        .collect::<std::io::Result<#ty>>()?
+                },
+            };
+            statements.push(match (versioned, field.since) {
+                (true, Some(since)) => {
+                    let since = date_tokens(since);
+                    quote! {
+                        let #binding: #ty = if *version >= #since {
+                            #value
+                        } else {
+                            <#ty as Default>::default()
+                        };
+                    }
+                },
+                _ => quote! { let #binding: #ty = #value; },
+            });
+        }
+        statements
+    }
+
+    fn dump_statements(&self, accessors: &[TokenStream2], versioned: bool) -> Vec<TokenStream2> {
+        let mut statements = Vec::with_capacity(self.fields.len());
+        for (field, access) in self.fields.iter().zip(accessors) {
+            let dump_one = |item: TokenStream2| if versioned {
+                quote! { Parsable::dump_versioned(#item, writer, version)? }
+            } else {
+                quote! { Parsable::dump(#item, writer)? }
+            };
+            let statement = match &field.encoding {
+                Encoding::Plain => {
+                    let dump = dump_one(quote! { &#access });
+                    quote! { write_count += #dump; }
+                },
+                Encoding::Skip => continue,
+                Encoding::Bool => {
+                    let dump = dump_one(quote! { &u8::from(#access) });
+                    quote! { write_count += #dump; }
+                },
+                Encoding::Len(_) => {
+                    let dump = dump_one(quote! { item });
+                    quote! {
+                        for item in #access.iter() {
+                            write_count += #dump;
+                        }
+                    }
+                },
+            };
+            statements.push(match (versioned, field.since) {
+                (true, Some(since)) => {
+                    let since = date_tokens(since);
+                    quote! {
+                        if *version >= #since {
+                            #statement
+                        }
+                    }
+                },
+                _ => statement,
+            });
+        }
+        statements
+    }
+}
+
+fn field_attributes(field: &Field, previous: &[FieldInfo]) -> syn::Result<(Encoding, Option<Date>)> {
+    let mut encoding = Encoding::Plain;
+    let mut since = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("parsable") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("since") {
+                let value: LitStr = meta.value()?.parse()?;
+                since = Some(parse_date(&value)?);
+                return Ok(());
+            }
+            if !matches!(encoding, Encoding::Plain) {
+                return Err(meta.error("only one of skip, bool and len can be applied to a field"));
+            }
+            if meta.path.is_ident("skip") {
+                encoding = Encoding::Skip;
+                Ok(())
+            } else if meta.path.is_ident("bool") {
+                encoding = Encoding::Bool;
+                Ok(())
+            } else if meta.path.is_ident("len") {
+                let value: LitStr = meta.value()?.parse()?;
+                let len_field = value.value();
+                let len_info = previous.iter()
+                    .find(|f| match &f.member {
+                        syn::Member::Named(ident) => *ident == len_field,
+                        syn::Member::Unnamed(index) => index.index.to_string() == len_field,
                     })
+                    .ok_or_else(|| syn::Error::new_spanned(&value, format!("len field `{}` must be declared before this field", len_field)))?;
+                if !matches!(len_info.encoding, Encoding::Plain) {
+                    return Err(syn::Error::new_spanned(&value, format!("len field `{}` must be a plain integer field", len_field)));
                 }
+                encoding = Encoding::Len(len_info.binding.clone());
+                Ok(())
+            } else {
+                Err(meta.error("unrecognised parsable attribute (expected skip, bool, len or since)"))
+            }
+        })?;
+    }
+    if since.is_some() && matches!(encoding, Encoding::Skip) {
+        return Err(syn::Error::new_spanned(field, "since cannot be applied to a skipped field"));
+    }
+    Ok((encoding, since))
+}
 
-                fn dump(&self, data: &mut dyn std::io::Write) -> std::io::Result<usize> {
-                    let mut write_count: usize = 0;
-                    #(write_count += #d_fields_gen;)*
-                    Ok(write_count)
-                }
+fn enum_repr(ast: &DeriveInput) -> syn::Result<syn::Type> {
+    let mut repr: syn::Type = syn::parse_quote! { u8 };
+    for attr in &ast.attrs {
+        if !attr.path().is_ident("parsable") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("repr") {
+                let value: LitStr = meta.value()?.parse()?;
+                repr = value.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unrecognised parsable attribute (expected repr)"))
             }
-        };
-        return final_gen.into();
-    } else {
-        panic!("Expected Parsable auto-trait to be applied to struct");
+        })?;
     }
+    Ok(repr)
+}
+
+fn variant_tag(variant: &syn::Variant, default: i128) -> syn::Result<i128> {
+    let mut tag = None;
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("parsable") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                let value: LitInt = meta.value()?.parse()?;
+                tag = Some(value.base10_parse::<i128>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unrecognised parsable attribute (expected tag)"))
+            }
+        })?;
+    }
+    if let Some(tag) = tag {
+        return Ok(tag);
+    }
+    match &variant.discriminant {
+        Some((_, expr)) => discriminant_value(expr),
+        None => Ok(default),
+    }
+}
+
+fn discriminant_value(expr: &Expr) -> syn::Result<i128> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int.base10_parse::<i128>(),
+        Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr, .. }) => Ok(-discriminant_value(expr)?),
+        _ => Err(syn::Error::new_spanned(expr, "discriminant must be an integer literal (or use #[parsable(tag = ...)])")),
+    }
+}
+
+fn parse_date(value: &LitStr) -> syn::Result<Date> {
+    let text = value.value();
+    let parts: Vec<&str> = text.split('-').collect();
+    if parts.len() == 3 {
+        if let (Ok(year), Ok(month), Ok(day)) = (parts[0].parse::<i32>(), parts[1].parse::<u32>(), parts[2].parse::<u32>()) {
+            if (1..=12).contains(&month) && (1..=31).contains(&day) {
+                return Ok((year, month, day));
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(value, "expected date in YYYY-MM-DD format"))
+}
+
+fn date_tokens((year, month, day): Date) -> TokenStream2 {
+    let year = Literal::i32_unsuffixed(year);
+    let month = Literal::u32_unsuffixed(month);
+    let day = Literal::u32_unsuffixed(day);
+    quote! { chrono::NaiveDate::from_ymd_opt(#year, #month, #day).unwrap() }
 }
@@ -0,0 +1,378 @@
+//! Response caching for factory lookups.
+//!
+//! A `ResponseCache` keeps recently used responses in memory, and optionally stores them on disk
+//! so they survive restarts. Use `robocraft::FactoryAPI::with_cache(...)` or `robocraft2::FactoryAPI::with_cache(...)`
+//! to cache the responses of `get(...)`.
+//!
+//! The async API clients use `get_async(...)` and `insert_async(...)`, which access the disk on tokio's blocking thread pool.
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+/// Hit and miss counters of a `ResponseCache`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from memory
+    pub memory_hits: u64,
+    /// Lookups answered from disk
+    pub disk_hits: u64,
+    /// Lookups which were not cached (or whose cached response had expired)
+    pub misses: u64,
+    /// Responses removed from memory to stay within the capacity
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Lookups answered from memory or disk
+    pub fn hits(&self) -> u64 {
+        self.memory_hits + self.disk_hits
+    }
+
+    /// Fraction of lookups which were answered from the cache, from 0.0 to 1.0
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits() + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits() as f64 / total as f64
+        }
+    }
+}
+
+struct MemoryEntry<V> {
+    value: V,
+    fetched: SystemTime,
+    last_used: u64,
+}
+
+/// On-disk format of a cached response
+#[derive(Serialize, Deserialize)]
+struct DiskEntry<V> {
+    /// Seconds since the unix epoch
+    fetched: u64,
+    value: V,
+}
+
+/// `DiskEntry` without the value, for checking expiry
+#[derive(Deserialize)]
+struct DiskHeader {
+    fetched: u64,
+}
+
+struct CacheState<V> {
+    entries: HashMap<String, MemoryEntry<V>>,
+    /// Keys by last use, least recently used first
+    recent: BTreeMap<u64, String>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+/// Least recently used response cache with a time-to-live, and an optional on-disk store.
+///
+/// Clones share the same cache, so a clone can be kept to read the statistics of a cache given to an API.
+/// Disk errors are ignored, since the response can always be retrieved again.
+pub struct ResponseCache<V> {
+    capacity: usize,
+    ttl: Duration,
+    directory: Option<PathBuf>,
+    state: Arc<Mutex<CacheState<V>>>,
+}
+
+impl<V> Clone for ResponseCache<V> {
+    fn clone(&self) -> Self {
+        Self {
+            capacity: self.capacity,
+            ttl: self.ttl,
+            directory: self.directory.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<V: Clone + Serialize + DeserializeOwned> ResponseCache<V> {
+    /// Create a new in-memory cache, which holds up to `capacity` responses for `ttl` each.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            directory: None,
+            state: Arc::new(Mutex::new(CacheState {
+                entries: HashMap::new(),
+                recent: BTreeMap::new(),
+                tick: 0,
+                stats: CacheStats::default(),
+            })),
+        }
+    }
+
+    /// Also store responses as JSON files in `directory`, which is created when the first response is stored.
+    ///
+    /// Responses on disk are not limited by the capacity, but they still expire.
+    /// Expired files are deleted when they are looked up, or by `remove_expired()`.
+    /// File names are the hex encoded key, so every key has its own file.
+    pub fn with_directory<P: AsRef<Path>>(mut self, directory: P) -> Self {
+        self.directory = Some(directory.as_ref().to_path_buf());
+        self
+    }
+
+    /// Maximum amount of responses held in memory
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Time a response stays valid after it is retrieved
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Amount of responses held in memory (including expired responses which have not been looked up yet)
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Returns true when no responses are held in memory
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Current hit and miss counters
+    pub fn stats(&self) -> CacheStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// Reset the hit and miss counters to 0
+    pub fn reset_stats(&self) {
+        self.state.lock().unwrap().stats = CacheStats::default();
+    }
+
+    /// Look up an unexpired response, first in memory and then on disk.
+    pub fn get(&self, key: &str) -> Option<V> {
+        if let Some(value) = self.get_memory(key) {
+            return Some(value);
+        }
+        let entry = self.read_disk(key);
+        self.finish_get(key, entry)
+    }
+
+    /// Store a response which was just retrieved.
+    pub fn insert(&self, key: &str, value: V) {
+        let fetched = SystemTime::now();
+        self.write_disk(key, &value, fetched);
+        let mut state = self.state.lock().unwrap();
+        self.insert_memory(&mut state, key, value, fetched);
+    }
+
+    /// Remove expired responses from memory and from disk, returning the amount of files which were deleted.
+    pub fn remove_expired(&self) -> usize {
+        {
+            let mut state = self.state.lock().unwrap();
+            let expired: Vec<String> = state.entries.iter()
+                .filter(|(_, entry)| self.is_expired(entry.fetched))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in expired {
+                state.remove(&key);
+            }
+        }
+        let files = match self.directory.as_ref().and_then(|dir| std::fs::read_dir(dir).ok()) {
+            Some(files) => files,
+            None => return 0,
+        };
+        let mut removed = 0;
+        for path in files.filter_map(|file| file.ok()).map(|file| file.path()) {
+            let is_cache_file = path.extension().map(|ext| ext == "json").unwrap_or(false)
+                && path.file_stem().and_then(|stem| stem.to_str()).and_then(decode_key).is_some();
+            if is_cache_file && self.is_expired_file(&path) && std::fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Look up an unexpired response in memory, without counting a miss
+    fn get_memory(&self, key: &str) -> Option<V> {
+        let mut state = self.state.lock().unwrap();
+        let tick = state.next_tick();
+        if let Some(entry) = state.entries.get_mut(key) {
+            if !self.is_expired(entry.fetched) {
+                let old_tick = std::mem::replace(&mut entry.last_used, tick);
+                let value = entry.value.clone();
+                state.recent.remove(&old_tick);
+                state.recent.insert(tick, key.to_owned());
+                state.stats.memory_hits += 1;
+                return Some(value);
+            }
+            state.remove(key);
+        }
+        None
+    }
+
+    /// Count the result of a disk lookup, and keep the response in memory
+    fn finish_get(&self, key: &str, entry: Option<DiskEntry<V>>) -> Option<V> {
+        let mut state = self.state.lock().unwrap();
+        match entry {
+            Some(entry) => {
+                state.stats.disk_hits += 1;
+                // do not replace a response which was inserted while the disk was being read
+                if !state.entries.contains_key(key) {
+                    let fetched = UNIX_EPOCH + Duration::from_secs(entry.fetched);
+                    self.insert_memory(&mut state, key, entry.value.clone(), fetched);
+                }
+                Some(entry.value)
+            },
+            None => {
+                state.stats.misses += 1;
+                None
+            },
+        }
+    }
+
+    /// Remove a response from memory and from disk.
+    pub fn remove(&self, key: &str) {
+        self.state.lock().unwrap().remove(key);
+        if let Some(path) = self.path(key) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Remove all responses from memory.
+    /// Responses on disk are kept.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.recent.clear();
+    }
+
+    fn is_expired(&self, fetched: SystemTime) -> bool {
+        fetched.elapsed().map(|age| age >= self.ttl).unwrap_or(false)
+    }
+
+    fn insert_memory(&self, state: &mut CacheState<V>, key: &str, value: V, fetched: SystemTime) {
+        if self.capacity == 0 {
+            return;
+        }
+        state.remove(key);
+        while state.entries.len() >= self.capacity {
+            let oldest = match state.recent.keys().next() {
+                Some(tick) => *tick,
+                None => break,
+            };
+            let oldest_key = state.recent.remove(&oldest).unwrap();
+            state.entries.remove(&oldest_key);
+            state.stats.evictions += 1;
+        }
+        let tick = state.next_tick();
+        state.recent.insert(tick, key.to_owned());
+        state.entries.insert(key.to_owned(), MemoryEntry {
+            value,
+            fetched,
+            last_used: tick,
+        });
+    }
+
+    /// Path of the file for a key, named after the hex encoded key
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.directory.as_ref().map(|dir| dir.join(encode_key(key) + ".json"))
+    }
+
+    /// Read an unexpired response from disk, deleting the file when the response has expired
+    fn read_disk(&self, key: &str) -> Option<DiskEntry<V>> {
+        let path = self.path(key)?;
+        let file = std::fs::File::open(&path).ok()?;
+        let entry: DiskEntry<V> = serde_json::from_reader(std::io::BufReader::new(file)).ok()?;
+        if self.is_expired(UNIX_EPOCH + Duration::from_secs(entry.fetched)) {
+            let _ = std::fs::remove_file(path);
+            return None;
+        }
+        Some(entry)
+    }
+
+    fn is_expired_file(&self, path: &Path) -> bool {
+        let header = std::fs::File::open(path).ok()
+            .and_then(|file| serde_json::from_reader::<_, DiskHeader>(std::io::BufReader::new(file)).ok());
+        match header {
+            Some(header) => self.is_expired(UNIX_EPOCH + Duration::from_secs(header.fetched)),
+            None => false,
+        }
+    }
+
+    fn write_disk(&self, key: &str, value: &V, fetched: SystemTime) {
+        if let (Some(dir), Some(path)) = (&self.directory, self.path(key)) {
+            let entry = DiskEntry {
+                fetched: fetched.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+                value,
+            };
+            if std::fs::create_dir_all(dir).is_ok() {
+                if let Ok(data) = serde_json::to_vec(&entry) {
+                    let _ = std::fs::write(path, data);
+                }
+            }
+        }
+    }
+}
+
+impl<V: Clone + Serialize + DeserializeOwned + Send + 'static> ResponseCache<V> {
+    /// Look up an unexpired response like `get(...)`, reading from disk without blocking the async runtime.
+    pub async fn get_async(&self, key: &str) -> Option<V> {
+        if let Some(value) = self.get_memory(key) {
+            return Some(value);
+        }
+        let entry = if self.directory.is_some() {
+            let cache = self.clone();
+            let owned_key = key.to_owned();
+            tokio::task::spawn_blocking(move || cache.read_disk(&owned_key)).await.ok().flatten()
+        } else {
+            None
+        };
+        self.finish_get(key, entry)
+    }
+
+    /// Store a response like `insert(...)`, writing to disk without blocking the async runtime.
+    pub async fn insert_async(&self, key: &str, value: V) {
+        let fetched = SystemTime::now();
+        if self.directory.is_some() {
+            let cache = self.clone();
+            let owned_key = key.to_owned();
+            let owned_value = value.clone();
+            let _ = tokio::task::spawn_blocking(move || cache.write_disk(&owned_key, &owned_value, fetched)).await;
+        }
+        let mut state = self.state.lock().unwrap();
+        self.insert_memory(&mut state, key, value, fetched);
+    }
+}
+
+/// Encode a key as lowercase hex, which is safe in file names (even on case-insensitive file systems)
+fn encode_key(key: &str) -> String {
+    key.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode a key encoded by `encode_key(...)`, or None when `name` is not a valid encoded key
+fn decode_key(name: &str) -> Option<String> {
+    if !name.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    let bytes = name.as_bytes().chunks(2)
+        .map(|pair| match pair {
+            [_, _] => u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+impl<V> CacheState<V> {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recent.remove(&entry.last_used);
+        }
+    }
+}
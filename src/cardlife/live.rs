@@ -2,6 +2,7 @@ use reqwest::{Client, Error};
 use url::{Url};
 
 use crate::cardlife::{AuthenticationInfo, AuthenticationPayload, LobbyInfo, LobbyPayload};
+use crate::retry::RequestPolicy;
 
 pub const AUTHENTICATION_DOMAIN: &str = "https://live-auth.cardlifegame.com/";
 pub const LOBBY_DOMAIN: &str = "https://live-lobby.cardlifegame.com/";
@@ -10,6 +11,7 @@ pub const LOBBY_DOMAIN: &str = "https://live-lobby.cardlifegame.com/";
 pub struct LiveAPI {
     client: Client,
     auth: Option<AuthenticationInfo>,
+    policy: RequestPolicy,
 }
 
 impl LiveAPI {
@@ -18,9 +20,18 @@ impl LiveAPI {
         LiveAPI {
             client: Client::new(),
             auth: None,
+            policy: RequestPolicy::default(),
         }
     }
 
+    /// Replace the retry and rate limit policy used for requests.
+    ///
+    /// Authentication is never retried, but retrieving lobbies is.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> LiveAPI {
+        self.policy = policy;
+        self
+    }
+
     /// Create a new instance and login using email
     pub async fn login_email(email: &str, password: &str) -> Result<LiveAPI, Error> {
         let mut instance = LiveAPI::new();
@@ -43,9 +54,9 @@ impl LiveAPI {
             email_address: email.to_string(),
             password: password.to_string()
         };
-        let result = self.client.post(url.clone())
-            .json(&payload)
-            .send().await;
+        let request = self.client.post(url.clone())
+            .json(&payload);
+        let result = self.policy.send(request, false).await;
         if let Ok(response) = result {
             //println!("Resp: {}", response.text().await.unwrap());
             let res = response.json::<AuthenticationInfo>().await;
@@ -75,7 +86,7 @@ impl LiveAPI {
             public_id = "".to_string();
         }
         let payload = LobbyPayload{public_id};
-        let result = self.client.post(url).json(&payload).send().await;
+        let result = self.policy.send(self.client.post(url).json(&payload), true).await;
         if let Ok(response) = result {
             return response.json::<LobbyInfo>().await;
         }
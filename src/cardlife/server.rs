@@ -1,11 +1,13 @@
 use reqwest::{Client, IntoUrl, Error};
 use url::{Origin, Url};
 use crate::cardlife::{GameInfo, StatusInfo};
+use crate::retry::RequestPolicy;
 
 /// CLre_server web server API implemenation
 pub struct CLreServer {
     client: Client,
     addr: Url,
+    policy: RequestPolicy,
 }
 
 impl CLreServer {
@@ -19,6 +21,7 @@ impl CLreServer {
                         CLreServer {
                             client: Client::new(),
                             addr,
+                            policy: RequestPolicy::default(),
                         }
                     );
                 }
@@ -27,10 +30,15 @@ impl CLreServer {
         Err(())
     }
 
+    /// Replace the retry and rate limit policy used for requests.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> CLreServer {
+        self.policy = policy;
+        self
+    }
+
     /// Retrieve the current game info
     pub async fn game_info(&self) -> Result<GameInfo, Error> {
-        let response = self.client.get(self.addr.join("/c/game.json").unwrap())
-            .send().await;
+        let response = self.policy.send(self.client.get(self.addr.join("/c/game.json").unwrap()), true).await;
         if let Ok(resp) = response {
             return resp.json::<GameInfo>().await
         }
@@ -39,8 +47,7 @@ impl CLreServer {
 
     /// Retrieve CLre_server information
     pub async fn status_info(&self) -> Result<StatusInfo, Error> {
-        let response = self.client.get(self.addr.join("/status.json").unwrap())
-            .send().await;
+        let response = self.policy.send(self.client.get(self.addr.join("/status.json").unwrap()), true).await;
         if let Ok(resp) = response {
             return resp.json::<StatusInfo>().await
         }
@@ -0,0 +1,96 @@
+use ureq::{Agent, Error};
+use url::{Url, ParseError};
+
+use crate::cardlife::{AuthenticationInfo, AuthenticationPayload, LobbyInfo, LobbyPayload, AUTHENTICATION_DOMAIN, LOBBY_DOMAIN};
+use crate::retry::RequestPolicy;
+
+/// Simpler Cardlife live information API.
+/// Refer to libfj::cardlife::LiveAPI for in-depth documentation.
+/// The only API difference is that this API is blocking (i.e. no async).
+pub struct LiveAPI {
+    client: Agent,
+    auth: Option<AuthenticationInfo>,
+    authentication_domain: Url,
+    lobby_domain: Url,
+    policy: RequestPolicy,
+}
+
+impl LiveAPI {
+    /// Create a new instance
+    pub fn new() -> LiveAPI {
+        LiveAPI {
+            client: Agent::new(),
+            auth: None,
+            authentication_domain: Url::parse(AUTHENTICATION_DOMAIN).unwrap(),
+            lobby_domain: Url::parse(LOBBY_DOMAIN).unwrap(),
+            policy: RequestPolicy::default(),
+        }
+    }
+
+    /// Create a new instance which uses other servers instead of `AUTHENTICATION_DOMAIN` and `LOBBY_DOMAIN`.
+    pub fn with_domains(authentication_domain: &str, lobby_domain: &str) -> Result<LiveAPI, ParseError> {
+        Ok(LiveAPI {
+            client: Agent::new(),
+            auth: None,
+            authentication_domain: Url::parse(authentication_domain)?,
+            lobby_domain: Url::parse(lobby_domain)?,
+            policy: RequestPolicy::default(),
+        })
+    }
+
+    /// Replace the retry and rate limit policy used for requests.
+    ///
+    /// Authentication is never retried, but retrieving lobbies is.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> LiveAPI {
+        self.policy = policy;
+        self
+    }
+
+    /// Create a new instance and login using email
+    pub fn login_email(email: &str, password: &str) -> Result<LiveAPI, Box<Error>> {
+        let mut instance = LiveAPI::new();
+        instance.authenticate_email(email, password)?;
+        Ok(instance)
+    }
+
+    /// Login using email and password
+    pub fn authenticate_email(&mut self, email: &str, password: &str) -> Result<AuthenticationInfo, Box<Error>> {
+        let url = self.authentication_domain
+            .join("api/auth/authenticate")
+            .unwrap();
+        let payload = AuthenticationPayload {
+            email_address: email.to_string(),
+            password: password.to_string()
+        };
+        let request = self.client.post(url.as_str())
+            .set("Content-Type", "application/json");
+        let auth = self.policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), false)?
+            .into_json::<AuthenticationInfo>()
+            .map_err(Error::from)?;
+        self.auth = Some(auth.clone());
+        Ok(auth)
+    }
+
+    /// Retrieve lobby information for all active Cardlife servers
+    pub fn lobbies(&self) -> Result<LobbyInfo, Box<Error>> {
+        let url = self.lobby_domain
+            .join("/api/client/games")
+            .unwrap();
+        let public_id = self.auth.as_ref()
+            .map(|auth| auth.public_id.clone())
+            .unwrap_or_default();
+        let payload = LobbyPayload{public_id};
+        let request = self.client.post(url.as_str())
+            .set("Content-Type", "application/json");
+        let lobbies = self.policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), true)?
+            .into_json::<LobbyInfo>()
+            .map_err(Error::from)?;
+        Ok(lobbies)
+    }
+}
+
+impl Default for LiveAPI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
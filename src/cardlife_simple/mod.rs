@@ -1,3 +1,8 @@
 //! Simple, blocking Cardlife API (WIP).
-//! Nothing is here yet, sorry!
-// TODO
+//! Refer to libfj::cardlife for in-depth documentation.
+
+mod server;
+pub use self::server::{CLreServer};
+
+mod live;
+pub use self::live::{LiveAPI};
@@ -0,0 +1,55 @@
+use ureq::{Agent, Error};
+use url::{Origin, Url, ParseError};
+use crate::cardlife::{GameInfo, StatusInfo};
+use crate::retry::RequestPolicy;
+
+/// Simpler CLre_server web server API implemenation.
+/// Refer to libfj::cardlife::CLreServer for in-depth documentation.
+/// The only API difference is that this API is blocking (i.e. no async).
+pub struct CLreServer {
+    client: Agent,
+    addr: Url,
+    policy: RequestPolicy,
+}
+
+impl CLreServer {
+    /// Create a new instance
+    pub fn new(url: &str) -> Result<CLreServer, ParseError> {
+        let uri = Url::parse(url)?;
+        if let Origin::Tuple(scheme, host, port) = uri.origin() {
+            let addr = Url::parse(&format!("{}://{}:{}", scheme, host, port))?;
+            return Ok(
+                CLreServer {
+                    client: Agent::new(),
+                    addr,
+                    policy: RequestPolicy::default(),
+                }
+            );
+        }
+        Err(ParseError::EmptyHost)
+    }
+
+    /// Replace the retry and rate limit policy used for requests.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> CLreServer {
+        self.policy = policy;
+        self
+    }
+
+    /// Retrieve the current game info
+    pub fn game_info(&self) -> Result<GameInfo, Box<Error>> {
+        let request = self.client.get(self.addr.join("/c/game.json").unwrap().as_str());
+        let info = self.policy.send_blocking(&request, None, true)?
+            .into_json::<GameInfo>()
+            .map_err(Error::from)?;
+        Ok(info)
+    }
+
+    /// Retrieve CLre_server information
+    pub fn status_info(&self) -> Result<StatusInfo, Box<Error>> {
+        let request = self.client.get(self.addr.join("/status.json").unwrap().as_str());
+        let info = self.policy.send_blocking(&request, None, true)?
+            .into_json::<StatusInfo>()
+            .map_err(Error::from)?;
+        Ok(info)
+    }
+}
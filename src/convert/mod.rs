@@ -2,7 +2,41 @@
 //!
 //! This will contain ways to convert data from one game to another as well as into standard formats.
 
+/// Scale from block shapes (which span -1 to 1) to Robocraft cube units
+#[cfg(feature = "robocraft")]
+const SCALE: f32 = 0.5;
+
 #[cfg(feature = "robocraft")]
 mod robocraft_3d;
 #[cfg(feature = "robocraft")]
-pub use robocraft_3d::{cubes_to_model, cubes_to_model_with_lut, cube_rotation_to_quat};
+pub use robocraft_3d::{cubes_to_model, cubes_to_model_with_lut, cubes_to_model_with_library, cube_rotation_to_quat, default_model_lut};
+#[cfg(feature = "robocraft")]
+mod robocraft_shapes;
+#[cfg(feature = "robocraft")]
+pub use robocraft_shapes::{CubeShape, ShapeLibrary};
+#[cfg(feature = "robocraft")]
+mod robocraft_palette;
+#[cfg(feature = "robocraft")]
+pub use robocraft_palette::{PROVISIONAL_ROBOCRAFT_PALETTE, PROVISIONAL_TECHBLOX_PAINTS, ROBOCRAFT_MATERIAL_LIB, FALLBACK_COLOUR, colour_to_rgb, try_colour_to_rgb, colour_material_name, colour_material, colours_to_mtl, techblox_palette};
+#[cfg(feature = "robocraft")]
+mod robocraft_optimise;
+#[cfg(feature = "robocraft")]
+pub use robocraft_optimise::{cubes_to_model_optimised, ModelOptions};
+#[cfg(feature = "robocraft")]
+mod robocraft_gltf;
+#[cfg(feature = "robocraft")]
+pub use robocraft_gltf::cubes_to_gltf;
+#[cfg(feature = "robocraft")]
+mod robocraft_vox;
+#[cfg(feature = "robocraft")]
+pub use robocraft_vox::{cubes_to_vox, vox_to_cubes, VoxImportOptions};
+#[cfg(feature = "robocraft")]
+mod robocraft_render;
+#[cfg(feature = "robocraft")]
+pub use robocraft_render::{cubes_to_png, RenderOptions};
+#[cfg(all(feature = "robocraft", feature = "robocraft2", feature = "techblox"))]
+pub use robocraft_render::vehicle_to_png;
+#[cfg(all(feature = "robocraft", feature = "techblox"))]
+mod robocraft_techblox;
+#[cfg(all(feature = "robocraft", feature = "techblox"))]
+pub use robocraft_techblox::{robocraft_to_techblox, robocraft_to_techblox_with_template, TechbloxConvertOptions, TECHBLOX_CUBE_DBID};
@@ -1,56 +1,48 @@
-use genmesh::{generators::Cube, Quad, MapToVertices, Vertices, Vertex};
+use genmesh::{Polygon, Triangle, Quad, MapToVertices, Vertices, Vertex};
 use obj;
-use cgmath::{Quaternion, Euler, Deg, Vector3};
+use cgmath::{Quaternion, Matrix3, Vector3, InnerSpace};
 use crate::robocraft;
-
-const SCALE: f32 = 0.5;
-
-const ROTATIONS: [Euler<Deg<f32>>; 24] = [
-    Euler{x: Deg(0.0), y: Deg(0.0), z: Deg(0.0)}, // 0
-    Euler{x: Deg(0.0), y: Deg(0.0), z: Deg(90.0)},
-    Euler{x: Deg(0.0), y: Deg(0.0), z: Deg(180.0)}, // 2
-    Euler{x: Deg(0.0), y: Deg(0.0), z: Deg(-90.0)},
-    Euler{x: Deg(0.0), y: Deg(90.0), z: Deg(0.0)}, // 4
-    Euler{x: Deg(0.0), y: Deg(90.0), z: Deg(90.0)},
-    Euler{x: Deg(-90.0), y: Deg(-90.0), z: Deg(0.0)}, // 6
-    Euler{x: Deg(0.0), y: Deg(90.0), z: Deg(-90.0)},
-    Euler{x: Deg(0.0), y: Deg(-90.0), z: Deg(90.0)}, // 8
-    Euler{x: Deg(0.0), y: Deg(-90.0), z: Deg(-90.0)},
-    Euler{x: Deg(90.0), y: Deg(-90.0), z: Deg(0.0)}, // 10
-    Euler{x: Deg(90.0), y: Deg(90.0), z: Deg(0.0)},
-    Euler{x: Deg(-90.0), y: Deg(90.0), z: Deg(0.0)}, // 12
-    Euler{x: Deg(0.0), y: Deg(90.0), z: Deg(180.0)},
-    Euler{x: Deg(0.0), y: Deg(180.0), z: Deg(0.0)}, // 14
-    Euler{x: Deg(0.0), y: Deg(180.0), z: Deg(90.0)},
-    Euler{x: Deg(0.0), y: Deg(180.0), z: Deg(0.0)}, // 16
-    Euler{x: Deg(0.0), y: Deg(180.0), z: Deg(180.0)},
-    Euler{x: Deg(0.0), y: Deg(-90.0), z: Deg(-90.0)}, // 18
-    Euler{x: Deg(0.0), y: Deg(-90.0), z: Deg(0.0)},
-    Euler{x: Deg(90.0), y: Deg(0.0), z: Deg(180.0)}, // 20
-    Euler{x: Deg(90.0), y: Deg(180.0), z: Deg(0.0)},
-    Euler{x: Deg(-90.0), y: Deg(0.0), z: Deg(0.0)}, // 22
-    Euler{x: Deg(-90.0), y: Deg(180.0), z: Deg(0.0)}, // 23
-];
+use crate::convert::{CubeShape, ShapeLibrary, colours_to_mtl, ROBOCRAFT_MATERIAL_LIB};
+use crate::convert::robocraft_palette::find_material;
+use crate::convert::SCALE;
 
 /// Convert a Robocraft robot's orientation enum into a physical rotation
+///
+/// Orientations 8, 10, 11, 12, 16, 18 and 23 have not been checked against in-game robots,
+/// so cubes with those orientations may be rotated incorrectly.
 pub fn cube_rotation_to_quat(orientation: u8) -> Quaternion<f32> {
-    ROTATIONS[orientation as usize].into()
+    let m = robocraft::ORIENTATION_MATRICES[orientation as usize];
+    // cgmath matrices are column-major
+    Matrix3::new(
+        m[0][0] as f32, m[1][0] as f32, m[2][0] as f32,
+        m[0][1] as f32, m[1][1] as f32, m[2][1] as f32,
+        m[0][2] as f32, m[1][2] as f32, m[2][2] as f32,
+    ).into()
 }
 
 /// Convert a Robocraft robot to a 3D model in Wavefront OBJ format.
+///
+/// Every cube is assigned the material for its colour, from the material library `robocraft.mtl`.
+/// libfj does not include the game's cube id to shape assignments, so every cube is exported as a full cube;
+/// use `cubes_to_model_with_library(...)` to export slopes, corners and other shapes.
 pub fn cubes_to_model(robot: robocraft::Cubes) -> obj::Obj {
     cubes_to_model_with_lut(robot, default_model_lut)
 }
 
+/// Convert a Robocraft robot to a 3D model in Wavefront OBJ format using the provided shape library.
+pub fn cubes_to_model_with_library(robot: robocraft::Cubes, library: &ShapeLibrary) -> obj::Obj {
+    cubes_to_model_with_lut(robot, |id| library.polygons(id))
+}
+
 /// Convert a Robocraft robot to a 3D model in Wavefront OBJ format using the provided lookup table function.
-pub fn cubes_to_model_with_lut<F: FnMut(u32) -> Vec<Quad<Vertex>>>(robot: robocraft::Cubes, mut lut: F) -> obj::Obj {
+pub fn cubes_to_model_with_lut<F: FnMut(u32) -> Vec<Polygon<Vertex>>>(robot: robocraft::Cubes, mut lut: F) -> obj::Obj {
     let mut positions = Vec::<[f32; 3]>::new(); // vertex positions
     let mut normals = Vec::<[f32; 3]>::new(); // vertex normals
+    let mut textures = Vec::<[f32; 2]>::new(); // vertex texture coordinates
     let mut objects = Vec::<obj::Object>::new(); // blocks
     let mut last = 0;
+    let mtl = colours_to_mtl(robot.into_iter().map(|c| c.colour), ROBOCRAFT_MATERIAL_LIB);
     for cube in robot.into_iter() {
-        // generate simple cube for every block
-        // TODO rotate blocks
         let vertices = lut(cube.id); // Use lookup table to find correct id <-> block translation
         let rotation: Quaternion<_> = cube_rotation_to_quat(cube.orientation);
         positions.extend::<Vec::<[f32; 3]>>(
@@ -65,27 +57,39 @@ pub fn cubes_to_model_with_lut<F: FnMut(u32) -> Vec<Quad<Vertex>>>(robot: robocr
         normals.extend::<Vec::<[f32; 3]>>(
             vertices.clone().into_iter().vertex(|v|
                 {
-                    let rotated = rotation * Vector3{x: v.normal.x * SCALE, y: v.normal.y * SCALE, z: v.normal.z * SCALE};
-                    [rotated.x + (cube.x as f32), rotated.y + (cube.y as f32), rotated.z + (cube.z as f32)]
+                    // normals are directions, so they are only rotated
+                    let rotated = (rotation * Vector3{x: v.normal.x, y: v.normal.y, z: v.normal.z}).normalize();
+                    [rotated.x, rotated.y, rotated.z]
+                })
+            .vertices()
+            .collect()
+        );
+        textures.extend::<Vec::<[f32; 2]>>(
+            vertices.clone().into_iter().vertex(|v|
+                {
+                    // texture is applied before rotation, so it turns with the block
+                    let local = [(v.pos.x + 1.0) * SCALE, (v.pos.y + 1.0) * SCALE, (v.pos.z + 1.0) * SCALE];
+                    face_uv(local, [v.normal.x, v.normal.y, v.normal.z])
                 })
             .vertices()
             .collect()
         );
         let polys = vertices.clone().into_iter().vertex(|_| {last+=1; return last-1;})
-            .map(|Quad{x: v0, y: v1, z: v2, w: v3}|
-                obj::SimplePolygon(vec![
-                obj::IndexTuple(v0, Some(0), Some(v0)),
-                obj::IndexTuple(v1, Some(0), Some(v1)),
-                obj::IndexTuple(v2, Some(0), Some(v2)),
-                obj::IndexTuple(v3, Some(0), Some(v3))
-                ])
-                /*obj::SimplePolygon(vec![
-                obj::IndexTuple(v0, None, None),
-                obj::IndexTuple(v1, None, None),
-                obj::IndexTuple(v2, None, None),
-                obj::IndexTuple(v3, None, None)
-                ])*/
-            ).collect();
+            .map(|poly| match poly {
+                Polygon::PolyTri(Triangle{x: v0, y: v1, z: v2}) =>
+                    obj::SimplePolygon(vec![
+                    obj::IndexTuple(v0, Some(v0), Some(v0)),
+                    obj::IndexTuple(v1, Some(v1), Some(v1)),
+                    obj::IndexTuple(v2, Some(v2), Some(v2))
+                    ]),
+                Polygon::PolyQuad(Quad{x: v0, y: v1, z: v2, w: v3}) =>
+                    obj::SimplePolygon(vec![
+                    obj::IndexTuple(v0, Some(v0), Some(v0)),
+                    obj::IndexTuple(v1, Some(v1), Some(v1)),
+                    obj::IndexTuple(v2, Some(v2), Some(v2)),
+                    obj::IndexTuple(v3, Some(v3), Some(v3))
+                    ]),
+            }).collect();
         objects.push(
             obj::Object{
                 name: format!("Cube-ID{}-NUM{}", cube.id, objects.len()),
@@ -93,30 +97,42 @@ pub fn cubes_to_model_with_lut<F: FnMut(u32) -> Vec<Quad<Vertex>>>(robot: robocr
                     obj::Group {
                         name: format!("Cube-ID{}-NUM{}-0", cube.id, objects.len()),
                         index: 0,
-                        material: None,
+                        material: find_material(&mtl, cube.colour),
                         polys: polys
                     },
                 ]
             }
         );
     }
-    println!("Last (index): {}, Vertices (len): {}", last, positions.len());
 
     obj::Obj{
         data: obj::ObjData {
             position: positions,
-            texture: vec![[0.0, 0.0]],
+            texture: textures,
             normal: normals,
             objects: objects,
-            material_libs: Vec::new(),
+            material_libs: vec![mtl],
         },
         path: std::path::PathBuf::new(),
     }
 }
 
-pub fn default_model_lut(id: u32) -> Vec<Quad<Vertex>> {
-    // TODO generate non-cube blocks properly
-    match id {
-        _ => Cube::new().collect(),
+/// Planar texture coordinates for a point on a face, projected along the face normal's main axis.
+///
+/// One unit of distance is one unit of texture, and textures are not mirrored on opposite faces.
+pub(crate) fn face_uv(point: [f32; 3], normal: [f32; 3]) -> [f32; 2] {
+    let axis = (0..3).max_by(|a, b| normal[*a].abs().total_cmp(&normal[*b].abs())).unwrap();
+    let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+    if normal[axis] < 0.0 {
+        [-point[u_axis], point[v_axis]]
+    } else {
+        [point[u_axis], point[v_axis]]
     }
 }
+
+/// Default lookup table function, which generates a full cube for every block.
+///
+/// Use a `ShapeLibrary` with `cubes_to_model_with_library(...)` for other block shapes.
+pub fn default_model_lut(_id: u32) -> Vec<Polygon<Vertex>> {
+    CubeShape::Cube.polygons()
+}
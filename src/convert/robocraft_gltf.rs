@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, HashMap};
+use genmesh::{Polygon, Triangle, Quad, Vertex};
+use serde_json::{json, Value};
+use crate::robocraft;
+use crate::convert::{ShapeLibrary, cube_rotation_to_quat, colour_to_rgb, colour_material_name};
+use crate::convert::robocraft_3d::face_uv;
+use crate::convert::SCALE;
+
+const GLB_MAGIC: u32 = 0x46546C67; // glTF
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F534A; // JSON
+const CHUNK_BIN: u32 = 0x004E4942; // BIN
+
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Convert a Robocraft robot to a self-contained binary glTF 2.0 (GLB) file.
+///
+/// Every cube id gets a parent node containing a node for every cube of that id, and cubes of the same id and colour share a mesh.
+/// There is one PBR material for every colour used by the robot.
+/// Cube ids without a shape in `library` are exported as full cubes.
+/// If `info` is provided, robot metadata (name, creator, CPU, etc.) is stored in the root node's `extras`.
+/// The returned bytes can be written directly to a `.glb` file.
+pub fn cubes_to_gltf(robot: &robocraft::Cubes, library: &ShapeLibrary, info: Option<&robocraft::FactoryRobotGetInfo>) -> Vec<u8> {
+    let mut buffer = Vec::<u8>::new();
+    let mut buffer_views = Vec::<Value>::new();
+    let mut accessors = Vec::<Value>::new();
+    let mut materials = Vec::<Value>::new();
+    let mut meshes = Vec::<Value>::new();
+    let mut nodes = Vec::<Value>::new();
+
+    // (cube index, cube) grouped by id, in order of first appearance
+    let mut groups = Vec::<(u32, Vec<(usize, &robocraft::Cube)>)>::new();
+    let mut group_by_id = HashMap::<u32, usize>::new();
+    let mut colours = BTreeMap::<u8, usize>::new(); // colour -> material index
+    for (i, cube) in robot.into_iter().enumerate() {
+        let group_i = *group_by_id.entry(cube.id).or_insert_with(|| {
+            groups.push((cube.id, Vec::new()));
+            groups.len() - 1
+        });
+        groups[group_i].1.push((i, cube));
+        colours.entry(cube.colour).or_insert(0);
+    }
+    for (colour, material_i) in colours.iter_mut() {
+        *material_i = materials.len();
+        materials.push(colour_to_gltf_material(*colour));
+    }
+
+    let mut type_nodes = Vec::<usize>::new();
+    for (id, cubes) in groups {
+        let geometry = ShapeGeometry::new(library.polygons(id));
+        let attributes = geometry.write(&mut buffer, &mut buffer_views, &mut accessors);
+        let mut mesh_by_colour = BTreeMap::<u8, usize>::new();
+        let mut children = Vec::<usize>::new();
+        for (i, cube) in cubes {
+            let mesh_i = *mesh_by_colour.entry(cube.colour).or_insert_with(|| {
+                meshes.push(json!({
+                    "name": format!("Cube-ID{}-{}", id, colour_material_name(cube.colour)),
+                    "primitives": [{
+                        "attributes": {
+                            "POSITION": attributes.0,
+                            "NORMAL": attributes.1,
+                            "TEXCOORD_0": attributes.2,
+                        },
+                        "indices": attributes.3,
+                        "material": colours[&cube.colour],
+                    }],
+                }));
+                meshes.len() - 1
+            });
+            let rotation = cube_rotation_to_quat(cube.orientation);
+            children.push(nodes.len());
+            nodes.push(json!({
+                "name": format!("Cube-ID{}-NUM{}", id, i),
+                "mesh": mesh_i,
+                "translation": [cube.x as f32, cube.y as f32, cube.z as f32],
+                "rotation": [rotation.v.x, rotation.v.y, rotation.v.z, rotation.s],
+                "extras": {
+                    "id": cube.id,
+                    "colour": cube.colour,
+                    "orientation": cube.orientation,
+                },
+            }));
+        }
+        type_nodes.push(nodes.len());
+        nodes.push(json!({
+            "name": format!("Cube-ID{}", id),
+            "children": children,
+            "extras": { "id": id },
+        }));
+    }
+
+    let root_i = nodes.len();
+    let mut root_extras = json!({ "cube_count": robot.len() });
+    if let Some(info) = info {
+        root_extras["item_id"] = json!(info.item_id);
+        root_extras["name"] = json!(info.item_name);
+        root_extras["description"] = json!(info.item_description);
+        root_extras["creator"] = json!(info.added_by);
+        root_extras["creator_display_name"] = json!(info.added_by_display_name);
+        root_extras["added_date"] = json!(info.added_date);
+        root_extras["cpu"] = json!(info.cpu);
+        root_extras["combat_rating"] = json!(info.combat_rating);
+        root_extras["cosmetic_rating"] = json!(info.cosmetic_rating);
+    }
+    let mut root = json!({
+        "name": info.map(|i| i.item_name.clone()).unwrap_or_else(|| "Robot".to_string()),
+        "extras": root_extras,
+    });
+    if !type_nodes.is_empty() {
+        root["children"] = json!(type_nodes);
+    }
+    nodes.push(root);
+
+    let mut document = json!({
+        "asset": {
+            "version": "2.0",
+            "generator": format!("libfj {}", crate::VERSION),
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": [root_i] }],
+        "nodes": nodes,
+    });
+    // glTF arrays must have at least one item, so leave out the empty ones (when the robot has no cubes)
+    for (key, values) in [("materials", materials), ("meshes", meshes), ("accessors", accessors), ("bufferViews", buffer_views)] {
+        if !values.is_empty() {
+            document[key] = Value::Array(values);
+        }
+    }
+    if !buffer.is_empty() {
+        document["buffers"] = json!([{ "byteLength": buffer.len() }]);
+    }
+    glb(&document, buffer)
+}
+
+/// Assemble a GLB container from the glTF JSON document and binary buffer
+fn glb(document: &Value, mut buffer: Vec<u8>) -> Vec<u8> {
+    // chunks must be 4-byte aligned
+    let mut json_bytes = serde_json::to_vec(document).unwrap();
+    json_bytes.resize(json_bytes.len().div_ceil(4) * 4, b' ');
+    buffer.resize(buffer.len().div_ceil(4) * 4, 0);
+    let mut total_len = 12 + 8 + json_bytes.len();
+    if !buffer.is_empty() {
+        total_len += 8 + buffer.len();
+    }
+    let mut out = Vec::with_capacity(total_len);
+    out.extend(&GLB_MAGIC.to_le_bytes());
+    out.extend(&GLB_VERSION.to_le_bytes());
+    out.extend(&(total_len as u32).to_le_bytes());
+    out.extend(&(json_bytes.len() as u32).to_le_bytes());
+    out.extend(&CHUNK_JSON.to_le_bytes());
+    out.extend(&json_bytes);
+    if !buffer.is_empty() {
+        out.extend(&(buffer.len() as u32).to_le_bytes());
+        out.extend(&CHUNK_BIN.to_le_bytes());
+        out.extend(&buffer);
+    }
+    out
+}
+
+fn colour_to_gltf_material(colour: u8) -> Value {
+    let rgb = colour_to_rgb(colour);
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    json!({
+        "name": colour_material_name(colour),
+        "pbrMetallicRoughness": {
+            "baseColorFactor": [linear(rgb[0]), linear(rgb[1]), linear(rgb[2]), 1.0],
+            "metallicFactor": 0.1,
+            "roughnessFactor": 0.6,
+        },
+        "extras": { "colour": colour },
+    })
+}
+
+/// Triangulated, flat-shaded vertex data for one block shape
+struct ShapeGeometry {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl ShapeGeometry {
+    fn new(polygons: Vec<Polygon<Vertex>>) -> Self {
+        let mut geometry = Self {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+        };
+        for poly in polygons {
+            let vertices = match poly {
+                Polygon::PolyTri(Triangle{x, y, z}) => vec![x, y, z],
+                Polygon::PolyQuad(Quad{x, y, z, w}) => vec![x, y, z, w],
+            };
+            let start = geometry.positions.len() as u32;
+            for v in &vertices {
+                let normal = [v.normal.x, v.normal.y, v.normal.z];
+                geometry.positions.push([v.pos.x * SCALE, v.pos.y * SCALE, v.pos.z * SCALE]);
+                geometry.normals.push(normal);
+                geometry.uvs.push(face_uv([(v.pos.x + 1.0) * SCALE, (v.pos.y + 1.0) * SCALE, (v.pos.z + 1.0) * SCALE], normal));
+            }
+            // triangle fan, which keeps the counter-clockwise winding
+            for i in 1..(vertices.len() as u32 - 1) {
+                geometry.indices.extend(&[start, start + i, start + i + 1]);
+            }
+        }
+        geometry
+    }
+
+    /// Append geometry to the buffer, returning the (position, normal, uv, index) accessor indices
+    fn write(&self, buffer: &mut Vec<u8>, views: &mut Vec<Value>, accessors: &mut Vec<Value>) -> (usize, usize, usize, usize) {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in &self.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        let position_i = push_accessor(buffer, views, accessors,
+            self.positions.iter().flatten().flat_map(|f| f.to_le_bytes()).collect(),
+            json!({ "componentType": COMPONENT_FLOAT, "count": self.positions.len(), "type": "VEC3", "min": min, "max": max }),
+            TARGET_ARRAY_BUFFER);
+        let normal_i = push_accessor(buffer, views, accessors,
+            self.normals.iter().flatten().flat_map(|f| f.to_le_bytes()).collect(),
+            json!({ "componentType": COMPONENT_FLOAT, "count": self.normals.len(), "type": "VEC3" }),
+            TARGET_ARRAY_BUFFER);
+        let uv_i = push_accessor(buffer, views, accessors,
+            self.uvs.iter().flatten().flat_map(|f| f.to_le_bytes()).collect(),
+            json!({ "componentType": COMPONENT_FLOAT, "count": self.uvs.len(), "type": "VEC2" }),
+            TARGET_ARRAY_BUFFER);
+        let index_i = push_accessor(buffer, views, accessors,
+            self.indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+            json!({ "componentType": COMPONENT_UNSIGNED_INT, "count": self.indices.len(), "type": "SCALAR" }),
+            TARGET_ELEMENT_ARRAY_BUFFER);
+        (position_i, normal_i, uv_i, index_i)
+    }
+}
+
+fn push_accessor(buffer: &mut Vec<u8>, views: &mut Vec<Value>, accessors: &mut Vec<Value>, data: Vec<u8>, mut accessor: Value, target: u32) -> usize {
+    views.push(json!({
+        "buffer": 0,
+        "byteOffset": buffer.len(),
+        "byteLength": data.len(),
+        "target": target,
+    }));
+    buffer.extend(data);
+    accessor["bufferView"] = json!(views.len() - 1);
+    accessors.push(accessor);
+    accessors.len() - 1
+}
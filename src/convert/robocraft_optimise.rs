@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use genmesh::{Polygon, Triangle, Quad, Vertex};
+use obj;
+use cgmath::{Quaternion, Vector3, InnerSpace};
+use crate::robocraft;
+use crate::convert::{CubeShape, ShapeLibrary, cube_rotation_to_quat, colours_to_mtl};
+use crate::convert::robocraft_palette::find_material;
+use crate::convert::robocraft_3d::face_uv;
+use crate::convert::SCALE;
+
+const DEDUPE_PRECISION: f32 = 4096.0;
+
+/// Face directions of a cube, as (axis, positive?)
+const DIRECTIONS: [(usize, bool); 6] = [(0, true), (0, false), (1, true), (1, false), (2, true), (2, false)];
+
+/// Options for `cubes_to_model_optimised(...)`.
+#[derive(Clone, Debug)]
+pub struct ModelOptions {
+    /// Remove faces between two adjacent full cubes, since they can never be seen
+    pub cull_hidden_faces: bool,
+    /// Merge neighbouring coplanar faces of the same colour into larger rectangles (greedy meshing)
+    pub merge_faces: bool,
+    /// Share identical vertex positions and normals between faces
+    pub dedupe_vertices: bool,
+    /// File name of the material library, which contains a material for every colour used by the robot
+    pub material_lib: String,
+}
+
+impl std::default::Default for ModelOptions {
+    fn default() -> Self {
+        Self {
+            cull_hidden_faces: true,
+            merge_faces: true,
+            dedupe_vertices: true,
+            material_lib: crate::convert::ROBOCRAFT_MATERIAL_LIB.to_string(),
+        }
+    }
+}
+
+/// Convert a Robocraft robot to a single optimised mesh in Wavefront OBJ format.
+///
+/// Unlike `cubes_to_model(...)`, this creates one object for the whole robot, which is much smaller and faster to render.
+/// Only full cubes (according to `library`) are culled and merged, other shapes are copied as-is.
+/// Faces are grouped by colour, with one material per colour.
+pub fn cubes_to_model_optimised(robot: &robocraft::Cubes, library: &ShapeLibrary, options: &ModelOptions) -> obj::Obj {
+    let mut mesh = MeshBuilder::new(options.dedupe_vertices);
+    let full_cubes: HashMap<[i32; 3], u8> = robot.into_iter()
+        .filter(|c| library.shape(c.id) == CubeShape::Cube)
+        .map(|c| ([c.x as i32, c.y as i32, c.z as i32], c.colour))
+        .collect();
+    // visible full cube faces, grouped by plane: (axis, positive?, plane) -> (u, v) -> colour
+    let mut planes: HashMap<(usize, bool, i32), HashMap<[i32; 2], u8>> = HashMap::new();
+    for cube in robot.into_iter() {
+        let pos = [cube.x as i32, cube.y as i32, cube.z as i32];
+        if library.shape(cube.id) != CubeShape::Cube {
+            mesh.add_polygons(library.polygons(cube.id), cube_rotation_to_quat(cube.orientation), pos, cube.colour);
+            continue;
+        }
+        for (axis, positive) in DIRECTIONS.iter() {
+            let mut neighbour = pos;
+            neighbour[*axis] += if *positive { 1 } else { -1 };
+            if options.cull_hidden_faces && full_cubes.contains_key(&neighbour) {
+                continue;
+            }
+            planes.entry((*axis, *positive, pos[*axis]))
+                .or_default()
+                .insert([pos[(axis + 1) % 3], pos[(axis + 2) % 3]], cube.colour);
+        }
+    }
+    let mut plane_keys: Vec<_> = planes.keys().copied().collect();
+    plane_keys.sort();
+    for key in plane_keys {
+        let cells = &planes[&key];
+        let rects = if options.merge_faces {
+            merge_cells(cells)
+        } else {
+            cells.keys().map(|c| (*c, [c[0] + 1, c[1] + 1])).collect()
+        };
+        for (start, end) in rects {
+            mesh.add_axis_face(key.0, key.1, key.2, start, end, cells[&start]);
+        }
+    }
+    let mtl = colours_to_mtl(robot.into_iter().map(|c| c.colour), &options.material_lib);
+    mesh.build("Robot", mtl)
+}
+
+/// Greedily merge cells of the same colour into rectangles, as (start, end) with exclusive end
+fn merge_cells(cells: &HashMap<[i32; 2], u8>) -> Vec<([i32; 2], [i32; 2])> {
+    let mut sorted: Vec<[i32; 2]> = cells.keys().copied().collect();
+    sorted.sort_by_key(|c| (c[1], c[0]));
+    let mut used = HashSet::new();
+    let mut rects = Vec::new();
+    for cell in sorted {
+        if used.contains(&cell) {
+            continue;
+        }
+        let colour = cells[&cell];
+        let available = |c: &[i32; 2], used: &HashSet<[i32; 2]>| cells.get(c) == Some(&colour) && !used.contains(c);
+        // extend along u
+        let mut end_u = cell[0] + 1;
+        while available(&[end_u, cell[1]], &used) {
+            end_u += 1;
+        }
+        // extend along v while the whole row matches
+        let mut end_v = cell[1] + 1;
+        while (cell[0]..end_u).all(|u| available(&[u, end_v], &used)) {
+            end_v += 1;
+        }
+        for v in cell[1]..end_v {
+            for u in cell[0]..end_u {
+                used.insert([u, v]);
+            }
+        }
+        rects.push((cell, [end_u, end_v]));
+    }
+    rects
+}
+
+/// Single-object mesh builder with optional vertex de-duplication
+struct MeshBuilder {
+    dedupe: bool,
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    textures: Vec<[f32; 2]>,
+    position_lookup: HashMap<[i32; 3], usize>,
+    normal_lookup: HashMap<[i32; 3], usize>,
+    texture_lookup: HashMap<[i32; 2], usize>,
+    polys: BTreeMap<u8, Vec<obj::SimplePolygon>>,
+}
+
+impl MeshBuilder {
+    fn new(dedupe: bool) -> Self {
+        Self {
+            dedupe,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            textures: Vec::new(),
+            position_lookup: HashMap::new(),
+            normal_lookup: HashMap::new(),
+            texture_lookup: HashMap::new(),
+            polys: BTreeMap::new(),
+        }
+    }
+
+    fn index_of<const N: usize>(dedupe: bool, values: &mut Vec<[f32; N]>, lookup: &mut HashMap<[i32; N], usize>, value: [f32; N]) -> usize {
+        if !dedupe {
+            values.push(value);
+            return values.len() - 1;
+        }
+        // snap to a grid, so that values with rounding errors from rotation still match
+        let key = value.map(|v| (v * DEDUPE_PRECISION).round() as i32);
+        *lookup.entry(key).or_insert_with(|| {
+            values.push(value);
+            values.len() - 1
+        })
+    }
+
+    fn add_face(&mut self, points: &[[f32; 3]], normal: [f32; 3], colour: u8) {
+        let normal_i = Self::index_of(self.dedupe, &mut self.normals, &mut self.normal_lookup, normal);
+        let mut indices = Vec::with_capacity(points.len());
+        for p in points {
+            let pos_i = Self::index_of(self.dedupe, &mut self.positions, &mut self.position_lookup, *p);
+            // offset so that texture edges line up with cube edges
+            let uv = face_uv([p[0] + SCALE, p[1] + SCALE, p[2] + SCALE], normal);
+            let uv_i = Self::index_of(self.dedupe, &mut self.textures, &mut self.texture_lookup, uv);
+            indices.push(obj::IndexTuple(pos_i, Some(uv_i), Some(normal_i)));
+        }
+        self.polys.entry(colour).or_default().push(obj::SimplePolygon(indices));
+    }
+
+    /// Add an axis-aligned rectangle on the outside of the cube plane `plane`, spanning cells start..end
+    fn add_axis_face(&mut self, axis: usize, positive: bool, plane: i32, start: [i32; 2], end: [i32; 2], colour: u8) {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let depth = plane as f32 + if positive { SCALE } else { -SCALE };
+        let point = |u: i32, v: i32| {
+            let mut p = [0.0; 3];
+            p[axis] = depth;
+            p[u_axis] = u as f32 - SCALE;
+            p[v_axis] = v as f32 - SCALE;
+            p
+        };
+        let mut points = [point(start[0], start[1]), point(end[0], start[1]), point(end[0], end[1]), point(start[0], end[1])];
+        let mut normal = [0.0; 3];
+        normal[axis] = if positive { 1.0 } else { -1.0 };
+        if !positive {
+            points.reverse(); // keep counter-clockwise winding when viewed from outside
+        }
+        self.add_face(&points, normal, colour);
+    }
+
+    /// Add shape polygons, rotated and moved to a cube position
+    fn add_polygons(&mut self, polygons: Vec<Polygon<Vertex>>, rotation: Quaternion<f32>, pos: [i32; 3], colour: u8) {
+        let transform = |v: &Vertex| {
+            let rotated = rotation * Vector3{x: v.pos.x * SCALE, y: v.pos.y * SCALE, z: v.pos.z * SCALE};
+            [rotated.x + pos[0] as f32, rotated.y + pos[1] as f32, rotated.z + pos[2] as f32]
+        };
+        for poly in polygons {
+            let vertices = match poly {
+                Polygon::PolyTri(Triangle{x, y, z}) => vec![x, y, z],
+                Polygon::PolyQuad(Quad{x, y, z, w}) => vec![x, y, z, w],
+            };
+            let n = (rotation * Vector3{x: vertices[0].normal.x, y: vertices[0].normal.y, z: vertices[0].normal.z}).normalize();
+            let points: Vec<[f32; 3]> = vertices.iter().map(transform).collect();
+            self.add_face(&points, [n.x, n.y, n.z], colour);
+        }
+    }
+
+    fn build(self, name: &str, mtl: obj::Mtl) -> obj::Obj {
+        let groups = self.polys.into_iter()
+            .map(|(colour, polys)| obj::Group {
+                name: format!("{}-Colour{}", name, colour),
+                index: 0,
+                material: find_material(&mtl, colour),
+                polys,
+            })
+            .collect();
+        obj::Obj{
+            data: obj::ObjData {
+                position: self.positions,
+                texture: self.textures,
+                normal: self.normals,
+                objects: vec![
+                    obj::Object{
+                        name: name.to_string(),
+                        groups,
+                    }
+                ],
+                material_libs: vec![mtl],
+            },
+            path: std::path::PathBuf::new(),
+        }
+    }
+}
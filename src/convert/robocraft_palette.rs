@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use obj;
+
+/// Default material library file name, used by Robocraft model conversion functions.
+pub const ROBOCRAFT_MATERIAL_LIB: &str = "robocraft.mtl";
+
+/// Provisional stand-in for Robocraft's 24 cube colours, as (name, sRGB) pairs indexed by `Cube.colour`.
+///
+/// This is not the game's palette: the names and sRGB values are hand-picked placeholders,
+/// which will be replaced once the actual paint colours are extracted from the game.
+/// Only the amount of colours (24) matches Robocraft.
+pub const PROVISIONAL_ROBOCRAFT_PALETTE: [(&str, [u8; 3]); 24] = [
+    ("White", [235, 235, 235]), // 0
+    ("LightGrey", [170, 170, 170]),
+    ("DarkGrey", [90, 90, 90]), // 2
+    ("Black", [30, 30, 30]),
+    ("Red", [200, 30, 30]), // 4
+    ("DarkRed", [120, 20, 20]),
+    ("Orange", [230, 120, 20]), // 6
+    ("Yellow", [240, 210, 30]),
+    ("Lime", [140, 200, 40]), // 8
+    ("Green", [40, 150, 50]),
+    ("DarkGreen", [20, 80, 30]), // 10
+    ("Teal", [20, 150, 140]),
+    ("Cyan", [60, 200, 230]), // 12
+    ("LightBlue", [100, 150, 230]),
+    ("Blue", [30, 70, 200]), // 14
+    ("DarkBlue", [20, 30, 110]),
+    ("Purple", [110, 40, 170]), // 16
+    ("Magenta", [200, 50, 180]),
+    ("Pink", [240, 140, 180]), // 18
+    ("Brown", [110, 70, 40]),
+    ("Tan", [200, 170, 120]), // 20
+    ("Olive", [110, 110, 40]),
+    ("Gold", [200, 160, 50]), // 22
+    ("Crimson", [160, 20, 60]), // 23
+];
+
+/// Provisional stand-in for Techblox's 10 paint colours, as (name, sRGB) pairs at their lightest shade.
+///
+/// Techblox palette index `shade * 10 + colour` is paint `colour` at `shade` (0 is the lightest, 9 the darkest),
+/// following the layout used by the community modding API.
+/// This is not the game's palette: the sRGB values are hand-picked placeholders.
+pub const PROVISIONAL_TECHBLOX_PAINTS: [(&str, [u8; 3]); 10] = [
+    ("White", [235, 235, 235]), // 0
+    ("Pink", [240, 140, 190]),
+    ("Purple", [140, 70, 200]), // 2
+    ("Blue", [40, 90, 220]),
+    ("Aqua", [50, 200, 210]), // 4
+    ("Green", [40, 160, 60]),
+    ("Lime", [150, 215, 50]), // 6
+    ("Yellow", [245, 215, 40]),
+    ("Orange", [240, 130, 30]), // 8
+    ("Red", [210, 35, 35]), // 9
+];
+
+/// Build a provisional 100 colour Techblox palette from `PROVISIONAL_TECHBLOX_PAINTS`, as sRGB colours indexed by Techblox palette index.
+///
+/// Every shade is 10% darker than the previous one; like the paints, the shades are placeholders.
+pub fn techblox_palette() -> Vec<[u8; 3]> {
+    (0..10).flat_map(|shade| PROVISIONAL_TECHBLOX_PAINTS.iter().map(move |(_, rgb)| {
+        rgb.map(|c| (c as f32 * (1.0 - shade as f32 / 10.0)).round() as u8)
+    })).collect()
+}
+
+/// Colour index used in place of colours which are not in `PROVISIONAL_ROBOCRAFT_PALETTE`.
+pub const FALLBACK_COLOUR: u8 = 0;
+
+/// Convert a Robocraft colour index into an sRGB colour from `PROVISIONAL_ROBOCRAFT_PALETTE`.
+///
+/// Out of range colours are treated as `FALLBACK_COLOUR` (white);
+/// use `try_colour_to_rgb(...)` to detect them instead.
+pub fn colour_to_rgb(colour: u8) -> [u8; 3] {
+    palette_entry(colour).1
+}
+
+/// Convert a Robocraft colour index into an sRGB colour from `PROVISIONAL_ROBOCRAFT_PALETTE`, or None when the colour is not in the palette.
+pub fn try_colour_to_rgb(colour: u8) -> Option<[u8; 3]> {
+    PROVISIONAL_ROBOCRAFT_PALETTE.get(colour as usize).map(|(_, rgb)| *rgb)
+}
+
+/// Name of the material used for a Robocraft colour index.
+///
+/// Out of range colours use the material of `FALLBACK_COLOUR`.
+pub fn colour_material_name(colour: u8) -> String {
+    format!("Robocraft{}", palette_entry(colour).0)
+}
+
+/// Build a material for a Robocraft colour index.
+pub fn colour_material(colour: u8) -> obj::Material {
+    let rgb = colour_to_rgb(colour);
+    let diffuse = [rgb[0] as f32 / 255.0, rgb[1] as f32 / 255.0, rgb[2] as f32 / 255.0];
+    let mut material = obj::Material::new(colour_material_name(colour));
+    material.ka = Some(diffuse);
+    material.kd = Some(diffuse);
+    material.ks = Some([0.1, 0.1, 0.1]);
+    material.d = Some(1.0);
+    material.illum = Some(2);
+    material
+}
+
+/// Build a material library with one material for each of the provided Robocraft colour indices.
+///
+/// Duplicate colours are only included once.
+pub fn colours_to_mtl<I: IntoIterator<Item = u8>>(colours: I, filename: &str) -> obj::Mtl {
+    let mut mtl = obj::Mtl::new(filename.to_string());
+    let mut used = Vec::new();
+    for colour in colours {
+        let colour = clamp_colour(colour);
+        if !used.contains(&colour) {
+            used.push(colour);
+        }
+    }
+    used.sort_unstable();
+    mtl.materials = used.into_iter().map(|c| Arc::new(colour_material(c))).collect();
+    mtl
+}
+
+/// Find the material for a colour in a material library built by `colours_to_mtl(...)`.
+pub(crate) fn find_material(mtl: &obj::Mtl, colour: u8) -> Option<obj::ObjMaterial> {
+    let name = colour_material_name(colour);
+    mtl.materials.iter()
+        .find(|m| m.name == name)
+        .map(|m| obj::ObjMaterial::Mtl(m.clone()))
+}
+
+fn clamp_colour(colour: u8) -> u8 {
+    if (colour as usize) < PROVISIONAL_ROBOCRAFT_PALETTE.len() { colour } else { FALLBACK_COLOUR }
+}
+
+fn palette_entry(colour: u8) -> (&'static str, [u8; 3]) {
+    PROVISIONAL_ROBOCRAFT_PALETTE[clamp_colour(colour) as usize]
+}
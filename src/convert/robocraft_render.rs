@@ -0,0 +1,262 @@
+use genmesh::{Polygon, Triangle, Quad, Vertex};
+use cgmath::{Quaternion, Vector3, Rotation3, Deg, InnerSpace};
+use crate::robocraft;
+use crate::convert::{ShapeLibrary, PROVISIONAL_ROBOCRAFT_PALETTE, cube_rotation_to_quat};
+use png::EncodingError;
+use crate::convert::SCALE;
+
+/// Options for rendering thumbnails with `cubes_to_png(...)` and `vehicle_to_png(...)`.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Image width, in pixels
+    pub width: u32,
+    /// Image height, in pixels
+    pub height: u32,
+    /// Background colour, as RGBA (use alpha 0 for a transparent background)
+    pub background: [u8; 4],
+    /// Camera rotation around the vertical axis, in degrees
+    pub yaw: f32,
+    /// Camera angle above the horizon, in degrees
+    pub pitch: f32,
+    /// Brightness of faces which are not lit, from 0.0 to 1.0
+    pub ambient: f32,
+    /// Empty space around the robot, in pixels
+    pub margin: u32,
+    /// Samples per pixel along each axis, for anti-aliasing (1 disables anti-aliasing)
+    pub supersampling: u32,
+    /// sRGB colour for every colour index, indexed by `Cube.colour` or `VehicleBlock.colour`.
+    /// Colours without an entry are rendered with the first colour.
+    /// When None, cubes use `PROVISIONAL_ROBOCRAFT_PALETTE` and vehicle blocks use `techblox_palette()`.
+    pub palette: Option<Vec<[u8; 3]>>,
+}
+
+impl std::default::Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+            background: [0, 0, 0, 0],
+            yaw: 45.0,
+            pitch: 30.0,
+            ambient: 0.45,
+            margin: 8,
+            supersampling: 2,
+            palette: None,
+        }
+    }
+}
+
+/// Render a Robocraft robot to a PNG image, using an isometric camera.
+///
+/// This is a software renderer, so no GPU is required.
+/// Cube shapes are looked up in `library` (cube ids without a shape are drawn as full cubes), and cubes are coloured using `options.palette`.
+/// Encode the result as base64 to use it as a `robocraft2::CreateRobotPayload.image`.
+///
+/// This fails when the image width or height is 0, or when the supersampled image is too big to address.
+pub fn cubes_to_png(robot: &robocraft::Cubes, library: &ShapeLibrary, options: &RenderOptions) -> Result<Vec<u8>, EncodingError> {
+    let default_palette: Vec<[u8; 3]> = PROVISIONAL_ROBOCRAFT_PALETTE.iter().map(|(_, rgb)| *rgb).collect();
+    let mut scene = Scene::new(options, options.palette.as_ref().unwrap_or(&default_palette));
+    for cube in robot.into_iter() {
+        let pos = Vector3::new(cube.x as f32, cube.y as f32, cube.z as f32);
+        scene.add_polygons(library.polygons(cube.id), cube_rotation_to_quat(cube.orientation), Vector3::new(SCALE, SCALE, SCALE), pos, cube.colour);
+    }
+    encode_png(&scene.render()?, options.width, options.height)
+}
+
+/// Render a Robocraft2 vehicle to a PNG image, using an isometric camera.
+///
+/// This is a software renderer, so no GPU is required.
+/// Every block is drawn as a box, and blocks are coloured using `options.palette`.
+/// Encode the result as base64 to use it as a `robocraft2::CreateRobotPayload.image`.
+///
+/// This fails when the image width or height is 0, or when the supersampled image is too big to address.
+#[cfg(all(feature = "robocraft2", feature = "techblox"))]
+pub fn vehicle_to_png(vehicle: &crate::robocraft2::Vehicle, options: &RenderOptions) -> Result<Vec<u8>, EncodingError> {
+    use crate::robocraft2::BLOCK_SIZE;
+    let default_palette = crate::convert::techblox_palette();
+    let mut scene = Scene::new(options, options.palette.as_ref().unwrap_or(&default_palette));
+    for block in vehicle.blocks() {
+        let rotation = Quaternion::new(block.rotation[3], block.rotation[0], block.rotation[1], block.rotation[2]);
+        let scale = Vector3::new(block.scale[0], block.scale[1], block.scale[2]) * (BLOCK_SIZE * SCALE);
+        let pos = Vector3::new(block.position[0], block.position[1], block.position[2]);
+        scene.add_polygons(crate::convert::CubeShape::Cube.polygons(), rotation, scale, pos, block.colour);
+    }
+    encode_png(&scene.render()?, options.width, options.height)
+}
+
+/// Encode RGBA pixels, which fails for images with a width or height of 0
+fn encode_png(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, EncodingError> {
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(pixels)?;
+    }
+    Ok(out)
+}
+
+/// Flat-shaded triangle in view space
+struct ViewTriangle {
+    points: [Vector3<f32>; 3],
+    colour: [f32; 3],
+}
+
+/// Triangles to render, already rotated into view space (x right, y up, z towards the camera)
+struct Scene<'a> {
+    options: &'a RenderOptions,
+    palette: &'a [[u8; 3]],
+    view: Quaternion<f32>,
+    light: Vector3<f32>,
+    triangles: Vec<ViewTriangle>,
+}
+
+impl<'a> Scene<'a> {
+    fn new(options: &'a RenderOptions, palette: &'a [[u8; 3]]) -> Self {
+        let view = Quaternion::from_angle_x(Deg(options.pitch)) * Quaternion::from_angle_y(Deg(-options.yaw));
+        Self {
+            options,
+            palette,
+            view,
+            // from the top left, slightly in front of the camera
+            light: Vector3::new(-0.4, 0.8, 0.45).normalize(),
+            triangles: Vec::new(),
+        }
+    }
+
+    fn add_polygons(&mut self, polygons: Vec<Polygon<Vertex>>, rotation: Quaternion<f32>, scale: Vector3<f32>, pos: Vector3<f32>, colour: u8) {
+        let rgb = self.palette.get(colour as usize)
+            .or_else(|| self.palette.first())
+            .copied()
+            .unwrap_or([255, 255, 255]);
+        let rotation = self.view * rotation;
+        let offset = self.view * pos;
+        for poly in polygons {
+            let vertices = match poly {
+                Polygon::PolyTri(Triangle{x, y, z}) => vec![x, y, z],
+                Polygon::PolyQuad(Quad{x, y, z, w}) => vec![x, y, z, w],
+            };
+            let normal = (rotation * Vector3::new(vertices[0].normal.x, vertices[0].normal.y, vertices[0].normal.z)).normalize();
+            if normal.z < 0.0 {
+                continue; // facing away from the camera
+            }
+            let brightness = self.options.ambient + (1.0 - self.options.ambient) * normal.dot(self.light).max(0.0);
+            let shaded = [0, 1, 2].map(|i| (srgb_to_linear(rgb[i]) * brightness).min(1.0));
+            let points: Vec<Vector3<f32>> = vertices.iter()
+                .map(|v| rotation * Vector3::new(v.pos.x * scale.x, v.pos.y * scale.y, v.pos.z * scale.z) + offset)
+                .collect();
+            // triangle fan
+            for i in 1..(points.len() - 1) {
+                self.triangles.push(ViewTriangle {
+                    points: [points[0], points[i], points[i + 1]],
+                    colour: shaded,
+                });
+            }
+        }
+    }
+
+    /// Rasterise all triangles into RGBA pixels, or fail when the supersampled image size overflows
+    fn render(&self) -> Result<Vec<u8>, EncodingError> {
+        let samples = self.options.supersampling.max(1);
+        let width = self.options.width.checked_mul(samples).ok_or(EncodingError::LimitsExceeded)? as usize;
+        let height = self.options.height.checked_mul(samples).ok_or(EncodingError::LimitsExceeded)? as usize;
+        let len = width.checked_mul(height).ok_or(EncodingError::LimitsExceeded)?;
+        let mut colour_buf: Vec<Option<[f32; 3]>> = vec![None; len];
+        let mut depth_buf = vec![f32::NEG_INFINITY; len];
+
+        // fit the robot in the image
+        let mut min = [f32::MAX; 2];
+        let mut max = [f32::MIN; 2];
+        for p in self.triangles.iter().flat_map(|t| t.points.iter()) {
+            min[0] = min[0].min(p.x);
+            min[1] = min[1].min(p.y);
+            max[0] = max[0].max(p.x);
+            max[1] = max[1].max(p.y);
+        }
+        let margin = self.options.margin as f32 * samples as f32;
+        let usable = [(width as f32 - 2.0 * margin).max(1.0), (height as f32 - 2.0 * margin).max(1.0)];
+        let zoom = (usable[0] / (max[0] - min[0]).max(f32::EPSILON)).min(usable[1] / (max[1] - min[1]).max(f32::EPSILON));
+        let centre = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        let to_screen = |p: &Vector3<f32>| [
+            (p.x - centre[0]) * zoom + width as f32 / 2.0,
+            (centre[1] - p.y) * zoom + height as f32 / 2.0,
+            p.z,
+        ];
+
+        for triangle in &self.triangles {
+            let [a, b, c] = [to_screen(&triangle.points[0]), to_screen(&triangle.points[1]), to_screen(&triangle.points[2])];
+            let area = edge(a, b, c);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+            let x_start = a[0].min(b[0]).min(c[0]).floor().max(0.0) as usize;
+            let x_end = (a[0].max(b[0]).max(c[0]).ceil().max(0.0) as usize).min(width);
+            let y_start = a[1].min(b[1]).min(c[1]).floor().max(0.0) as usize;
+            let y_end = (a[1].max(b[1]).max(c[1]).ceil().max(0.0) as usize).min(height);
+            for y in y_start..y_end {
+                for x in x_start..x_end {
+                    let p = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+                    // barycentric weights, which all have the same sign as the area inside the triangle
+                    let w = [edge(b, c, p) / area, edge(c, a, p) / area, edge(a, b, p) / area];
+                    if w[0] < 0.0 || w[1] < 0.0 || w[2] < 0.0 {
+                        continue;
+                    }
+                    let depth = w[0] * a[2] + w[1] * b[2] + w[2] * c[2];
+                    let i = y * width + x;
+                    if depth > depth_buf[i] {
+                        depth_buf[i] = depth;
+                        colour_buf[i] = Some(triangle.colour);
+                    }
+                }
+            }
+        }
+
+        // downsample and convert to sRGB
+        let samples = samples as usize;
+        let background = self.options.background;
+        let mut pixels = Vec::with_capacity(self.options.width as usize * self.options.height as usize * 4);
+        for y in 0..self.options.height as usize {
+            for x in 0..self.options.width as usize {
+                let mut sum = [0.0f32; 3];
+                let mut coverage = 0.0f32;
+                for sy in 0..samples {
+                    for sx in 0..samples {
+                        let i = (y * samples + sy) * width + x * samples + sx;
+                        let (colour, alpha) = match colour_buf[i] {
+                            Some(colour) => (colour, 1.0),
+                            None => ([0, 1, 2].map(|c| srgb_to_linear(background[c])), background[3] as f32 / 255.0),
+                        };
+                        for c in 0..3 {
+                            sum[c] += colour[c] * alpha;
+                        }
+                        coverage += alpha;
+                    }
+                }
+                let count = (samples * samples) as f32;
+                if coverage > 0.0 {
+                    pixels.extend(sum.map(|c| linear_to_srgb(c / coverage)));
+                } else {
+                    pixels.extend(&background[..3]);
+                }
+                pixels.push((coverage / count * 255.0).round() as u8);
+            }
+        }
+        Ok(pixels)
+    }
+}
+
+/// Signed area of the parallelogram formed by a->b and a->p
+fn edge(a: [f32; 3], b: [f32; 3], p: [f32; 3]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
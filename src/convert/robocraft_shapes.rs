@@ -0,0 +1,132 @@
+use genmesh::{generators::Cube, Polygon, Triangle, Quad, Vertex};
+use crate::id_table::IdTable;
+
+/// Amount of sides used to approximate round shapes
+const ROUND_SEGMENTS: usize = 12;
+
+/// Size of weapon and module proxy boxes, relative to a cube
+const PROXY_SIZE: f32 = 0.6;
+
+/// The general shape of a Robocraft block.
+///
+/// Shapes are defined in the default orientation (orientation 0), spanning -1 to 1 on every axis.
+/// Complex blocks (wheels, weapons, modules, etc.) are approximated by simpler proxy shapes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeShape {
+    /// Full cube
+    Cube,
+    /// Slope, with full bottom (-y) and back (-z) faces
+    Prism,
+    /// Corner slope, with a full bottom (-y) face and a peak at the top back left (-x, +y, -z) corner
+    Tetra,
+    /// Inner corner, which is a cube with the top front right (+x, +y, +z) corner cut off
+    Inner,
+    /// Wheel proxy, a cylinder with the axle along x
+    Wheel,
+    /// Weapon or module proxy, a small centred box
+    Proxy,
+}
+
+impl CubeShape {
+    /// Generate the polygons of this shape.
+    ///
+    /// Polygons are wound counter-clockwise when viewed from outside the shape.
+    pub fn polygons(&self) -> Vec<Polygon<Vertex>> {
+        match self {
+            Self::Cube => Cube::new().map(Polygon::PolyQuad).collect(),
+            Self::Prism => vec![
+                quad([-1.0, -1.0, -1.0], [1.0, -1.0, -1.0], [1.0, -1.0, 1.0], [-1.0, -1.0, 1.0]), // bottom
+                quad([-1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [1.0, 1.0, -1.0], [1.0, -1.0, -1.0]), // back
+                quad([-1.0, -1.0, 1.0], [1.0, -1.0, 1.0], [1.0, 1.0, -1.0], [-1.0, 1.0, -1.0]), // slope
+                tri([-1.0, -1.0, -1.0], [-1.0, -1.0, 1.0], [-1.0, 1.0, -1.0]), // left
+                tri([1.0, -1.0, -1.0], [1.0, 1.0, -1.0], [1.0, -1.0, 1.0]), // right
+            ],
+            Self::Tetra => vec![
+                quad([-1.0, -1.0, -1.0], [1.0, -1.0, -1.0], [1.0, -1.0, 1.0], [-1.0, -1.0, 1.0]), // bottom
+                tri([-1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [1.0, -1.0, -1.0]), // back
+                tri([-1.0, -1.0, -1.0], [-1.0, -1.0, 1.0], [-1.0, 1.0, -1.0]), // left
+                tri([1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [1.0, -1.0, 1.0]), // right slope
+                tri([-1.0, -1.0, 1.0], [1.0, -1.0, 1.0], [-1.0, 1.0, -1.0]), // front slope
+            ],
+            Self::Inner => vec![
+                quad([-1.0, -1.0, -1.0], [1.0, -1.0, -1.0], [1.0, -1.0, 1.0], [-1.0, -1.0, 1.0]), // bottom
+                quad([-1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [1.0, 1.0, -1.0], [1.0, -1.0, -1.0]), // back
+                quad([-1.0, -1.0, -1.0], [-1.0, -1.0, 1.0], [-1.0, 1.0, 1.0], [-1.0, 1.0, -1.0]), // left
+                tri([-1.0, 1.0, -1.0], [-1.0, 1.0, 1.0], [1.0, 1.0, -1.0]), // top
+                tri([1.0, -1.0, -1.0], [1.0, 1.0, -1.0], [1.0, -1.0, 1.0]), // right
+                tri([-1.0, -1.0, 1.0], [1.0, -1.0, 1.0], [-1.0, 1.0, 1.0]), // front
+                tri([1.0, 1.0, -1.0], [-1.0, 1.0, 1.0], [1.0, -1.0, 1.0]), // cut
+            ],
+            Self::Wheel => cylinder(ROUND_SEGMENTS),
+            Self::Proxy => Cube::new()
+                .map(|q| Polygon::PolyQuad(Quad::new(shrink(q.x), shrink(q.y), shrink(q.z), shrink(q.w))))
+                .collect(),
+        }
+    }
+}
+
+/// Per-cube-id lookup table of block shapes.
+///
+/// Any cube id without a registered shape uses the default shape (a full cube, unless changed with `set_default(...)`).
+/// libfj does not include the game's cube id to shape assignments, so they must be registered by the user.
+pub type ShapeLibrary = IdTable<CubeShape>;
+
+impl IdTable<CubeShape> {
+    /// Get the shape of a cube id.
+    pub fn shape(&self, id: u32) -> CubeShape {
+        self.get(id).copied().unwrap_or(CubeShape::Cube)
+    }
+
+    /// Generate the polygons for a cube id, for use as a lookup table function.
+    pub fn polygons(&self, id: u32) -> Vec<Polygon<Vertex>> {
+        self.shape(id).polygons()
+    }
+}
+
+fn shrink(v: Vertex) -> Vertex {
+    Vertex {
+        pos: [v.pos.x * PROXY_SIZE, v.pos.y * PROXY_SIZE, v.pos.z * PROXY_SIZE].into(),
+        normal: v.normal,
+    }
+}
+
+/// Unit normal of the plane through counter-clockwise points a, b and c
+fn normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    [n[0] / len, n[1] / len, n[2] / len]
+}
+
+/// Build a flat triangle from counter-clockwise points
+fn tri(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> Polygon<Vertex> {
+    let normal = normal(a, b, c);
+    let vert = |p: [f32; 3]| Vertex { pos: p.into(), normal: normal.into() };
+    Polygon::PolyTri(Triangle::new(vert(a), vert(b), vert(c)))
+}
+
+/// Build a flat quad from counter-clockwise, coplanar points
+fn quad(a: [f32; 3], b: [f32; 3], c: [f32; 3], d: [f32; 3]) -> Polygon<Vertex> {
+    let normal = normal(a, b, c);
+    debug_assert!((0..3).map(|i| (d[i] - a[i]) * normal[i]).sum::<f32>().abs() < 1e-4, "quad points must be coplanar");
+    let vert = |p: [f32; 3]| Vertex { pos: p.into(), normal: normal.into() };
+    Polygon::PolyQuad(Quad::new(vert(a), vert(b), vert(c), vert(d)))
+}
+
+/// Cylinder with the axle along x, with flat-shaded sides
+fn cylinder(segments: usize) -> Vec<Polygon<Vertex>> {
+    let point = |i: usize| {
+        let angle = (i % segments) as f32 / segments as f32 * std::f32::consts::TAU;
+        (angle.cos(), angle.sin())
+    };
+    let mut polys = Vec::with_capacity(segments * 3);
+    for i in 0..segments {
+        let (y0, z0) = point(i);
+        let (y1, z1) = point(i + 1);
+        polys.push(quad([-1.0, y0, z0], [-1.0, y1, z1], [1.0, y1, z1], [1.0, y0, z0])); // tread
+        polys.push(tri([-1.0, 0.0, 0.0], [-1.0, y1, z1], [-1.0, y0, z0])); // left cap
+        polys.push(tri([1.0, 0.0, 0.0], [1.0, y0, z0], [1.0, y1, z1])); // right cap
+    }
+    polys
+}
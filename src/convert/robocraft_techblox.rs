@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use chrono::naive::NaiveDate;
+use half::f16;
+use crate::robocraft;
+use crate::techblox::{GameSave, EntityHeader, BlockGroupEntity, SavedBlockGroupIdComponent, BlockGroupTransformEntityComponent,
+SerializedEntityDescriptor, SerializedFlyCamEntity, SerializedRigidBodyEntityStruct, SerializedPhysicsCameraEntity,
+SerializedCameraEntityStruct, UnityFloat3, UnityFloat4, UnityFloat4x4, UnityHalf3, UnityQuaternion};
+use crate::techblox::blocks::{Block, BlockEntity, DBEntityStruct, PositionEntityStruct, ScalingEntityStruct, RotationEntityStruct,
+SkewComponent, GridRotationStruct, SerializedGridConnectionsEntityStruct, SerializedBlockPlacementInfoStruct,
+SerializedCubeMaterialStruct, SerializedUniformBlockScaleEntityStruct, SerializedColourParameterEntityStruct,
+BlockGroupEntityComponent, SerializedGlobalWireSettingsEntity, GlobalWireSettingsEntityStruct};
+use crate::convert::{cube_rotation_to_quat, techblox_palette, PROVISIONAL_ROBOCRAFT_PALETTE};
+
+/// Size of a Techblox block, in metres
+const TECHBLOX_BLOCK_SIZE: f32 = 0.2;
+
+/// Ticks between 0001-01-01 and 1970-01-01, for .NET-style timestamps
+const TICKS_TO_UNIX_EPOCH: i64 = 621_355_968_000_000_000;
+
+/// Block database id of Techblox's standard cube.
+///
+/// Every block of tests/GameSave.Techblox (a 2021-05-05 game save built from standard cubes) has this dbid.
+pub const TECHBLOX_CUBE_DBID: u32 = 0;
+
+/// Game save version written by `robocraft_to_techblox(...)`, which is the version of tests/GameSave.Techblox
+const SAVE_VERSION: (i32, u32, u32) = (2021, 5, 5);
+
+// Entity group identifiers copied from tests/GameSave.Techblox (2021-05-05).
+// Other versions use different ids (2021-06-08 stores standard blocks in group 10677, for example),
+// so `robocraft_to_techblox_with_template(...)` takes them from the template instead.
+const GROUP_ID_BLOCK_GROUPS: u32 = 10102;
+const GROUP_ID_BLOCKS: u32 = 10062;
+const GROUP_ID_WIRE_SETTINGS: u32 = 10363;
+const GROUP_ID_FLYCAM: u32 = 11071;
+const GROUP_ID_PHYCAM: u32 = 11061;
+
+/// Options for `robocraft_to_techblox(...)`.
+#[derive(Clone, Debug)]
+pub struct TechbloxConvertOptions {
+    /// Techblox block database id used for Robocraft cube ids without an entry in `dbids`
+    pub default_dbid: u32,
+    /// Robocraft cube id to Techblox block database id mapping
+    pub dbids: HashMap<u32, u32>,
+    /// Robocraft colour to Techblox palette index mapping (indexed by `Cube.colour`).
+    /// Colours without an entry are converted to palette index 0.
+    /// By default, every colour is mapped to the nearest colour of `techblox_palette()`;
+    /// use `with_palette(...)` to map to the nearest colour of another palette.
+    pub colours: Vec<u8>,
+    /// Techblox material id for all blocks
+    pub material_id: u8,
+    /// Distance between neighbouring blocks, in metres
+    pub block_size: f32,
+}
+
+impl std::default::Default for TechbloxConvertOptions {
+    fn default() -> Self {
+        Self {
+            default_dbid: TECHBLOX_CUBE_DBID,
+            dbids: HashMap::new(),
+            colours: Vec::new(),
+            material_id: 0,
+            block_size: TECHBLOX_BLOCK_SIZE,
+        }.with_palette(&techblox_palette())
+    }
+}
+
+impl TechbloxConvertOptions {
+    /// Map every Robocraft colour to the nearest colour of a Techblox palette, given as sRGB colours indexed by palette index.
+    pub fn with_palette(mut self, palette: &[[u8; 3]]) -> Self {
+        self.colours = PROVISIONAL_ROBOCRAFT_PALETTE.iter()
+            .map(|(_, rgb)| nearest_colour(palette, *rgb))
+            .collect();
+        self
+    }
+}
+
+/// Convert a Robocraft robot into a Techblox game save.
+///
+/// Every cube becomes a standard block in its own block group, positioned on the Techblox grid using the cube's x/y/z coordinates.
+/// The game save uses the 2021-05-05 Techblox format; use `robocraft_to_techblox_with_template(...)` for other versions.
+pub fn robocraft_to_techblox(robot: &robocraft::Cubes, options: &TechbloxConvertOptions) -> GameSave {
+    let version = NaiveDate::from_ymd_opt(SAVE_VERSION.0, SAVE_VERSION.1, SAVE_VERSION.2).unwrap();
+    let centre = robot_centre(robot, options.block_size);
+    let camera_pos = UnityFloat3 { x: centre[0], y: centre[1] + 2.0, z: centre[2] - 4.0 };
+    let mut save = GameSave {
+        version,
+        ticks: now_ticks(),
+        cube_len: 0,
+        max_entity_id: 0,
+        group_len: 0,
+        group_headers: Vec::new(),
+        cube_groups: Vec::new(),
+        cube_headers: Vec::new(),
+        cube_entities: Vec::new(),
+        wire_len: 0,
+        wire_headers: Vec::new(),
+        wire_entities: Vec::new(),
+        wire_settings_header: EntityHeader::from_name(SerializedGlobalWireSettingsEntity::HASHNAME, 0, GROUP_ID_WIRE_SETTINGS,
+            SerializedGlobalWireSettingsEntity::serialized_components()),
+        wire_settings_entity: SerializedGlobalWireSettingsEntity {
+            settings_component: GlobalWireSettingsEntityStruct { obsolete: 0 },
+        },
+        flycam_header: EntityHeader::from_name(SerializedFlyCamEntity::HASHNAME, 0, GROUP_ID_FLYCAM,
+            SerializedFlyCamEntity::serialized_components()),
+        flycam_entity: SerializedFlyCamEntity {
+            rb_component: SerializedRigidBodyEntityStruct { position: camera_pos },
+        },
+        phycam_header: EntityHeader::from_name(SerializedPhysicsCameraEntity::HASHNAME, 0, GROUP_ID_PHYCAM,
+            SerializedPhysicsCameraEntity::serialized_components()),
+        phycam_entity: SerializedPhysicsCameraEntity {
+            cam_component: SerializedCameraEntityStruct {
+                position: UnityHalf3 { x: f16::from_f32(camera_pos.x), y: f16::from_f32(camera_pos.y), z: f16::from_f32(camera_pos.z) },
+                rotation: UnityHalf3 { x: f16::from_f32(20.0), y: f16::ZERO, z: f16::ZERO },
+            },
+        },
+    };
+    add_cubes(&mut save, robot, options, GROUP_ID_BLOCK_GROUPS, GROUP_ID_BLOCKS);
+    save
+}
+
+/// Convert a Robocraft robot into a Techblox game save, using the version and non-block data of an existing game save.
+///
+/// Blocks, block groups and wires in `template` are not copied.
+/// This is useful for creating game saves for a Techblox version other than the one used by `robocraft_to_techblox(...)`.
+pub fn robocraft_to_techblox_with_template(robot: &robocraft::Cubes, options: &TechbloxConvertOptions, template: &GameSave) -> GameSave {
+    let mut save = GameSave {
+        version: template.version,
+        ticks: now_ticks(),
+        cube_len: 0,
+        max_entity_id: 0,
+        group_len: 0,
+        group_headers: Vec::new(),
+        cube_groups: Vec::new(),
+        cube_headers: Vec::new(),
+        cube_entities: Vec::new(),
+        wire_len: 0,
+        wire_headers: Vec::new(),
+        wire_entities: Vec::new(),
+        wire_settings_header: template.wire_settings_header,
+        wire_settings_entity: template.wire_settings_entity,
+        flycam_header: template.flycam_header,
+        flycam_entity: template.flycam_entity,
+        phycam_header: template.phycam_header,
+        phycam_entity: template.phycam_entity,
+    };
+    let group_group_id = template.group_headers.first().map(|h| h.group_id).unwrap_or(GROUP_ID_BLOCK_GROUPS);
+    let block_group_id = template.cube_headers.first().map(|h| h.group_id).unwrap_or(GROUP_ID_BLOCKS);
+    add_cubes(&mut save, robot, options, group_group_id, block_group_id);
+    save
+}
+
+fn add_cubes(save: &mut GameSave, robot: &robocraft::Cubes, options: &TechbloxConvertOptions, group_group_id: u32, block_group_id: u32) {
+    for (i, cube) in robot.into_iter().enumerate() {
+        let block_group = i as i32 + 1;
+        let position = UnityFloat3 {
+            x: cube.x as f32 * options.block_size,
+            y: cube.y as f32 * options.block_size,
+            z: cube.z as f32 * options.block_size,
+        };
+        let q = cube_rotation_to_quat(cube.orientation);
+        let rotation = UnityQuaternion { value: UnityFloat4 { x: q.v.x, y: q.v.y, z: q.v.z, w: q.s } };
+        save.group_headers.push(EntityHeader::from_name(BlockGroupEntity::HASHNAME, block_group as u32, group_group_id,
+            BlockGroupEntity::serialized_components()));
+        save.cube_groups.push(BlockGroupEntity {
+            saved_block_group_id: SavedBlockGroupIdComponent { saved_block_group_id: block_group },
+            block_group_transform: BlockGroupTransformEntityComponent {
+                block_group_grid_position: position,
+                block_group_grid_rotation: rotation,
+            },
+        });
+        let block = BlockEntity {
+            db_component: DBEntityStruct { dbid: *options.dbids.get(&cube.id).unwrap_or(&options.default_dbid) },
+            pos_component: PositionEntityStruct { position },
+            scale_component: ScalingEntityStruct { scale: UnityFloat3 { x: 1.0, y: 1.0, z: 1.0 } },
+            rot_component: RotationEntityStruct { rotation },
+            skew_component: SkewComponent { skew_matrix: identity_matrix() },
+            grid_component: GridRotationStruct { rotation, position },
+            grid_conn_component: SerializedGridConnectionsEntityStruct {},
+            placement_component: SerializedBlockPlacementInfoStruct {},
+            material_component: SerializedCubeMaterialStruct { material_id: options.material_id },
+            uscale_component: SerializedUniformBlockScaleEntityStruct { scale_factor: 1 },
+            colour_component: SerializedColourParameterEntityStruct {
+                index_in_palette: *options.colours.get(cube.colour as usize).unwrap_or(&0),
+            },
+            group_component: BlockGroupEntityComponent { current_block_group: block_group },
+        };
+        save.cube_headers.push(EntityHeader::from_name(BlockEntity::HASHNAME, i as u32, block_group_id,
+            BlockEntity::serialized_components()));
+        save.cube_entities.push(Box::new(block) as Box<dyn Block>);
+    }
+    save.cube_len = save.cube_entities.len() as u32;
+    save.group_len = save.cube_groups.len() as u32;
+    save.max_entity_id = save.cube_len;
+}
+
+/// Index of the palette colour with the smallest squared distance to `rgb`
+fn nearest_colour(palette: &[[u8; 3]], rgb: [u8; 3]) -> u8 {
+    let mut best = (0, i32::MAX);
+    for (i, colour) in palette.iter().enumerate().take(u8::MAX as usize + 1) {
+        let distance: i32 = (0..3).map(|c| (colour[c] as i32 - rgb[c] as i32).pow(2)).sum();
+        if distance < best.1 {
+            best = (i as u8, distance);
+        }
+    }
+    best.0
+}
+
+fn identity_matrix() -> UnityFloat4x4 {
+    UnityFloat4x4 {
+        c0: UnityFloat4 { x: 1.0, y: 0.0, z: 0.0, w: 0.0 },
+        c1: UnityFloat4 { x: 0.0, y: 1.0, z: 0.0, w: 0.0 },
+        c2: UnityFloat4 { x: 0.0, y: 0.0, z: 1.0, w: 0.0 },
+        c3: UnityFloat4 { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
+    }
+}
+
+fn robot_centre(robot: &robocraft::Cubes, block_size: f32) -> [f32; 3] {
+    let mut sum = [0.0f32; 3];
+    for cube in robot.into_iter() {
+        sum[0] += cube.x as f32;
+        sum[1] += cube.y as f32;
+        sum[2] += cube.z as f32;
+    }
+    let count = robot.len().max(1) as f32;
+    [sum[0] / count * block_size, sum[1] / count * block_size, sum[2] / count * block_size]
+}
+
+/// Current time as .NET ticks (100ns intervals since 0001-01-01)
+fn now_ticks() -> i64 {
+    let since_epoch = chrono::Utc::now().timestamp_micros();
+    TICKS_TO_UNIX_EPOCH + since_epoch * 10
+}
@@ -0,0 +1,178 @@
+use std::io::{Error, ErrorKind};
+use crate::robocraft;
+use crate::convert::{PROVISIONAL_ROBOCRAFT_PALETTE, FALLBACK_COLOUR};
+
+const VOX_MAGIC: &[u8; 4] = b"VOX ";
+const VOX_VERSION: i32 = 150;
+
+/// Options for `vox_to_cubes(...)`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VoxImportOptions {
+    /// Cube id used for every voxel, since .vox files only store colours
+    pub cube_id: u32,
+    /// Orientation used for every voxel
+    pub orientation: u8,
+}
+
+/// Convert a Robocraft robot to a MagicaVoxel .vox file.
+///
+/// Robocraft colours are stored in the .vox palette, so colour `c` becomes palette index `c + 1`.
+/// MagicaVoxel is z-up while Robocraft is y-up, so the y and z axes are swapped.
+/// Cube ids and orientations are not stored.
+/// Robocraft coordinates are 0 to 255, so every robot fits in MagicaVoxel's maximum model size of 256.
+pub fn cubes_to_vox(robot: &robocraft::Cubes) -> Vec<u8> {
+    let mut size = [1i32; 3];
+    let mut xyzi = Vec::with_capacity(4 + robot.len() * 4);
+    xyzi.extend(&(robot.len() as u32).to_le_bytes());
+    for cube in robot.into_iter() {
+        let voxel = [cube.x, cube.z, cube.y];
+        for axis in 0..3 {
+            size[axis] = size[axis].max(voxel[axis] as i32 + 1);
+        }
+        let colour = if (cube.colour as usize) < PROVISIONAL_ROBOCRAFT_PALETTE.len() { cube.colour } else { FALLBACK_COLOUR };
+        xyzi.extend(&[voxel[0], voxel[1], voxel[2], colour + 1]);
+    }
+    let mut size_chunk = Vec::with_capacity(12);
+    for axis in size.iter() {
+        size_chunk.extend(&axis.to_le_bytes());
+    }
+    let mut rgba = Vec::with_capacity(256 * 4);
+    for i in 0..256 {
+        match PROVISIONAL_ROBOCRAFT_PALETTE.get(i) {
+            Some((_, rgb)) => rgba.extend(&[rgb[0], rgb[1], rgb[2], 255]),
+            None => rgba.extend(&[0, 0, 0, 255]),
+        }
+    }
+    let mut children = Vec::new();
+    write_chunk(&mut children, b"SIZE", &size_chunk, &[]);
+    write_chunk(&mut children, b"XYZI", &xyzi, &[]);
+    write_chunk(&mut children, b"RGBA", &rgba, &[]);
+    let mut out = Vec::new();
+    out.extend(VOX_MAGIC);
+    out.extend(&VOX_VERSION.to_le_bytes());
+    write_chunk(&mut out, b"MAIN", &[], &children);
+    out
+}
+
+/// Convert a MagicaVoxel .vox file to a Robocraft robot.
+///
+/// Only the first model in the file is converted.
+/// Voxel colours are matched to the closest Robocraft colour using the file's palette,
+/// or MagicaVoxel's default palette for files without one.
+pub fn vox_to_cubes(data: &[u8], options: &VoxImportOptions) -> std::io::Result<robocraft::Cubes> {
+    if data.len() < 8 || &data[0..4] != VOX_MAGIC {
+        return Err(Error::new(ErrorKind::InvalidData, "Not a MagicaVoxel .vox file"));
+    }
+    let (id, _, children, _) = read_chunk(&data[8..])?;
+    if &id != b"MAIN" {
+        return Err(Error::new(ErrorKind::InvalidData, "Missing MAIN chunk"));
+    }
+    let mut voxels: Option<&[u8]> = None;
+    let default_palette = default_palette();
+    let mut palette: &[u8] = &default_palette;
+    let mut remaining = children;
+    while !remaining.is_empty() {
+        let (id, content, _, rest) = read_chunk(remaining)?;
+        match &id {
+            b"XYZI" if voxels.is_none() => voxels = Some(content),
+            b"RGBA" => palette = content,
+            _ => {},
+        }
+        remaining = rest;
+    }
+    let voxels = voxels.ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing XYZI chunk"))?;
+    let count = read_u32(voxels, 0)? as usize;
+    if voxels.len() < 4 + count * 4 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "XYZI chunk is too short"));
+    }
+    let mut cubes = Vec::with_capacity(count);
+    for voxel in voxels[4..4 + count * 4].chunks_exact(4) {
+        cubes.push(robocraft::Cube {
+            id: options.cube_id,
+            x: voxel[0],
+            y: voxel[2],
+            z: voxel[1],
+            orientation: options.orientation,
+            colour: closest_colour(palette, voxel[3]),
+        });
+    }
+    Ok(robocraft::Cubes::from(cubes))
+}
+
+/// Find the Robocraft colour nearest to a .vox palette colour
+fn closest_colour(palette: &[u8], index: u8) -> u8 {
+    let i = (index as usize).saturating_sub(1) * 4; // palette entry i is colour index i + 1
+    if palette.len() < i + 3 {
+        return FALLBACK_COLOUR;
+    }
+    let target = [palette[i] as i32, palette[i + 1] as i32, palette[i + 2] as i32];
+    let mut best = (0, i32::MAX);
+    for (colour, (_, rgb)) in PROVISIONAL_ROBOCRAFT_PALETTE.iter().enumerate() {
+        let distance: i32 = (0..3).map(|c| (rgb[c] as i32 - target[c]).pow(2)).sum();
+        if distance < best.1 {
+            best = (colour as u8, distance);
+        }
+    }
+    best.0
+}
+
+/// MagicaVoxel's default palette, in the layout of an RGBA chunk.
+///
+/// As listed in the .vox file format specification, colour indices 1 to 215 are a 6x6x6 colour cube
+/// (red, then green, then blue from 0xff down to 0x00, without black),
+/// followed by red, green, blue and grey ramps of the remaining multiples of 0x11 (from 0xee down to 0x11).
+fn default_palette() -> [u8; 1024] {
+    const CUBE: [u8; 6] = [0xff, 0xcc, 0x99, 0x66, 0x33, 0x00];
+    const RAMP: [u8; 10] = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+    let mut colours = Vec::with_capacity(256);
+    for r in CUBE {
+        for g in CUBE {
+            for b in CUBE {
+                colours.push([r, g, b]);
+            }
+        }
+    }
+    colours.pop(); // black
+    colours.extend(RAMP.iter().map(|&v| [v, 0, 0]));
+    colours.extend(RAMP.iter().map(|&v| [0, v, 0]));
+    colours.extend(RAMP.iter().map(|&v| [0, 0, v]));
+    colours.extend(RAMP.iter().map(|&v| [v, v, v]));
+    let mut rgba = [0u8; 1024];
+    for (i, rgb) in colours.iter().enumerate() {
+        rgba[i * 4..i * 4 + 3].copy_from_slice(rgb);
+        rgba[i * 4 + 3] = 255;
+    }
+    rgba
+}
+
+fn write_chunk(out: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: &[u8]) {
+    out.extend(id);
+    out.extend(&(content.len() as u32).to_le_bytes());
+    out.extend(&(children.len() as u32).to_le_bytes());
+    out.extend(content);
+    out.extend(children);
+}
+
+/// Chunk id, content, children and the data after the chunk
+type Chunk<'a> = ([u8; 4], &'a [u8], &'a [u8], &'a [u8]);
+
+fn read_chunk(data: &[u8]) -> std::io::Result<Chunk<'_>> {
+    if data.len() < 12 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Chunk header is too short"));
+    }
+    let id = [data[0], data[1], data[2], data[3]];
+    let content_len = read_u32(data, 4)? as usize;
+    let children_len = read_u32(data, 8)? as usize;
+    let end = 12 + content_len + children_len;
+    if data.len() < end {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Chunk is too short"));
+    }
+    Ok((id, &data[12..12 + content_len], &data[12 + content_len..end], &data[end..]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> std::io::Result<u32> {
+    match data.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(Error::new(ErrorKind::UnexpectedEof, "Unexpected end of data")),
+    }
+}
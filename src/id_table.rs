@@ -0,0 +1,92 @@
+//! Lookup tables from game ids to per-id data.
+//!
+//! `robocraft::CubeTable`, `robocraft2::BlockTable` and `convert::ShapeLibrary` are all `IdTable`s,
+//! with extra functions for the kind of data they hold.
+use std::collections::HashMap;
+
+/// Lookup table from cube or block ids to values, with an optional fallback value for unregistered ids.
+#[derive(Clone, Debug)]
+pub struct IdTable<V> {
+    values: HashMap<u32, V>,
+    fallback: Option<V>,
+}
+
+impl<V> IdTable<V> {
+    /// Create a new table, without any registered ids or fallback value.
+    pub fn new() -> Self {
+        Self {
+            values: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Create a table from (id, value) pairs.
+    pub fn from_pairs(pairs: &[(u32, V)]) -> Self where V: Clone {
+        pairs.iter().cloned().collect()
+    }
+
+    /// Register the value of an id, returning the value previously registered for the id.
+    pub fn insert(&mut self, id: u32, value: V) -> Option<V> {
+        self.values.insert(id, value)
+    }
+
+    /// Remove the value registered for an id.
+    pub fn remove(&mut self, id: u32) -> Option<V> {
+        self.values.remove(&id)
+    }
+
+    /// Set the value used for unregistered ids.
+    pub fn set_default(&mut self, value: V) {
+        self.fallback = Some(value);
+    }
+
+    /// Remove the value used for unregistered ids, returning it.
+    pub fn clear_default(&mut self) -> Option<V> {
+        self.fallback.take()
+    }
+
+    /// Get the value of an id, or the fallback value when the id is not registered.
+    pub fn get(&self, id: u32) -> Option<&V> {
+        self.values.get(&id).or(self.fallback.as_ref())
+    }
+
+    /// Returns true when a value is registered for the id (ignoring the fallback value).
+    pub fn contains(&self, id: u32) -> bool {
+        self.values.contains_key(&id)
+    }
+
+    /// Amount of registered ids
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true when no ids are registered
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterate over the registered (id, value) pairs, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &V)> {
+        self.values.iter().map(|(id, value)| (*id, value))
+    }
+}
+
+impl<V> std::default::Default for IdTable<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> std::iter::FromIterator<(u32, V)> for IdTable<V> {
+    fn from_iter<I: IntoIterator<Item = (u32, V)>>(iter: I) -> Self {
+        let mut table = Self::new();
+        table.extend(iter);
+        table
+    }
+}
+
+impl<V> std::iter::Extend<(u32, V)> for IdTable<V> {
+    fn extend<I: IntoIterator<Item = (u32, V)>>(&mut self, iter: I) {
+        self.values.extend(iter);
+    }
+}
@@ -0,0 +1,11 @@
+use chrono::{DateTime, NaiveDateTime, ParseError, Utc};
+
+/// Parse an ISO 8601 date and time, as used in API responses.
+///
+/// Dates without a timezone (which the Robocraft CRF returns) are treated as UTC.
+pub(crate) fn parse_iso_datetime(date: &str) -> Result<DateTime<Utc>, ParseError> {
+    match DateTime::parse_from_rfc3339(date) {
+        Ok(d) => Ok(d.with_timezone(&Utc)),
+        Err(_) => NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f").map(|d| d.and_utc()),
+    }
+}
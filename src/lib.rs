@@ -1,6 +1,10 @@
 //! An unofficial collection of APIs used in Robocraft and Cardlife.
 //!
 //! This crate is WIP, but the available APIs are tested and very usable.
+
+// allows code generated by #[derive(EntityDescriptor)] to use `::libfj` paths inside this crate too
+#[cfg(feature = "techblox")]
+extern crate self as libfj;
 #[cfg(feature = "cardlife")]
 pub mod cardlife;
 #[cfg(all(feature = "simple", feature = "cardlife"))]
@@ -15,5 +19,17 @@ pub mod techblox;
 pub mod convert;
 #[cfg(feature = "robocraft2")]
 pub mod robocraft2;
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+pub mod robocraft2_simple;
+#[cfg(any(feature = "robocraft", feature = "robocraft2"))]
+pub mod id_table;
+#[cfg(any(feature = "robocraft", feature = "robocraft2", feature = "cardlife"))]
+pub mod retry;
+#[cfg(any(feature = "robocraft", feature = "robocraft2"))]
+pub mod cache;
+#[cfg(any(feature = "robocraft", feature = "robocraft2"))]
+mod paging;
+#[cfg(any(feature = "robocraft2", all(feature = "robocraft", feature = "chrono")))]
+mod iso_date;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,72 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+
+/// Amount of pages in a row without any new items before paging stops
+const STALE_PAGE_LIMIT: usize = 2;
+
+/// Page walker for paginated searches, which removes items already seen on earlier pages.
+///
+/// Paging stops on an empty page, or after `STALE_PAGE_LIMIT` pages without any new items,
+/// since some APIs ignore the page number or clamp it to the last page.
+pub(crate) struct Pager<T, K> {
+    page: isize,
+    seen: HashSet<K>,
+    buffer: VecDeque<T>,
+    stale_pages: usize,
+    done: bool,
+    key: fn(&T) -> K,
+}
+
+impl<T, K: Hash + Eq> Pager<T, K> {
+    pub fn new(first_page: isize, key: fn(&T) -> K) -> Self {
+        Self {
+            page: first_page,
+            seen: HashSet::new(),
+            buffer: VecDeque::new(),
+            stale_pages: 0,
+            done: false,
+            key,
+        }
+    }
+
+    /// Page number to retrieve next
+    pub fn page(&self) -> isize {
+        self.page
+    }
+
+    /// Take the next item from the last retrieved page
+    pub fn next_item(&mut self) -> Option<T> {
+        self.buffer.pop_front()
+    }
+
+    /// Returns true when no more pages should be retrieved
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Add the items of the page returned by `page()`
+    pub fn add_page(&mut self, items: Vec<T>) {
+        self.page += 1;
+        if items.is_empty() {
+            self.done = true;
+            return;
+        }
+        let before = self.buffer.len();
+        for item in items {
+            if self.seen.insert((self.key)(&item)) {
+                self.buffer.push_back(item);
+            }
+        }
+        if self.buffer.len() == before {
+            self.stale_pages += 1;
+            self.done = self.stale_pages >= STALE_PAGE_LIMIT;
+        } else {
+            self.stale_pages = 0;
+        }
+    }
+
+    /// Stop retrieving pages, e.g. after an error
+    pub fn stop(&mut self) {
+        self.done = true;
+    }
+}
@@ -0,0 +1,335 @@
+//! Retries, exponential backoff and rate limiting for HTTP requests.
+//!
+//! Every API client (`robocraft::FactoryAPI`, `robocraft2::FactoryAPI`, `robocraft2::PortalTokenProvider`, `cardlife::LiveAPI`
+//! and `cardlife::CLreServer`, and their blocking equivalents in the `*_simple` modules)
+//! sends its requests through a `RequestPolicy`, which can be replaced with the client's `with_policy(...)` function.
+//! The default policy does not retry, so retries have to be enabled with `RequestPolicy::retry(...)`.
+//! To share a requests-per-second budget between clients, give them clones of the same `RateLimiter`.
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use reqwest::{RequestBuilder, Response, Error, StatusCode};
+
+/// Retry behaviour for failed requests.
+///
+/// Only idempotent requests are retried, after a connection error, a timeout,
+/// or a `429 Too Many Requests`, `500`, `502`, `503` or `504` response.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum amount of retries after the first attempt (0 disables retries)
+    pub max_retries: u32,
+    /// Delay before the first retry, which doubles for every following retry
+    pub base_delay: Duration,
+    /// Maximum delay between attempts.
+    /// When a `Retry-After` header asks for a longer delay, the response is returned instead of retrying.
+    pub max_delay: Duration,
+    /// Randomise delays between half and all of the calculated backoff, so concurrent tasks do not retry in lockstep
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Create a new retry policy, with the default settings (up to 3 retries, starting with a 500ms delay).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a retry policy which never retries.
+    pub fn never() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Set the maximum amount of retries
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set the maximum delay between attempts
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Enable or disable random jitter
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Delay before retry number `retry` (starting at 0), without a `Retry-After` header.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.base_delay
+            .checked_mul(2u32.saturating_pow(retry))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        if self.jitter {
+            delay.mul_f64(0.5 + random_fraction() / 2.0)
+        } else {
+            delay
+        }
+    }
+
+    /// Delay before retrying a request which got a response with `status`,
+    /// or None when the response should not be retried.
+    ///
+    /// `retry_after` is the value of the response's `Retry-After` header, in seconds or as an HTTP date.
+    pub fn retry_delay(&self, retry: u32, status: u16, retry_after: Option<&str>) -> Option<Duration> {
+        if retry >= self.max_retries || !is_retryable_status(status) {
+            return None;
+        }
+        match retry_after.and_then(parse_retry_after) {
+            Some(delay) if delay > self.max_delay => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(retry)),
+        }
+    }
+}
+
+impl std::default::Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+/// Requests-per-second budget, shared by every clone of the limiter.
+///
+/// Requests are spaced out evenly, and requests over budget wait for their turn instead of failing.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter, which allows `requests` requests per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is not a finite number greater than zero,
+    /// or is so small that the interval between requests does not fit in a `Duration`.
+    pub fn per_second(requests: f64) -> Self {
+        assert!(requests.is_finite() && requests > 0.0, "requests per second must be finite and greater than zero, got {}", requests);
+        let interval = Duration::try_from_secs_f64(1.0 / requests)
+            .expect("requests per second is too small");
+        Self::with_interval(interval)
+    }
+
+    /// Create a new rate limiter, which allows one request every `interval`.
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Minimum time between requests
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Reserve the next request slot, returning how long to wait before sending the request.
+    pub fn reserve(&self) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next.lock().unwrap();
+        let slot = match *next {
+            Some(instant) if instant > now => instant,
+            _ => now,
+        };
+        *next = Some(slot + self.interval);
+        slot - now
+    }
+
+    /// Wait until the next request may be sent.
+    pub async fn acquire(&self) {
+        let delay = self.reserve();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Block the current thread until the next request may be sent.
+    pub fn acquire_blocking(&self) {
+        let delay = self.reserve();
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Retry policy and (optional) rate limit used by an API client.
+///
+/// The default policy never retries and has no rate limit, so requests are sent exactly once.
+#[derive(Clone, Debug)]
+pub struct RequestPolicy {
+    /// Retry behaviour for failed idempotent requests (`RetryPolicy::never()` by default)
+    pub retry: RetryPolicy,
+    /// Rate limit applied to every attempt, including retries
+    pub rate_limit: Option<RateLimiter>,
+}
+
+impl RequestPolicy {
+    /// Create a new request policy, without retries or a rate limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the retry policy
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the rate limiter
+    pub fn rate_limit(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limit = Some(limiter);
+        self
+    }
+
+    /// Send a request, waiting for the rate limiter and retrying as allowed by the retry policy.
+    ///
+    /// Non-idempotent requests (and requests whose body cannot be cloned) are only attempted once.
+    /// The last response is returned as-is, even if it is unsuccessful.
+    pub(crate) async fn send(&self, mut request: RequestBuilder, idempotent: bool) -> Result<Response, Error> {
+        let mut retry = 0;
+        loop {
+            let next = if idempotent && retry < self.retry.max_retries {
+                request.try_clone()
+            } else {
+                None
+            };
+            if let Some(limiter) = &self.rate_limit {
+                limiter.acquire().await;
+            }
+            let result = request.send().await;
+            let next = match next {
+                Some(next) => next,
+                None => return result,
+            };
+            let delay = match &result {
+                Ok(response) => {
+                    let retry_after = response.headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok());
+                    match self.retry.retry_delay(retry, response.status().as_u16(), retry_after) {
+                        Some(delay) => delay,
+                        None => return result,
+                    }
+                },
+                Err(e) if e.is_connect() || e.is_timeout() => self.retry.backoff(retry),
+                Err(_) => return result,
+            };
+            tokio::time::sleep(delay).await;
+            request = next;
+            retry += 1;
+        }
+    }
+
+    /// Blocking equivalent of `send(...)`, used by the `ureq` clients.
+    ///
+    /// A copy of `request` is sent for every attempt, with `body` (if any) as the request body.
+    #[cfg(feature = "simple")]
+    pub(crate) fn send_blocking(&self, request: &ureq::Request, body: Option<&str>, idempotent: bool) -> Result<ureq::Response, Box<ureq::Error>> {
+        let mut retry = 0;
+        loop {
+            if let Some(limiter) = &self.rate_limit {
+                limiter.acquire_blocking();
+            }
+            let result = match body {
+                Some(body) => request.clone().send_string(body),
+                None => request.clone().call(),
+            }.map_err(Box::new);
+            if !idempotent || retry >= self.retry.max_retries {
+                return result;
+            }
+            let delay = match result.as_ref().map_err(|e| e.as_ref()) {
+                Err(ureq::Error::Status(status, response)) => {
+                    match self.retry.retry_delay(retry, *status, response.header("Retry-After")) {
+                        Some(delay) => delay,
+                        None => return result,
+                    }
+                },
+                Err(ureq::Error::Transport(e)) if matches!(e.kind(), ureq::ErrorKind::ConnectionFailed | ureq::ErrorKind::Dns) => self.retry.backoff(retry),
+                _ => return result,
+            };
+            std::thread::sleep(delay);
+            retry += 1;
+        }
+    }
+}
+
+impl std::default::Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::never(),
+            rate_limit: None,
+        }
+    }
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    matches!(StatusCode::from_u16(status),
+        Ok(StatusCode::TOO_MANY_REQUESTS)
+        | Ok(StatusCode::INTERNAL_SERVER_ERROR)
+        | Ok(StatusCode::BAD_GATEWAY)
+        | Ok(StatusCode::SERVICE_UNAVAILABLE)
+        | Ok(StatusCode::GATEWAY_TIMEOUT))
+}
+
+/// Parse a `Retry-After` header value, which is either an amount of seconds or an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    parse_http_date(value).map(|date| date.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+}
+
+/// Parse an IMF-fixdate, like `Sun, 06 Nov 1994 08:49:37 GMT`
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let mut parts = value.split_once(", ")?.1.split(' ');
+    let day: u64 = parts.next()?.parse().ok()?;
+    let month_name = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month_name)? as u64 + 1;
+    let year: u64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':').map(|x| x.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || year < 1970 || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    // days since the epoch, from Howard Hinnant's days_from_civil algorithm
+    let (y, m) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era = y / 400;
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86400 + hour * 3600 + minute * 60 + second))
+}
+
+/// Pseudo-random number between 0.0 and 1.0, which is good enough for jitter
+fn random_fraction() -> f64 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+    // splitmix64
+    let mut x = STATE.fetch_add(0x9E3779B97F4A7C15, Ordering::Relaxed) ^ seed;
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^= x >> 31;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use base64::Engine;
+use serde::de::Error;
+
+use crate::robocraft::{Cubes, FactoryRobotGetInfo};
+use crate::id_table::IdTable;
+
+/// Parse the `cube_amounts` JSON string of a CRF robot into a cube id to cube count map.
+pub fn parse_cube_amounts(cube_amounts: &str) -> Result<HashMap<u32, u32>, serde_json::Error> {
+    let raw: HashMap<String, u32> = serde_json::from_str(cube_amounts)?;
+    raw.into_iter()
+        .map(|(id, count)| id.parse::<u32>()
+            .map(|id| (id, count))
+            .map_err(|_| serde_json::Error::custom(format!("invalid cube id `{}`", id))))
+        .collect()
+}
+
+/// Count the cubes of every cube id in a robot.
+pub fn cube_counts(robot: &Cubes) -> HashMap<u32, u32> {
+    let mut counts = HashMap::new();
+    for cube in robot.iter() {
+        *counts.entry(cube.id).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Find groups of cubes which are connected to each other by their faces.
+///
+/// Each island is a list of cube indices (see `Cubes.get(...)`), and islands are sorted from largest to smallest.
+/// A valid robot has exactly one island.
+/// Only positions are considered, so cubes which only touch with a sloped face are still treated as connected.
+pub fn islands(robot: &Cubes) -> Vec<Vec<usize>> {
+    let mut positions: HashMap<[i32; 3], Vec<usize>> = HashMap::new();
+    for (i, cube) in robot.iter().enumerate() {
+        positions.entry([cube.x as i32, cube.y as i32, cube.z as i32]).or_default().push(i);
+    }
+    let mut visited = vec![false; robot.len()];
+    let mut islands = Vec::new();
+    for start in 0..robot.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut island = Vec::new();
+        let mut queue = VecDeque::from(vec![start]);
+        while let Some(i) = queue.pop_front() {
+            island.push(i);
+            let cube = robot.get(i).unwrap();
+            let pos = [cube.x as i32, cube.y as i32, cube.z as i32];
+            let mut neighbours = vec![pos];
+            for axis in 0..3 {
+                for delta in [-1, 1] {
+                    let mut neighbour = pos;
+                    neighbour[axis] += delta;
+                    neighbours.push(neighbour);
+                }
+            }
+            for neighbour in neighbours {
+                for &j in positions.get(&neighbour).into_iter().flatten() {
+                    if !visited[j] {
+                        visited[j] = true;
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+        island.sort_unstable();
+        islands.push(island);
+    }
+    islands.sort_by_key(|island| std::cmp::Reverse(island.len()));
+    islands
+}
+
+/// CPU and robot ranking of a single cube.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct CubeStats {
+    /// CPU used by the cube
+    pub cpu: u32,
+    /// Robot ranking (RR) added by the cube
+    pub robot_ranking: u32,
+}
+
+/// Per-cube-id lookup table of cube stats, used for estimating robot CPU and ranking.
+///
+/// No cube stats are built in, since they changed many times during Robocraft's life
+/// and libfj has no record of the values used by the final version of the game.
+pub type CubeTable = IdTable<CubeStats>;
+
+impl IdTable<CubeStats> {
+    /// Estimate the CPU and robot ranking of a robot.
+    pub fn estimate(&self, robot: &Cubes) -> RobotEstimate {
+        self.estimate_counts(&cube_counts(robot))
+    }
+
+    /// Estimate the CPU and robot ranking from cube counts, like those from `parse_cube_amounts(...)` or `cube_counts(...)`.
+    pub fn estimate_counts(&self, counts: &HashMap<u32, u32>) -> RobotEstimate {
+        let mut estimate = RobotEstimate::default();
+        for (id, count) in counts.iter() {
+            match self.get(*id) {
+                Some(stats) => {
+                    estimate.cpu += stats.cpu as u64 * *count as u64;
+                    estimate.robot_ranking += stats.robot_ranking as u64 * *count as u64;
+                },
+                None => {
+                    estimate.unknown_cubes.insert(*id, *count);
+                },
+            }
+        }
+        estimate
+    }
+}
+
+/// Estimated CPU and robot ranking of a robot.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RobotEstimate {
+    /// Total CPU of all known cubes
+    pub cpu: u64,
+    /// Total robot ranking (RR) of all known cubes
+    pub robot_ranking: u64,
+    /// Counts of cube ids which are not in the cube table, which are not included in the totals
+    pub unknown_cubes: HashMap<u32, u32>,
+}
+
+impl RobotEstimate {
+    /// Returns true when every cube was in the cube table, so the totals are complete.
+    pub fn is_complete(&self) -> bool {
+        self.unknown_cubes.is_empty()
+    }
+}
+
+/// A problem found in a CRF robot by `analyse_robot(...)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RobotIssue {
+    /// The cube or colour data could not be decoded
+    InvalidCubeData,
+    /// The cube count stored in the cube data does not match the amount of cubes
+    LengthMismatch {
+        /// Cube count stored in the cube data
+        provided: u32,
+        /// Amount of cubes decoded
+        parsed: usize,
+    },
+    /// Multiple cubes are at the same position
+    OverlappingCubes {
+        /// Amount of cubes sharing a position with an earlier cube
+        count: usize,
+    },
+    /// Cubes are not all connected to each other
+    Disconnected {
+        /// Amount of separate cube islands
+        islands: usize,
+    },
+    /// The `cube_amounts` field could not be parsed
+    InvalidCubeAmounts,
+    /// The `cube_amounts` field does not match the decoded cubes
+    CubeAmountsMismatch,
+    /// The robot's CPU does not match the CPU estimated from its cubes
+    CpuMismatch {
+        /// CPU according to the CRF
+        reported: usize,
+        /// CPU estimated from the robot's cubes
+        estimated: u64,
+    },
+}
+
+/// Analysis results of a CRF robot.
+#[derive(Clone, Debug)]
+pub struct RobotAnalysis {
+    /// Decoded cubes, if the cube data is valid
+    pub cubes: Option<Cubes>,
+    /// Count of every cube id in the decoded cubes
+    pub cube_counts: HashMap<u32, u32>,
+    /// Parsed `cube_amounts` field, if it is valid
+    pub cube_amounts: Option<HashMap<u32, u32>>,
+    /// Estimated CPU and robot ranking of the decoded cubes
+    pub estimate: RobotEstimate,
+    /// Amount of separate cube islands
+    pub island_count: usize,
+    /// Problems found with the robot
+    pub issues: Vec<RobotIssue>,
+}
+
+impl RobotAnalysis {
+    /// Returns true when no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Decode and check a CRF robot, to find broken or suspicious uploads.
+///
+/// The CPU is only checked when every cube id of the robot is in `table`.
+/// The `cube_amounts` field is compared to the cube counts of every cube id it contains,
+/// since it does not always include every cube id.
+pub fn analyse_robot(info: &FactoryRobotGetInfo, table: &CubeTable) -> RobotAnalysis {
+    let mut issues = Vec::new();
+    let cubes = decode_cubes(info);
+    if cubes.is_none() {
+        issues.push(RobotIssue::InvalidCubeData);
+    }
+    let cube_counts = cubes.as_ref().map(cube_counts).unwrap_or_default();
+    let estimate = table.estimate_counts(&cube_counts);
+    let mut island_count = 0;
+    if let Some(cubes) = &cubes {
+        if cubes.provided_len as usize != cubes.len() {
+            issues.push(RobotIssue::LengthMismatch { provided: cubes.provided_len, parsed: cubes.len() });
+        }
+        let mut positions = HashSet::new();
+        let overlapping = cubes.iter().filter(|c| !positions.insert([c.x, c.y, c.z])).count();
+        if overlapping != 0 {
+            issues.push(RobotIssue::OverlappingCubes { count: overlapping });
+        }
+        island_count = islands(cubes).len();
+        if island_count > 1 {
+            issues.push(RobotIssue::Disconnected { islands: island_count });
+        }
+        if estimate.is_complete() && !cube_counts.is_empty() && estimate.cpu != info.cpu as u64 {
+            issues.push(RobotIssue::CpuMismatch { reported: info.cpu, estimated: estimate.cpu });
+        }
+    }
+    let cube_amounts = info.cube_amounts_map().ok();
+    match &cube_amounts {
+        Some(amounts) => {
+            if cubes.is_some() && amounts.iter().any(|(id, count)| cube_counts.get(id).copied().unwrap_or(0) != *count) {
+                issues.push(RobotIssue::CubeAmountsMismatch);
+            }
+        },
+        None => issues.push(RobotIssue::InvalidCubeAmounts),
+    }
+    RobotAnalysis {
+        cubes,
+        cube_counts,
+        cube_amounts,
+        estimate,
+        island_count,
+        issues,
+    }
+}
+
+fn decode_cubes(info: &FactoryRobotGetInfo) -> Option<Cubes> {
+    let mut cube_buf = base64::engine::general_purpose::STANDARD.decode(&info.cube_data).ok()?;
+    let mut colour_buf = base64::engine::general_purpose::STANDARD.decode(&info.colour_data).ok()?;
+    Cubes::parse(&mut cube_buf, &mut colour_buf).ok()
+}
@@ -3,13 +3,85 @@ use base64::Engine;
 
 // TODO(maybe) parse iteratively instead of one-shot
 
+/// Rotation matrices (row-major) of Robocraft's 24 orientations, indexed by orientation.
+///
+/// This is also the table used by `convert::cube_rotation_to_quat(...)`.
+/// Each of the 24 possible orientations appears exactly once.
+/// Entries 8, 10, 11, 12, 16, 18 and 23 repeated an earlier orientation in libfj 0.7.5's table
+/// (6, 9, 5, 7, 14, 9 and 20 respectively), so they were replaced by the seven rotations missing from it.
+/// The replacements are ordered by their Euler angles, not checked against in-game robots;
+/// all other entries are unchanged.
+pub(crate) const ORIENTATION_MATRICES: [[[i32; 3]; 3]; 24] = [
+    [[1, 0, 0], [0, 1, 0], [0, 0, 1]], // 0
+    [[0, -1, 0], [1, 0, 0], [0, 0, 1]],
+    [[-1, 0, 0], [0, -1, 0], [0, 0, 1]], // 2
+    [[0, 1, 0], [-1, 0, 0], [0, 0, 1]],
+    [[0, 0, 1], [0, 1, 0], [-1, 0, 0]], // 4
+    [[0, 0, 1], [1, 0, 0], [0, 1, 0]],
+    [[0, 0, -1], [1, 0, 0], [0, -1, 0]], // 6
+    [[0, 0, 1], [-1, 0, 0], [0, -1, 0]],
+    [[0, 0, -1], [0, -1, 0], [-1, 0, 0]], // 8
+    [[0, 0, -1], [-1, 0, 0], [0, 1, 0]],
+    [[0, 1, 0], [0, 0, -1], [-1, 0, 0]], // 10
+    [[0, -1, 0], [0, 0, -1], [1, 0, 0]],
+    [[0, 1, 0], [0, 0, 1], [1, 0, 0]], // 12
+    [[0, 0, 1], [0, -1, 0], [1, 0, 0]],
+    [[-1, 0, 0], [0, 1, 0], [0, 0, -1]], // 14
+    [[0, 1, 0], [1, 0, 0], [0, 0, -1]],
+    [[0, -1, 0], [-1, 0, 0], [0, 0, -1]], // 16
+    [[1, 0, 0], [0, -1, 0], [0, 0, -1]],
+    [[0, -1, 0], [0, 0, 1], [-1, 0, 0]], // 18
+    [[0, 0, -1], [0, 1, 0], [1, 0, 0]],
+    [[-1, 0, 0], [0, 0, -1], [0, -1, 0]], // 20
+    [[-1, 0, 0], [0, 0, 1], [0, 1, 0]],
+    [[1, 0, 0], [0, 0, 1], [0, -1, 0]], // 22
+    [[1, 0, 0], [0, 0, -1], [0, 1, 0]], // 23
+];
+
+type Matrix = [[i32; 3]; 3];
+
+/// An axis of a Robocraft robot, using the same directions as `Cube` positions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeAxis {
+    /// Right to left, as seen from the front of the garage bay (see `Cube`)
+    X,
+    /// Bottom to top
+    Y,
+    /// Back to front
+    Z,
+}
+
+impl CubeAxis {
+    fn index(&self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+        }
+    }
+}
+
+/// Error for edits which would move a cube outside of the 0 to 255 position range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CubeOutOfRange;
+
+impl std::fmt::Display for CubeOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cube position out of range")
+    }
+}
+
+impl std::error::Error for CubeOutOfRange {}
+
 /// A collection of cube data
 ///
 /// This holds all data parsed from cube_data and colour_data. 
-/// Individual Cube structs can be iterated through.
-#[derive(Clone)]
+/// Individual Cube structs can be iterated through and edited.
+#[derive(Clone, Debug)]
 pub struct Cubes {
     /// Parsed cube count (the first 32 bits of data parsed to `u32`)
+    ///
+    /// All methods which add or remove cubes set this to the actual amount of cubes.
     pub provided_len: u32,
     cubes: Vec<Cube>,
 }
@@ -101,6 +173,235 @@ impl Cubes {
     pub fn len(&self) -> usize {
         self.cubes.len()
     }
+
+    /// Returns true when there are no cubes.
+    pub fn is_empty(&self) -> bool {
+        self.cubes.is_empty()
+    }
+
+    /// Iterate over all cubes.
+    pub fn iter(&self) -> std::slice::Iter<'_, Cube> {
+        self.cubes.iter()
+    }
+
+    /// Iterate over all cubes, allowing them to be modified.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Cube> {
+        self.cubes.iter_mut()
+    }
+
+    /// Get the cube at an index.
+    pub fn get(&self, index: usize) -> Option<&Cube> {
+        self.cubes.get(index)
+    }
+
+    /// Get the cube at an index, allowing it to be modified.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Cube> {
+        self.cubes.get_mut(index)
+    }
+
+    /// Add a cube to the end of the robot.
+    ///
+    /// This does not check whether another cube is already at the same position.
+    pub fn push(&mut self, cube: Cube) {
+        self.cubes.push(cube);
+        self.sync_len();
+    }
+
+    /// Remove and return the cube at an index, or `None` if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<Cube> {
+        if index >= self.cubes.len() {
+            return None;
+        }
+        let cube = self.cubes.remove(index);
+        self.sync_len();
+        Some(cube)
+    }
+
+    /// Keep only the cubes for which `f` returns true.
+    pub fn retain<F: FnMut(&Cube) -> bool>(&mut self, f: F) {
+        self.cubes.retain(f);
+        self.sync_len();
+    }
+
+    /// Remove all cubes.
+    pub fn clear(&mut self) {
+        self.cubes.clear();
+        self.sync_len();
+    }
+
+    /// Find the index of the cube at a position.
+    pub fn index_at(&self, x: u8, y: u8, z: u8) -> Option<usize> {
+        self.cubes.iter().position(|c| c.x == x && c.y == y && c.z == z)
+    }
+
+    /// Get the cube at a position.
+    pub fn get_at(&self, x: u8, y: u8, z: u8) -> Option<&Cube> {
+        self.index_at(x, y, z).map(move |i| &self.cubes[i])
+    }
+
+    /// Get the cube at a position, allowing it to be modified.
+    pub fn get_at_mut(&mut self, x: u8, y: u8, z: u8) -> Option<&mut Cube> {
+        self.index_at(x, y, z).map(move |i| &mut self.cubes[i])
+    }
+
+    /// Remove and return the cube at a position.
+    pub fn remove_at(&mut self, x: u8, y: u8, z: u8) -> Option<Cube> {
+        self.index_at(x, y, z).and_then(|i| self.remove(i))
+    }
+
+    /// Get the smallest box containing every cube, as (minimum, maximum) inclusive `[x, y, z]` positions.
+    ///
+    /// This is `None` when there are no cubes.
+    pub fn bounding_box(&self) -> Option<([u8; 3], [u8; 3])> {
+        let first = self.cubes.first()?;
+        let mut min = [first.x, first.y, first.z];
+        let mut max = min;
+        for c in self.cubes.iter() {
+            let pos = [c.x, c.y, c.z];
+            for axis in 0..3 {
+                min[axis] = min[axis].min(pos[axis]);
+                max[axis] = max[axis].max(pos[axis]);
+            }
+        }
+        Some((min, max))
+    }
+
+    /// Change the colour of every cube of colour `from` to `to`, returning the amount of changed cubes.
+    pub fn replace_colour(&mut self, from: u8, to: u8) -> usize {
+        let mut count = 0;
+        for c in self.cubes.iter_mut().filter(|c| c.colour == from) {
+            c.colour = to;
+            count += 1;
+        }
+        count
+    }
+
+    /// Move every cube by an offset.
+    ///
+    /// This fails without changing any cubes if a cube would be moved outside of the 0 to 255 position range.
+    pub fn translate(&mut self, dx: i32, dy: i32, dz: i32) -> Result<(), CubeOutOfRange> {
+        if let Some((min, max)) = self.bounding_box() {
+            let offset = [dx, dy, dz];
+            for axis in 0..3 {
+                if min[axis] as i32 + offset[axis] < 0 || max[axis] as i32 + offset[axis] > u8::MAX as i32 {
+                    return Err(CubeOutOfRange);
+                }
+            }
+        }
+        for c in self.cubes.iter_mut() {
+            c.x = (c.x as i32 + dx) as u8;
+            c.y = (c.y as i32 + dy) as u8;
+            c.z = (c.z as i32 + dz) as u8;
+        }
+        Ok(())
+    }
+
+    /// Rotate the whole robot by 90 degree steps around an axis.
+    ///
+    /// Positive `quarter_turns` are counter-clockwise when looking from the positive end of the axis towards the origin (right-hand rule).
+    /// Cube orientations are changed to match, and the robot stays at the same minimum position (see `bounding_box()`).
+    /// This fails without changing any cubes if the rotated robot does not fit in the 0 to 255 position range.
+    pub fn rotate(&mut self, axis: CubeAxis, quarter_turns: i32) -> Result<(), CubeOutOfRange> {
+        let mut rotation = ORIENTATION_MATRICES[0];
+        for _ in 0..quarter_turns.rem_euclid(4) {
+            rotation = mat_mul(&quarter_turn(axis), &rotation);
+        }
+        self.transform(&rotation, |o| mat_mul(&rotation, o))
+    }
+
+    /// Mirror the whole robot along an axis.
+    ///
+    /// Cube orientations are mirrored too, and the robot stays at the same minimum position (see `bounding_box()`).
+    /// Since orientations can only be rotations, cubes which are not symmetrical (e.g. left and right variants of a block) are not converted into their mirrored variant.
+    pub fn mirror(&mut self, axis: CubeAxis) -> Result<(), CubeOutOfRange> {
+        let mut mirror = ORIENTATION_MATRICES[0];
+        mirror[axis.index()][axis.index()] = -1;
+        // reflecting a rotation on both sides gives the mirrored rotation
+        self.transform(&mirror, |o| mat_mul(&mat_mul(&mirror, o), &mirror))
+    }
+
+    fn transform<F: Fn(&Matrix) -> Matrix>(&mut self, matrix: &Matrix, orientation_fn: F) -> Result<(), CubeOutOfRange> {
+        let (min, _) = match self.bounding_box() {
+            Some(b) => b,
+            None => return Ok(()),
+        };
+        let positions: Vec<[i32; 3]> = self.cubes.iter()
+            .map(|c| mat_vec(matrix, [c.x as i32, c.y as i32, c.z as i32]))
+            .collect();
+        let mut new_min = [i32::MAX; 3];
+        for pos in positions.iter() {
+            for axis in 0..3 {
+                new_min[axis] = new_min[axis].min(pos[axis]);
+            }
+        }
+        let offset = [min[0] as i32 - new_min[0], min[1] as i32 - new_min[1], min[2] as i32 - new_min[2]];
+        let positions: Vec<[i32; 3]> = positions.iter()
+            .map(|p| [p[0] + offset[0], p[1] + offset[1], p[2] + offset[2]])
+            .collect();
+        if positions.iter().flatten().any(|p| *p > u8::MAX as i32) {
+            return Err(CubeOutOfRange);
+        }
+        for (c, pos) in self.cubes.iter_mut().zip(positions) {
+            c.x = pos[0] as u8;
+            c.y = pos[1] as u8;
+            c.z = pos[2] as u8;
+            // unknown orientations are left as-is
+            if let Some(old) = ORIENTATION_MATRICES.get(c.orientation as usize) {
+                let new = orientation_fn(old);
+                if let Some(o) = ORIENTATION_MATRICES.iter().position(|m| *m == new) {
+                    c.orientation = o as u8;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sync_len(&mut self) {
+        self.provided_len = self.cubes.len() as u32;
+    }
+}
+
+fn quarter_turn(axis: CubeAxis) -> Matrix {
+    match axis {
+        CubeAxis::X => [[1, 0, 0], [0, 0, -1], [0, 1, 0]],
+        CubeAxis::Y => [[0, 0, 1], [0, 1, 0], [-1, 0, 0]],
+        CubeAxis::Z => [[0, -1, 0], [1, 0, 0], [0, 0, 1]],
+    }
+}
+
+fn mat_mul(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut out = [[0; 3]; 3];
+    for (row, out_row) in out.iter_mut().enumerate() {
+        for (col, out_value) in out_row.iter_mut().enumerate() {
+            *out_value = (0..3).map(|i| a[row][i] * b[i][col]).sum();
+        }
+    }
+    out
+}
+
+fn mat_vec(m: &Matrix, v: [i32; 3]) -> [i32; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+impl std::iter::Extend<Cube> for Cubes {
+    fn extend<I: IntoIterator<Item = Cube>>(&mut self, iter: I) {
+        self.cubes.extend(iter);
+        self.sync_len();
+    }
+}
+
+impl<'a> std::iter::IntoIterator for &'a mut Cubes {
+    type Item = &'a mut Cube;
+
+    type IntoIter = std::slice::IterMut<'a, Cube>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cubes.iter_mut()
+    }
 }
 
 impl<'a> std::iter::IntoIterator for &'a Cubes {
@@ -116,12 +417,12 @@ impl<'a> std::iter::IntoIterator for &'a Cubes {
 /// A single block in a Robocraft robot.
 ///
 /// From the front of a Robocraft garage bay, looking at the back, all positions are measured from the back bottom right corner.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Cube {
     /// The cube id
     pub id: u32,
-    /// The cube's x position (left to right)
-    pub x: u8, // left to right
+    /// The cube's x position (right to left, as seen from the front of the garage bay)
+    pub x: u8, // right to left
     /// The cube's y position (bottom to top)
     pub y: u8, // bottom to top
     /// The cube's z position (back to front)
@@ -201,6 +502,15 @@ impl std::default::Default for Cube {
     }
 }
 
+impl std::convert::From<Vec<Cube>> for Cubes {
+    fn from(other: Vec<Cube>) -> Self {
+        Self {
+            provided_len: other.len() as u32,
+            cubes: other,
+        }
+    }
+}
+
 impl std::convert::From<crate::robocraft::FactoryRobotGetInfo> for Cubes {
     fn from(other: crate::robocraft::FactoryRobotGetInfo) -> Self {
         let mut cube_buf = base64::engine::general_purpose::STANDARD.decode(other.cube_data).unwrap();
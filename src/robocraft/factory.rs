@@ -3,6 +3,8 @@ use url::{Url};
 
 use crate::robocraft::{ITokenProvider, DefaultTokenProvider, FactoryInfo, FactorySearchBuilder, RoboShopItemsInfo, FactoryRobotGetInfo};
 use crate::robocraft::factory_json::ListPayload;
+use crate::retry::RequestPolicy;
+use crate::cache::ResponseCache;
 
 /// Community Factory Robot root URL
 pub const FACTORY_DOMAIN: &str = "https://factory.robocraftgame.com/";
@@ -11,6 +13,8 @@ pub const FACTORY_DOMAIN: &str = "https://factory.robocraftgame.com/";
 pub struct FactoryAPI {
     client: Client,
     token: Box<dyn ITokenProvider>,
+    policy: RequestPolicy,
+    cache: Option<ResponseCache<FactoryInfo<FactoryRobotGetInfo>>>,
 }
 
 impl FactoryAPI {
@@ -19,6 +23,8 @@ impl FactoryAPI {
         FactoryAPI {
             client: Client::new(),
             token: Box::new(DefaultTokenProvider{}),
+            policy: RequestPolicy::default(),
+            cache: None,
         }
     }
     
@@ -27,8 +33,29 @@ impl FactoryAPI {
         FactoryAPI {
             client: Client::new(),
             token: token_provider,
+            policy: RequestPolicy::default(),
+            cache: None,
         }
     }
+
+    /// Replace the retry and rate limit policy used for requests.
+    ///
+    /// Listing and getting robots are both retried, since they do not change anything.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> FactoryAPI {
+        self.policy = policy;
+        self
+    }
+
+    /// Cache the responses of `get(...)`, using the item id as the key.
+    pub fn with_cache(mut self, cache: ResponseCache<FactoryInfo<FactoryRobotGetInfo>>) -> FactoryAPI {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The cache used by `get(...)`, if any.
+    pub fn cache(&self) -> Option<&ResponseCache<FactoryInfo<FactoryRobotGetInfo>>> {
+        self.cache.as_ref()
+    }
     
     /// Retrieve CRF robots on the main page.
     ///
@@ -44,7 +71,7 @@ impl FactoryAPI {
         if let Ok(token) = self.token.token() {
             request_builder = request_builder.header("Authorization", "Web ".to_owned() + &token);
         }
-        let result = request_builder.send().await;
+        let result = self.policy.send(request_builder, true).await;
         if let Ok(response) = result {
             return response.json::<FactoryInfo<RoboShopItemsInfo>>().await;
         }
@@ -64,13 +91,19 @@ impl FactoryAPI {
             token_opt = Some(token);
         }
         let request_builder = self.client.post(url);
-        FactorySearchBuilder::new(request_builder, token_opt)
+        FactorySearchBuilder::new(request_builder, token_opt, self.policy.clone())
     }
     
     /// Get in-depth info on a CRF robot.
     ///
     /// `item_id` corresponds to the field with the same name for FactoryRobotGetInfo and FactoryRobotListInfo.
+    /// When a cache is set with `with_cache(...)`, cached robots are returned without a request.
     pub async fn get(&self, item_id: usize) -> Result<FactoryInfo<FactoryRobotGetInfo>, Error> {
+        if let Some(cache) = &self.cache {
+            if let Some(cached) = cache.get_async(&item_id.to_string()).await {
+                return Ok(cached);
+            }
+        }
         let url = Url::parse(FACTORY_DOMAIN)
             .unwrap()
             .join(&format!("/api/roboShopItems/get/{}", item_id))
@@ -79,9 +112,13 @@ impl FactoryAPI {
         if let Ok(token) = self.token.token() {
             request_builder = request_builder.header("Authorization", "Web ".to_owned() + &token);
         }
-        let result = request_builder.send().await;
+        let result = self.policy.send(request_builder, true).await;
         if let Ok(response) = result {
-            return response.json::<FactoryInfo<FactoryRobotGetInfo>>().await;
+            let info = response.json::<FactoryInfo<FactoryRobotGetInfo>>().await?;
+            if let Some(cache) = &self.cache {
+                cache.insert_async(&item_id.to_string(), info.clone()).await;
+            }
+            return Ok(info);
         }
         Err(result.err().unwrap())
     }
@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "chrono")]
+use crate::iso_date::parse_iso_datetime;
 
 // list endpoint
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub(crate) struct ListPayload {
     #[serde(rename = "page")]
     pub page: isize,
@@ -170,6 +176,44 @@ pub struct FactoryRobotListInfo {
     pub cube_amounts: String, // JSON as str
 }
 
+// cube_amounts and date accessors shared by FactoryRobotListInfo and FactoryRobotGetInfo
+macro_rules! robot_info_accessors {
+    ($info:ty) => {
+        impl $info {
+            /// Parse `cube_amounts` into a cube id to cube count map.
+            pub fn cube_amounts_map(&self) -> Result<HashMap<u32, u32>, serde_json::Error> {
+                crate::robocraft::parse_cube_amounts(&self.cube_amounts)
+            }
+
+            /// Parse `added_date`.
+            #[cfg(feature = "chrono")]
+            pub fn added_datetime(&self) -> Result<DateTime<Utc>, chrono::ParseError> {
+                parse_iso_datetime(&self.added_date)
+            }
+
+            /// Parse `expiry_date`.
+            #[cfg(feature = "chrono")]
+            pub fn expiry_datetime(&self) -> Result<DateTime<Utc>, chrono::ParseError> {
+                parse_iso_datetime(&self.expiry_date)
+            }
+
+            /// Parse `removed_date`, if the robot has one.
+            #[cfg(feature = "chrono")]
+            pub fn removed_datetime(&self) -> Option<Result<DateTime<Utc>, chrono::ParseError>> {
+                self.removed_date.as_deref().map(parse_iso_datetime)
+            }
+
+            /// Parse `ban_date`, if the robot has one.
+            #[cfg(feature = "chrono")]
+            pub fn ban_datetime(&self) -> Option<Result<DateTime<Utc>, chrono::ParseError>> {
+                self.ban_date.as_deref().map(parse_iso_datetime)
+            }
+        }
+    };
+}
+
+robot_info_accessors!(FactoryRobotListInfo);
+
 impl std::string::ToString for FactoryRobotListInfo {
     fn to_string(&self) -> String {
         format!("{} by {} ({})", &self.item_name, &self.added_by_display_name, &self.item_id)
@@ -249,6 +293,8 @@ pub struct FactoryRobotGetInfo {
     pub cube_amounts: String, // JSON as str
 }
 
+robot_info_accessors!(FactoryRobotGetInfo);
+
 impl std::string::ToString for FactoryRobotGetInfo {
     fn to_string(&self) -> String {
         format!("{} by {} ({})", &self.item_name, &self.added_by_display_name, &self.item_id)
@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+
+use crate::robocraft::{FactoryOrderType, FactoryMovementType, FactoryWeaponType, FactoryTextSearchType, FactoryTypeFilter};
+use crate::robocraft::factory_json::ListPayload;
+
+/// Reusable CRF list query.
+///
+/// Unlike `FactorySearchBuilder`, this is a plain value which does not depend on an API instance,
+/// so it can be cloned, compared and (de)serialized.
+/// Execute it with `FactoryAPI.list_builder().with_query(query).send()`,
+/// or the equivalent blocking API in `libfj::robocraft_simple`.
+/// This follows the builder pattern, so functions can be chained.
+///
+/// When deserializing, non-empty `movementTypes` and `weaponTypes` take precedence over the filter strings,
+/// so a saved query can be edited by changing only the types.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(from = "SavedFactoryQuery")]
+pub struct FactoryQuery {
+    #[serde(flatten)]
+    pub(crate) payload: ListPayload,
+    #[serde(rename = "movementTypes", default)]
+    movement: FactoryTypeFilter<FactoryMovementType>,
+    #[serde(rename = "weaponTypes", default)]
+    weapon: FactoryTypeFilter<FactoryWeaponType>,
+}
+
+/// Deserialized `FactoryQuery`, before the filter strings are updated to match the types
+#[derive(Deserialize)]
+struct SavedFactoryQuery {
+    #[serde(flatten)]
+    payload: ListPayload,
+    #[serde(rename = "movementTypes", default)]
+    movement: FactoryTypeFilter<FactoryMovementType>,
+    #[serde(rename = "weaponTypes", default)]
+    weapon: FactoryTypeFilter<FactoryWeaponType>,
+}
+
+impl From<SavedFactoryQuery> for FactoryQuery {
+    fn from(saved: SavedFactoryQuery) -> Self {
+        let mut query = Self {
+            payload: saved.payload,
+            movement: saved.movement,
+            weapon: saved.weapon,
+        };
+        // empty types are either unfiltered or set with a raw filter string, which is kept as-is
+        if !query.movement.is_empty() {
+            query.sync_movement();
+        }
+        if !query.weapon.is_empty() {
+            query.sync_weapon();
+        }
+        query
+    }
+}
+
+impl FactoryQuery {
+    /// Create a new query, which matches all robots.
+    pub fn new() -> Self {
+        Self {
+            payload: ListPayload::empty(),
+            movement: FactoryTypeFilter::new(),
+            weapon: FactoryTypeFilter::new(),
+        }
+    }
+
+    /// Retrieve list page page_number
+    pub fn page(mut self, page_number: isize) -> Self {
+        self.payload.page = page_number;
+        self
+    }
+    
+    /// Retrieve page_size items per page (this is unreliable, use `FactorySearchBuilder.stream()` to retrieve all pages instead)
+    pub fn items_per_page(mut self, page_size: isize) -> Self {
+        self.payload.page_size = page_size;
+        self
+    }
+    
+    /// Order list by order_type
+    pub fn order(mut self, order_type: FactoryOrderType) -> Self {
+        self.payload.order = order_type as isize;
+        self
+    }
+    
+    /* // this appears to not do anything (removed to prevent confusion)
+    // use text_search_type(FactoryTextSearchType::Player) instead
+    pub fn players_only(mut self, p: bool) -> Self {
+        self.payload.player_filter = p;
+        self
+    }
+    */
+    
+    /// Retrieve items with movement type.
+    ///
+    /// Multiple calls to this function will cause logical OR behaviour.
+    /// e.g. results will contain robots with Wheels OR Aerofoils (or both).
+    pub fn movement_or(mut self, movement_type: FactoryMovementType) -> Self {
+        self.movement.insert(movement_type);
+        self.sync_movement();
+        self
+    }
+
+    /// Retrieve items with a specific movement sub-category id of a movement type.
+    ///
+    /// This combines with `movement_or(...)` using logical OR behaviour.
+    pub fn movement_subcategory_or(mut self, movement_type: FactoryMovementType, id: u32) -> Self {
+        self.movement.insert_subcategory(movement_type, id);
+        self.sync_movement();
+        self
+    }
+
+    /// Override allowed movement types
+    pub fn movement_filter(mut self, filter: FactoryTypeFilter<FactoryMovementType>) -> Self {
+        self.movement = filter;
+        self.sync_movement();
+        self
+    }
+    
+    /// Override allowed movement types, using comma-separated ids.
+    ///
+    /// This clears the movement types added by other functions.
+    pub fn movement_raw(mut self, filter: String) -> Self {
+        self.movement.clear();
+        self.payload.movement_filter = filter.clone();
+        self.payload.movement_category_filter = filter.clone();
+        self
+    }
+
+    /// Allowed movement types
+    pub fn movements(&self) -> &FactoryTypeFilter<FactoryMovementType> {
+        &self.movement
+    }
+    
+    /// Retrieve items with weapon type.
+    ///
+    /// Multiple calls to this function will cause logical OR behaviour.
+    /// e.g. results will contain robots with ChainShredder OR GyroMortar (or both).
+    pub fn weapon_or(mut self, weapon_type: FactoryWeaponType) -> Self {
+        self.weapon.insert(weapon_type);
+        self.sync_weapon();
+        self
+    }
+
+    /// Retrieve items with a specific weapon sub-category id of a weapon type.
+    ///
+    /// This combines with `weapon_or(...)` using logical OR behaviour.
+    pub fn weapon_subcategory_or(mut self, weapon_type: FactoryWeaponType, id: u32) -> Self {
+        self.weapon.insert_subcategory(weapon_type, id);
+        self.sync_weapon();
+        self
+    }
+
+    /// Override allowed weapon types
+    pub fn weapon_filter(mut self, filter: FactoryTypeFilter<FactoryWeaponType>) -> Self {
+        self.weapon = filter;
+        self.sync_weapon();
+        self
+    }
+    
+    /// Override allowed weapon types, using comma-separated ids.
+    ///
+    /// This clears the weapon types added by other functions.
+    pub fn weapon_raw(mut self, filter: String) -> Self {
+        self.weapon.clear();
+        self.payload.weapon_filter = filter.clone();
+        self.payload.weapon_category_filter = filter.clone();
+        self
+    }
+
+    /// Allowed weapon types
+    pub fn weapons(&self) -> &FactoryTypeFilter<FactoryWeaponType> {
+        &self.weapon
+    }
+
+    fn sync_movement(&mut self) {
+        self.payload.movement_filter = self.movement.filter_string();
+        self.payload.movement_category_filter = self.movement.category_filter_string();
+    }
+
+    fn sync_weapon(&mut self) {
+        self.payload.weapon_filter = self.weapon.filter_string();
+        self.payload.weapon_category_filter = self.weapon.category_filter_string();
+    }
+    
+    /// Retrieve items within the specified CPU min and max values
+    pub fn cpu_range(mut self, min: isize, max: isize) -> Self {
+        self.payload.minimum_cpu = min;
+        self.payload.maximum_cpu = max;
+        self
+    }
+    
+    /// Retrieve items with CPU no lower than min
+    /// overrides cpu_range()
+    pub fn min_cpu(mut self, min: isize) -> Self {
+        self.payload.minimum_cpu = min;
+        self
+    }
+    
+    /// Retrieve items with CPU no greater than max
+    /// overrides cpu_range()
+    pub fn max_cpu(mut self, max: isize) -> Self {
+        self.payload.maximum_cpu = max;
+        self
+    }
+    
+    /// Retrieve items with any minimum CPU
+    pub fn no_minimum_cpu(mut self) -> Self {
+        self.payload.minimum_cpu = -1;
+        self
+    }
+    
+    /// Retrieve items with any maximum CPU
+    pub fn no_maximum_cpu(mut self) -> Self {
+        self.payload.maximum_cpu = -1;
+        self
+    }
+    
+    /// Retrieve items which match text
+    pub fn text(mut self, t: String) -> Self {
+        self.payload.text_filter = t;
+        self
+    }
+    
+    /// Text filter searches search_type
+    pub fn text_search_type(mut self, search_type: FactoryTextSearchType) -> Self {
+        self.payload.text_search_field = search_type as isize;
+        self
+    }
+    
+    // setting buyable to false while using the default token provider will cause HTTP status 500 error
+    /// Retrieve only items which are buyable for current account? (default: false)
+    /// Buyable means that the account owns all blocks required.
+    /// This will cause an error when using DEFAULT_TOKEN
+    pub fn buyable(mut self, b: bool) -> Self {
+        self.payload.buyable = b;
+        self
+    }
+    
+    /// Retrieve items with featured robot at start? (default: false)
+    pub fn prepend_featured(mut self, b: bool) -> Self {
+        self.payload.prepend_featured_robot = b;
+        self
+    }
+    
+    /// Retrieve default robot list? (default: false)
+    /// The default page is the CRF landing page (I think?)
+    pub fn default_page(mut self, b: bool) -> Self {
+        self.payload.default_page = b;
+        self
+    }
+}
+
+impl std::default::Default for FactoryQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,13 @@
+use std::collections::BTreeSet;
 use reqwest::{RequestBuilder, Error};
-use num_enum::{TryFromPrimitive};
+use num_enum::{TryFromPrimitive, IntoPrimitive};
+use serde::{Deserialize, Serialize};
+use futures_util::stream::{self, Stream};
 
-use crate::robocraft::{FactoryInfo, RoboShopItemsInfo};
+use crate::robocraft::{FactoryInfo, RoboShopItemsInfo, FactoryRobotListInfo, FactoryQuery};
 use crate::robocraft::factory_json::ListPayload;
+use crate::paging::Pager;
+use crate::retry::RequestPolicy;
 
 /// Factory list response ordering
 #[derive(Eq, PartialEq, TryFromPrimitive)]
@@ -23,7 +28,7 @@ pub enum FactoryOrderType {
 }
 
 /// Robot movement categories
-#[derive(Eq, PartialEq, TryFromPrimitive)]
+#[derive(Eq, PartialEq, TryFromPrimitive, IntoPrimitive, Clone, Copy, Debug, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum FactoryMovementType {
     /// Vrooooom
@@ -53,7 +58,7 @@ pub enum FactoryMovementType {
 }
 
 /// Robot weapon categories
-#[derive(Eq, PartialEq, TryFromPrimitive)]
+#[derive(Eq, PartialEq, TryFromPrimitive, IntoPrimitive, Clone, Copy, Debug, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum FactoryWeaponType {
     /// All laser weapons (aka Lasor, SMG)
@@ -90,37 +95,141 @@ pub enum FactoryTextSearchType {
     Name=2,
 }
 
+/// Set of allowed movement or weapon types for a list query.
+///
+/// Whole categories (e.g. all wheels) and sub-categories (e.g. a specific wheel) can be allowed.
+/// An empty filter allows everything.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FactoryTypeFilter<T: Ord> {
+    categories: BTreeSet<T>,
+    subcategories: BTreeSet<(T, u32)>,
+}
+
+impl<T: Copy + Ord + Into<u32>> FactoryTypeFilter<T> {
+    /// Create a new filter, which allows everything.
+    pub fn new() -> Self {
+        Self {
+            categories: BTreeSet::new(),
+            subcategories: BTreeSet::new(),
+        }
+    }
+
+    /// Allow a whole category, returning false if it was already allowed.
+    pub fn insert(&mut self, category: T) -> bool {
+        self.categories.insert(category)
+    }
+
+    /// Allow a sub-category id of a category, returning false if it was already allowed.
+    pub fn insert_subcategory(&mut self, category: T, id: u32) -> bool {
+        self.subcategories.insert((category, id))
+    }
+
+    /// Remove a category and all of its sub-categories, returning false if it was not in the filter.
+    pub fn remove(&mut self, category: T) -> bool {
+        let sub_len = self.subcategories.len();
+        self.subcategories.retain(|(c, _)| *c != category);
+        self.categories.remove(&category) || sub_len != self.subcategories.len()
+    }
+
+    /// Remove all categories and sub-categories.
+    pub fn clear(&mut self) {
+        self.categories.clear();
+        self.subcategories.clear();
+    }
+
+    /// Returns true when nothing is in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty() && self.subcategories.is_empty()
+    }
+
+    /// Returns true when the category, or any of its sub-categories, is in the filter.
+    pub fn contains(&self, category: T) -> bool {
+        self.categories.contains(&category) || self.subcategories.iter().any(|(c, _)| *c == category)
+    }
+
+    /// Whole categories in the filter, in ascending order.
+    pub fn categories(&self) -> impl Iterator<Item = T> + '_ {
+        self.categories.iter().copied()
+    }
+
+    /// (category, sub-category id) pairs in the filter, in ascending order.
+    pub fn subcategories(&self) -> impl Iterator<Item = (T, u32)> + '_ {
+        self.subcategories.iter().copied()
+    }
+
+    /// Comma-separated ids of the whole categories and sub-categories, as used in the `*Filter` list query fields.
+    ///
+    /// Sub-categories of whole categories are left out, since the whole category is already allowed.
+    pub fn filter_string(&self) -> String {
+        let mut ids: BTreeSet<u32> = self.categories.iter().map(|c| (*c).into()).collect();
+        ids.extend(self.subcategories.iter()
+            .filter(|(c, _)| !self.categories.contains(c))
+            .map(|(_, id)| *id));
+        join_ids(ids)
+    }
+
+    /// Comma-separated ids of every category with anything allowed, as used in the `*CategoryFilter` list query fields.
+    pub fn category_filter_string(&self) -> String {
+        let mut ids: BTreeSet<u32> = self.categories.iter().map(|c| (*c).into()).collect();
+        ids.extend(self.subcategories.iter().map(|(c, _)| (*c).into()));
+        join_ids(ids)
+    }
+}
+
+impl<T: Copy + Ord + Into<u32>> std::default::Default for FactoryTypeFilter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn join_ids(ids: BTreeSet<u32>) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")
+}
+
 /// Factory API list query builder
 pub struct FactorySearchBuilder {
     reqwest_builder: RequestBuilder,
-    payload: ListPayload,
+    query: FactoryQuery,
     token: Option<String>,
+    policy: RequestPolicy,
 }
 
 impl FactorySearchBuilder {
-    pub(crate) fn new(request_builder: RequestBuilder, token: Option<String>) -> FactorySearchBuilder {
+    pub(crate) fn new(request_builder: RequestBuilder, token: Option<String>, policy: RequestPolicy) -> FactorySearchBuilder {
         FactorySearchBuilder {
             reqwest_builder: request_builder,
-            payload: ListPayload::empty(),
+            query: FactoryQuery::new(),
             token,
+            policy,
         }
     }
+
+    /// Replace all query parameters with those of an existing query
+    pub fn with_query(mut self, query: FactoryQuery) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Query parameters, for saving or reusing the query
+    pub fn query(&self) -> &FactoryQuery {
+        &self.query
+    }
     
     /// Retrieve list page page_number
     pub fn page(mut self, page_number: isize) -> Self {
-        self.payload.page = page_number;
+        self.query = self.query.page(page_number);
         self
     }
     
-    /// Retrieve page_size items per page (this is unreliable)
+    /// Retrieve page_size items per page (this is unreliable, use `stream()` to retrieve all pages instead)
     pub fn items_per_page(mut self, page_size: isize) -> Self {
-        self.payload.page_size = page_size;
+        self.query = self.query.items_per_page(page_size);
         self
     }
     
     /// Order list by order_type
     pub fn order(mut self, order_type: FactoryOrderType) -> Self {
-        self.payload.order = order_type as isize;
+        self.query = self.query.order(order_type);
         self
     }
     
@@ -137,85 +246,114 @@ impl FactorySearchBuilder {
     /// Multiple calls to this function will cause logical OR behaviour.
     /// e.g. results will contain robots with Wheels OR Aerofoils (or both).
     pub fn movement_or(mut self, movement_type: FactoryMovementType) -> Self {
-        if self.payload.movement_filter == "" {
-            self.payload.movement_filter = format!("{},{}", &self.payload.movement_filter, movement_type as isize);
-        } else {
-            self.payload.movement_filter = (movement_type as isize).to_string();
-        }
-        self.payload.movement_category_filter = self.payload.movement_filter.clone();
+        self.query = self.query.movement_or(movement_type);
         self
     }
-    
+
+    /// Retrieve items with a specific movement sub-category id of a movement type.
+    ///
+    /// This combines with `movement_or(...)` using logical OR behaviour.
+    pub fn movement_subcategory_or(mut self, movement_type: FactoryMovementType, id: u32) -> Self {
+        self.query = self.query.movement_subcategory_or(movement_type, id);
+        self
+    }
+
     /// Override allowed movement types
+    pub fn movement_filter(mut self, filter: FactoryTypeFilter<FactoryMovementType>) -> Self {
+        self.query = self.query.movement_filter(filter);
+        self
+    }
+    
+    /// Override allowed movement types, using comma-separated ids.
+    ///
+    /// This clears the movement types added by other functions.
     pub fn movement_raw(mut self, filter: String) -> Self {
-        self.payload.movement_filter = filter.clone();
-        self.payload.movement_category_filter = filter.clone();
+        self.query = self.query.movement_raw(filter);
         self
     }
+
+    /// Allowed movement types
+    pub fn movements(&self) -> &FactoryTypeFilter<FactoryMovementType> {
+        self.query.movements()
+    }
     
     /// Retrieve items with weapon type.
     ///
     /// Multiple calls to this function will cause logical OR behaviour.
     /// e.g. results will contain robots with ChainShredder OR GyroMortar (or both).
     pub fn weapon_or(mut self, weapon_type: FactoryWeaponType) -> Self {
-        if self.payload.weapon_filter == "" {
-            self.payload.weapon_filter = format!("{},{}", &self.payload.weapon_filter, weapon_type as isize);
-        } else {
-            self.payload.weapon_filter = (weapon_type as isize).to_string();
-        }
-        self.payload.weapon_category_filter = self.payload.weapon_filter.clone();
+        self.query = self.query.weapon_or(weapon_type);
         self
     }
-    
+
+    /// Retrieve items with a specific weapon sub-category id of a weapon type.
+    ///
+    /// This combines with `weapon_or(...)` using logical OR behaviour.
+    pub fn weapon_subcategory_or(mut self, weapon_type: FactoryWeaponType, id: u32) -> Self {
+        self.query = self.query.weapon_subcategory_or(weapon_type, id);
+        self
+    }
+
     /// Override allowed weapon types
-    pub fn weapon_raw(mut self, filter: String) -> Self {
-        self.payload.weapon_filter = filter.clone();
-        self.payload.weapon_category_filter = filter.clone();
+    pub fn weapon_filter(mut self, filter: FactoryTypeFilter<FactoryWeaponType>) -> Self {
+        self.query = self.query.weapon_filter(filter);
         self
     }
     
+    /// Override allowed weapon types, using comma-separated ids.
+    ///
+    /// This clears the weapon types added by other functions.
+    pub fn weapon_raw(mut self, filter: String) -> Self {
+        self.query = self.query.weapon_raw(filter);
+        self
+    }
+
+    /// Allowed weapon types
+    pub fn weapons(&self) -> &FactoryTypeFilter<FactoryWeaponType> {
+        self.query.weapons()
+    }
+
     /// Retrieve items within the specified CPU min and max values
     pub fn cpu_range(mut self, min: isize, max: isize) -> Self {
-        self.payload.minimum_cpu = min;
-        self.payload.maximum_cpu = max;
+        self.query = self.query.cpu_range(min, max);
         self
     }
     
     /// Retrieve items with CPU no lower than min
     /// overrides cpu_range()
     pub fn min_cpu(mut self, min: isize) -> Self {
-        self.payload.minimum_cpu = min;
+        self.query = self.query.min_cpu(min);
         self
     }
     
     /// Retrieve items with CPU no greater than max
     /// overrides cpu_range()
     pub fn max_cpu(mut self, max: isize) -> Self {
-        self.payload.maximum_cpu = max;
+        self.query = self.query.max_cpu(max);
         self
     }
     
     /// Retrieve items with any minimum CPU
     pub fn no_minimum_cpu(mut self) -> Self {
-        self.payload.minimum_cpu = -1;
+        self.query = self.query.no_minimum_cpu();
         self
     }
     
     /// Retrieve items with any maximum CPU
     pub fn no_maximum_cpu(mut self) -> Self {
-        self.payload.maximum_cpu = -1;
+        self.query = self.query.no_maximum_cpu();
         self
     }
     
     /// Retrieve items which match text
     pub fn text(mut self, t: String) -> Self {
-        self.payload.text_filter = t;
+        self.query = self.query.text(t);
         self
     }
     
     /// Text filter searches search_type
     pub fn text_search_type(mut self, search_type: FactoryTextSearchType) -> Self {
-        self.payload.text_search_field = search_type as isize;
+        self.query = self.query.text_search_type(search_type);
         self
     }
     
@@ -224,30 +362,63 @@ impl FactorySearchBuilder {
     /// Buyable means that the account owns all blocks required.
     /// This will cause an error when using DEFAULT_TOKEN
     pub fn buyable(mut self, b: bool) -> Self {
-        self.payload.buyable = b;
+        self.query = self.query.buyable(b);
         self
     }
     
     /// Retrieve items with featured robot at start? (default: false)
     pub fn prepend_featured(mut self, b: bool) -> Self {
-        self.payload.prepend_featured_robot = b;
+        self.query = self.query.prepend_featured(b);
         self
     }
     
     /// Retrieve default robot list? (default: false)
     /// The default page is the CRF landing page (I think?)
     pub fn default_page(mut self, b: bool) -> Self {
-        self.payload.default_page = b;
+        self.query = self.query.default_page(b);
         self
     }
     
     /// Execute list query
-    pub async fn send(mut self) -> Result<FactoryInfo<RoboShopItemsInfo>, Error> {
-        self.reqwest_builder = self.reqwest_builder.json(&self.payload);
+    pub async fn send(self) -> Result<FactoryInfo<RoboShopItemsInfo>, Error> {
+        self.fetch(&self.query.payload).await
+    }
+
+    /// Execute list query for every page, starting at the page set by `page(...)`.
+    ///
+    /// Pages are retrieved as the stream is polled, using the page size set by `items_per_page(...)`.
+    /// Robots which already appeared on an earlier page are skipped.
+    /// The stream ends when a page is empty (or contains no new robots), or after the first error.
+    pub fn stream(self) -> impl Stream<Item = Result<FactoryRobotListInfo, Error>> {
+        let pager = Pager::new(self.query.payload.page, |robot: &FactoryRobotListInfo| robot.item_id);
+        stream::unfold((self, pager), |(builder, mut pager)| async move {
+            loop {
+                if let Some(robot) = pager.next_item() {
+                    return Some((Ok(robot), (builder, pager)));
+                }
+                if pager.is_done() {
+                    return None;
+                }
+                let mut payload = builder.query.payload.clone();
+                payload.page = pager.page();
+                match builder.fetch(&payload).await {
+                    Ok(info) => pager.add_page(info.response.roboshop_items),
+                    Err(e) => {
+                        pager.stop();
+                        return Some((Err(e), (builder, pager)));
+                    }
+                }
+            }
+        })
+    }
+
+    async fn fetch(&self, payload: &ListPayload) -> Result<FactoryInfo<RoboShopItemsInfo>, Error> {
+        // request builders without a body can always be cloned
+        let mut request_builder = self.reqwest_builder.try_clone().unwrap().json(payload);
         if let Some(token) = self.token.clone() {
-            self.reqwest_builder = self.reqwest_builder.header("Authorization", "Web ".to_owned() + &token);
+            request_builder = request_builder.header("Authorization", "Web ".to_owned() + &token);
         }
-        let result = self.reqwest_builder.send().await;
+        let result = self.policy.send(request_builder, true).await;
         //dbg!(&result);
         match result {
             Ok(response) => {
@@ -4,14 +4,21 @@
 mod factory;
 mod factory_json;
 mod factory_request_builder;
+mod factory_query;
 pub use self::factory::{FactoryAPI, FACTORY_DOMAIN};
 pub use self::factory_json::{FactoryInfo, FactoryRobotListInfo, RoboShopItemsInfo, FactoryRobotGetInfo};
-pub use self::factory_request_builder::{FactorySearchBuilder, FactoryMovementType, FactoryOrderType, FactoryWeaponType, FactoryTextSearchType};
+pub use self::factory_request_builder::{FactorySearchBuilder, FactoryMovementType, FactoryOrderType, FactoryWeaponType, FactoryTextSearchType, FactoryTypeFilter};
+pub use self::factory_query::FactoryQuery;
 #[cfg(feature = "simple")]
 pub(crate) use self::factory_json::{ListPayload};
 
 mod cubes;
-pub use self::cubes::{Cube, Cubes};
+pub use self::cubes::{Cube, Cubes, CubeAxis, CubeOutOfRange};
+#[cfg(feature = "convert")]
+pub(crate) use self::cubes::ORIENTATION_MATRICES;
+
+mod analysis;
+pub use self::analysis::{parse_cube_amounts, cube_counts, islands, analyse_robot, CubeStats, CubeTable, RobotEstimate, RobotIssue, RobotAnalysis};
 
 mod auth;
 pub use self::auth::{ITokenProvider, DefaultTokenProvider};
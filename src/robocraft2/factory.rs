@@ -2,9 +2,13 @@ use std::sync::Mutex;
 
 use reqwest::{Client, Error as ReqwestError, Response};
 use url::{Url};
+use futures_util::stream::{self, Stream};
 
 use crate::robocraft2::{ITokenProvider, ErrorPayload};
-use crate::robocraft2::{SearchPayload, SearchResponse, CreateRobotPayload, CreateRobotResponse, FactoryInfoResponse, PublishRobotPayload, PublishRobotResponse, MyRobotsResponse, GetRobotResponse, ModerateRobotPayload, ReportRobotPayload};
+use crate::paging::Pager;
+use crate::retry::RequestPolicy;
+use crate::cache::ResponseCache;
+use crate::robocraft2::{SearchPayload, SearchResponse, SearchResponseItem, CreateRobotPayload, CreateRobotResponse, FactoryInfoResponse, PublishRobotPayload, PublishRobotResponse, MyRobotsResponse, GetRobotResponse, ModerateRobotPayload, ReportRobotPayload};
 
 /// Community Factory Robot 2 root URL
 pub const FACTORY_DOMAIN: &str = "https://factory.production.robocraft2.com";
@@ -44,10 +48,70 @@ async fn handle_json_response<D: for<'a> serde::Deserialize<'a>>(response: Respo
     }
 }
 
+/// Build the search endpoint URL, with the search parameters in the query string
+pub(crate) fn search_url(params: &SearchPayload) -> Url {
+    let mut url = Url::parse(FACTORY_DOMAIN)
+        .unwrap()
+        .join("/v1/foundry/search")
+        .unwrap();
+    if let Some(text) = &params.text {
+        url.query_pairs_mut().append_pair("text", text);
+    }
+    if let Some(base_minimum_cpu) = params.base_minimum_cpu {
+        url.query_pairs_mut().append_pair("baseCpuMinimum", &base_minimum_cpu.to_string());
+    }
+    if let Some(base_maximum_cpu) = &params.base_maximum_cpu {
+        url.query_pairs_mut().append_pair("baseCpuMaximum", &base_maximum_cpu.to_string());
+    }
+    if let Some(x) = &params.weapon_minimum_cpu {
+        url.query_pairs_mut().append_pair("weaponCpuMinimum", &x.to_string());
+    }
+    if let Some(x) = &params.weapon_maximum_cpu {
+        url.query_pairs_mut().append_pair("weaponCpuMaximum", &x.to_string());
+    }
+    if let Some(x) = &params.cosmetic_minimum_cpu {
+        url.query_pairs_mut().append_pair("cosmeticCpuMinimum", &x.to_string());
+    }
+    if let Some(x) = &params.cosmetic_maximum_cpu {
+        url.query_pairs_mut().append_pair("cosmeticCpuMaximum", &x.to_string());
+    }
+    if let Some(x) = &params.cluster_minimum {
+        url.query_pairs_mut().append_pair("clusterMinimum", &x.to_string());
+    }
+    if let Some(x) = &params.cluster_maximum {
+        url.query_pairs_mut().append_pair("clusterMaximum", &x.to_string());
+    }
+    let (date_minimum, date_maximum) = params.date_params();
+    if let Some(x) = &date_minimum {
+        url.query_pairs_mut().append_pair("dateMinimum", x);
+    }
+    if let Some(x) = &date_maximum {
+        url.query_pairs_mut().append_pair("dateMaximum", x);
+    }
+    if let Some(x) = &params.purchased_only {
+        url.query_pairs_mut().append_pair("purchasedOnly", if *x { "true" } else { "false" });
+    }
+    if let Some(x) = &params.creator_id {
+        url.query_pairs_mut().append_pair("creatorId", x);
+    }
+    if let Some(x) = &params.page {
+        url.query_pairs_mut().append_pair("page", &x.to_string());
+    }
+    if let Some(x) = &params.count {
+        url.query_pairs_mut().append_pair("count", &x.to_string());
+    }
+    url.query_pairs_mut().append_pair("sortBy", params.sort_by.as_str());
+    url.query_pairs_mut().append_pair("orderBy", params.order_by.as_str());
+    url.query_pairs_mut().append_pair("modFilter", params.moderation_filter.as_str());
+    url
+}
+
 /// CRF API implementation
 pub struct FactoryAPI {
     client: Client,
     token: Mutex<Box<dyn ITokenProvider + Send>>,
+    policy: RequestPolicy,
+    cache: Option<ResponseCache<GetRobotResponse>>,
 }
 
 impl FactoryAPI {
@@ -64,9 +128,30 @@ impl FactoryAPI {
         FactoryAPI {
             client: Client::new(),
             token: Mutex::new(token_provider),
+            policy: RequestPolicy::default(),
+            cache: None,
         }
     }
 
+    /// Replace the retry and rate limit policy used for requests.
+    ///
+    /// Only idempotent requests (searching, getting and deleting robots) are retried.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> FactoryAPI {
+        self.policy = policy;
+        self
+    }
+
+    /// Cache the responses of `get(...)`, using the robot id as the key.
+    pub fn with_cache(mut self, cache: ResponseCache<GetRobotResponse>) -> FactoryAPI {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The cache used by `get(...)`, if any.
+    pub fn cache(&self) -> Option<&ResponseCache<GetRobotResponse>> {
+        self.cache.as_ref()
+    }
+
     /// Retrieve CRF robots on the main page.
     pub async fn list(&self) -> Result<SearchResponse, FactoryError> {
         self.search(SearchPayload::default()).await
@@ -74,79 +159,55 @@ impl FactoryAPI {
 
     /// Search for robots on the CRF which meet the provided parameters
     pub async fn search(&self, params: SearchPayload) -> Result<SearchResponse, FactoryError> {
-        let mut url = Url::parse(FACTORY_DOMAIN)
-            .unwrap()
-            .join("/v1/foundry/search")
-            .unwrap();
-        if let Some(text) = &params.text {
-            url.query_pairs_mut().append_pair("text", text);
-        }
-        if let Some(base_minimum_cpu) = params.base_minimum_cpu {
-            url.query_pairs_mut().append_pair("baseCpuMinimum", &base_minimum_cpu.to_string());
-        }
-        if let Some(base_maximum_cpu) = &params.base_maximum_cpu {
-            url.query_pairs_mut().append_pair("baseCpuMaximum", &base_maximum_cpu.to_string());
-        }
-        if let Some(x) = &params.weapon_minimum_cpu {
-            url.query_pairs_mut().append_pair("weaponCpuMinimum", &x.to_string());
-        }
-        if let Some(x) = &params.weapon_maximum_cpu {
-            url.query_pairs_mut().append_pair("weaponCpuMaximum", &x.to_string());
-        }
-        if let Some(x) = &params.cosmetic_minimum_cpu {
-            url.query_pairs_mut().append_pair("cosmeticCpuMinimum", &x.to_string());
-        }
-        if let Some(x) = &params.cosmetic_maximum_cpu {
-            url.query_pairs_mut().append_pair("cosmeticCpuMaximum", &x.to_string());
-        }
-        if let Some(x) = &params.cluster_minimum {
-            url.query_pairs_mut().append_pair("clusterMinimum", &x.to_string());
-        }
-        if let Some(x) = &params.cluster_maximum {
-            url.query_pairs_mut().append_pair("clusterMaximum", &x.to_string());
-        }
-        if let Some(x) = &params.date_minimum {
-            url.query_pairs_mut().append_pair("dateMinimum", x);
-        }
-        if let Some(x) = &params.date_maximum {
-            url.query_pairs_mut().append_pair("dateMaximum", x);
-        }
-        if let Some(x) = &params.purchased_only {
-            url.query_pairs_mut().append_pair("purchasedOnly", if *x { "true" } else { "false" });
-        }
-        if let Some(x) = &params.creator_id {
-            url.query_pairs_mut().append_pair("creatorId", x);
-        }
-        if let Some(x) = &params.page {
-            url.query_pairs_mut().append_pair("page", &x.to_string());
-        }
-        if let Some(x) = &params.count {
-            url.query_pairs_mut().append_pair("count", &x.to_string());
-        }
-        url.query_pairs_mut().append_pair("sortBy", &params.sort_by);
-        url.query_pairs_mut().append_pair("orderBy", &params.order_by);
-        url.query_pairs_mut().append_pair("modFilter", &params.moderation_filter);
+        let url = search_url(&params);
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.get(url)
-            .header("Authorization", "Bearer ".to_owned() + &token)
-            .send().await
+        let request = self.client.get(url)
+            .header("Authorization", "Bearer ".to_owned() + &token);
+        let result = self.policy.send(request, true).await
             .map_err(FactoryError::Protocol)?;
         //println!("result: {}", result.text().await.map_err(FactoryError::Protocol)?);
         handle_json_response::<SearchResponse>(result).await
         //Err(FactoryError::Response(ErrorPayload { error: -42, error_message: "Disabled on purpose".to_owned() }))
     }
 
+    /// Search for robots on the CRF for every page, starting at `params.page` (or page 0).
+    ///
+    /// Pages are retrieved as the stream is polled, using `params.count` as the page size.
+    /// Robots which already appeared on an earlier page are skipped.
+    /// The stream ends when a page is empty (or contains no new robots), or after the first error.
+    pub fn search_stream(&self, params: SearchPayload) -> impl Stream<Item = Result<SearchResponseItem, FactoryError>> + '_ {
+        let pager = Pager::new(params.page.unwrap_or(0), |item: &SearchResponseItem| item.robot.id.clone());
+        stream::unfold((params, pager), move |(mut params, mut pager)| async move {
+            loop {
+                if let Some(item) = pager.next_item() {
+                    return Some((Ok(item), (params, pager)));
+                }
+                if pager.is_done() {
+                    return None;
+                }
+                params.page = Some(pager.page());
+                match self.search(params.clone()).await {
+                    Ok(response) => pager.add_page(response.results),
+                    Err(e) => {
+                        pager.stop();
+                        return Some((Err(e), (params, pager)));
+                    }
+                }
+            }
+        })
+    }
+
     pub async fn create_robot(&self, robot: CreateRobotPayload) -> Result<CreateRobotResponse, FactoryError> {
         let url = Url::parse(FACTORY_DOMAIN)
             .unwrap()
             .join("/v1/foundry/garage")
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.post(url)
+        let request = self.client.post(url)
             .header("Authorization", "Bearer ".to_owned() + &token)
             .header("Content-Type", "application/json")
-            .json(&robot)
-            .send().await
+            .json(&robot);
+        let result = self.policy.send(request, false).await
             .map_err(FactoryError::Protocol)?;
         handle_json_response::<CreateRobotResponse>(result).await
     }
@@ -157,11 +218,11 @@ impl FactoryAPI {
             .join(&format!("/v1/foundry/vehicles/{}/publish", id))
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.post(url)
+        let request = self.client.post(url)
             .header("Authorization", "Bearer ".to_owned() + &token)
             .header("Content-Type", "application/json")
-            .json(&robot)
-            .send().await
+            .json(&robot);
+        let result = self.policy.send(request, false).await
             .map_err(FactoryError::Protocol)?;
         handle_json_response(result).await
     }
@@ -172,9 +233,9 @@ impl FactoryAPI {
             .join(&format!("/v1/foundry/vehicles/{}/unpublish", id))
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.post(url)
-            .header("Authorization", "Bearer ".to_owned() + &token)
-            .send().await
+        let request = self.client.post(url)
+            .header("Authorization", "Bearer ".to_owned() + &token);
+        let result = self.policy.send(request, false).await
             .map_err(FactoryError::Protocol)?;
         let status_code = result.status().as_u16();
         if status_code > 199 && status_code < 300 {
@@ -193,10 +254,10 @@ impl FactoryAPI {
             .join(&format!("/v1/foundry/vehicles/{}", id))
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.delete(url)
+        let request = self.client.delete(url)
             .header("Authorization", "Bearer ".to_owned() + &token)
-            .header("Content-Type", "application/json")
-            .send().await
+            .header("Content-Type", "application/json");
+        let result = self.policy.send(request, true).await
             .map_err(FactoryError::Protocol)?;
         let status_code = result.status().as_u16();
         if status_code > 199 && status_code < 300 {
@@ -215,9 +276,9 @@ impl FactoryAPI {
             .join("/v1/foundry/info")
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.get(url)
-            .header("Authorization", "Bearer ".to_owned() + &token)
-            .send().await
+        let request = self.client.get(url)
+            .header("Authorization", "Bearer ".to_owned() + &token);
+        let result = self.policy.send(request, true).await
             .map_err(FactoryError::Protocol)?;
         //println!("result: {}", result.text().await.map_err(FactoryError::Protocol)?);
         handle_json_response::<FactoryInfoResponse>(result).await
@@ -230,9 +291,9 @@ impl FactoryAPI {
             .join("/v1/foundry/garage")
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.get(url)
-            .header("Authorization", "Bearer ".to_owned() + &token)
-            .send().await
+        let request = self.client.get(url)
+            .header("Authorization", "Bearer ".to_owned() + &token);
+        let result = self.policy.send(request, true).await
             .map_err(FactoryError::Protocol)?;
         handle_json_response::<MyRobotsResponse>(result).await
     }
@@ -243,25 +304,37 @@ impl FactoryAPI {
             .join("/v1/foundry/published")
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.get(url)
-            .header("Authorization", "Bearer ".to_owned() + &token)
-            .send().await
+        let request = self.client.get(url)
+            .header("Authorization", "Bearer ".to_owned() + &token);
+        let result = self.policy.send(request, true).await
             .map_err(FactoryError::Protocol)?;
         handle_json_response::<MyRobotsResponse>(result).await
     }
 
+    /// Get in-depth info on a CRF robot.
+    ///
+    /// When a cache is set with `with_cache(...)`, cached robots are returned without a request.
     pub async fn get(&self, id: String) -> Result<GetRobotResponse, FactoryError> {
+        if let Some(cache) = &self.cache {
+            if let Some(cached) = cache.get_async(&id).await {
+                return Ok(cached);
+            }
+        }
         let url = Url::parse(FACTORY_DOMAIN)
             .unwrap()
             .join(&format!("/v1/foundry/vehicles/{}", id))
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let result = self.client.get(url)
-            .header("Authorization", "Bearer ".to_owned() + &token)
-            .send().await
+        let request = self.client.get(url)
+            .header("Authorization", "Bearer ".to_owned() + &token);
+        let result = self.policy.send(request, true).await
             .map_err(FactoryError::Protocol)?;
         //println!("result: {}", result.text().await.map_err(FactoryError::Protocol)?);
-        handle_json_response::<GetRobotResponse>(result).await
+        let robot = handle_json_response::<GetRobotResponse>(result).await?;
+        if let Some(cache) = &self.cache {
+            cache.insert_async(&id, robot.clone()).await;
+        }
+        Ok(robot)
         //Err(FactoryError::Response(ErrorPayload { error: -42, error_message: "Disabled on purpose".to_owned() }))
     }
 
@@ -271,11 +344,11 @@ impl FactoryAPI {
             .join(&format!("/v1/foundry/vehicles/{}/moderate", id))
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let _result = self.client.post(url)
+        let request = self.client.post(url)
             .header("Authorization", "Bearer ".to_owned() + &token)
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await
+            .json(&payload);
+        let _result = self.policy.send(request, false).await
             .map_err(FactoryError::Protocol)?;
         Ok(())
     }
@@ -286,11 +359,11 @@ impl FactoryAPI {
             .join(&format!("/v1/foundry/vehicles/{}/report", id))
             .unwrap();
         let token = self.token.lock().unwrap().token().await.map_err(FactoryError::Protocol)?;
-        let _result = self.client.post(url)
+        let request = self.client.post(url)
             .header("Authorization", "Bearer ".to_owned() + &token)
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await
+            .json(&payload);
+        let _result = self.policy.send(request, false).await
             .map_err(FactoryError::Protocol)?;
         Ok(())
     }
@@ -1,34 +1,102 @@
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
 
-pub mod order {
-    pub const ASCENDING: &str = "ascending";
-    pub const DESCENDING: &str = "descending";
-    pub(super) fn default() -> &'static str {
-        ASCENDING
+use crate::iso_date::parse_iso_datetime;
+
+/// Sort order direction of search results.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderBy {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl OrderBy {
+    /// Value used by the CRF API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ascending => "ascending",
+            Self::Descending => "descending",
+        }
+    }
+}
+
+/// Property to sort search results by.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SortBy {
+    CpuPower,
+    FirePower,
+    EnginePower,
+    Price,
+    Date,
+    ClusterCount,
+    Views,
+    MostPurchased,
+    #[default]
+    Default,
+}
+
+impl SortBy {
+    /// Value used by the CRF API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CpuPower => "cpuPower",
+            Self::FirePower => "firePower",
+            Self::EnginePower => "enginePower",
+            Self::Price => "price",
+            Self::Date => "date",
+            Self::ClusterCount => "clusterCount",
+            Self::Views => "views",
+            Self::MostPurchased => "mostPurchased",
+            Self::Default => "default",
+        }
     }
 }
 
-pub mod sort {
-    pub const CPU_POWER: &str = "cpuPower";
-    pub const FIRE_POWER: &str = "firePower";
-    pub const ENGINE_POWER: &str = "enginePower";
-    pub const PRICE: &str = "price";
-    pub const DATE: &str = "date";
-    pub const CLUSTER_COUNT: &str = "clusterCount";
-    pub const VIEWS: &str = "views";
-    pub const MOST_PURCHASED: &str = "mostPurchased";
-    pub const DEFAULT: &str = "default";
-    pub(super) fn default() -> &'static str {
-        DEFAULT
+/// Moderation state of robots to include in search results.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ModerationFilter {
+    #[default]
+    #[serde(rename = "none")]
+    NoFilter,
+    NeedsModeration,
+    ModeratedOnly,
+}
+
+impl ModerationFilter {
+    /// Value used by the CRF API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoFilter => "none",
+            Self::NeedsModeration => "needsModeration",
+            Self::ModeratedOnly => "moderatedOnly",
+        }
     }
 }
 
-pub mod moderation {
-    pub const NO_FILTER: &str = "none";
-    pub const NEEDS_MODERATION: &str = "needsModeration";
-    pub const MODERATED: &str = "moderatedOnly";
-    pub(super) fn default() -> &'static str {
-        NO_FILTER
+/// (De)serialize optional dates as ISO 8601 strings, like the CRF API uses
+mod optional_date {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use chrono::{DateTime, SecondsFormat, Utc};
+
+    pub(super) fn to_param(date: &DateTime<Utc>) -> String {
+        date.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    pub(super) fn serialize<S: Serializer>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(d) => serializer.serialize_some(&to_param(d)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|d| crate::iso_date::parse_iso_datetime(&d).map_err(serde::de::Error::custom))
+            .transpose()
     }
 }
 
@@ -42,7 +110,7 @@ pub struct ErrorPayload {
 
 // search endpoint
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct SearchPayload {
     #[serde(rename = "text")]
     pub text: Option<String>,
@@ -62,10 +130,10 @@ pub struct SearchPayload {
     pub cluster_minimum: Option<isize>,
     #[serde(rename = "clusterMaximum")]
     pub cluster_maximum: Option<isize>,
-    #[serde(rename = "dateMinimum")]
-    pub date_minimum: Option<String>,
-    #[serde(rename = "dateMaximum")]
-    pub date_maximum: Option<String>,
+    #[serde(rename = "dateMinimum", with = "optional_date", default)]
+    pub date_minimum: Option<DateTime<Utc>>,
+    #[serde(rename = "dateMaximum", with = "optional_date", default)]
+    pub date_maximum: Option<DateTime<Utc>>,
     #[serde(rename = "purchasedOnly")]
     pub purchased_only: Option<bool>,
     #[serde(rename = "creatorId")]
@@ -74,12 +142,12 @@ pub struct SearchPayload {
     pub page: Option<isize>,
     #[serde(rename = "count")]
     pub count: Option<isize>,
-    #[serde(rename = "sortBy", default="sort::default")]
-    pub sort_by: &'static str,
-    #[serde(rename = "orderBy", default="order::default")]
-    pub order_by: &'static str,
-    #[serde(rename = "modFilter", default="moderation::default")]
-    pub moderation_filter: &'static str,
+    #[serde(rename = "sortBy", default)]
+    pub sort_by: SortBy,
+    #[serde(rename = "orderBy", default)]
+    pub order_by: OrderBy,
+    #[serde(rename = "modFilter", default)]
+    pub moderation_filter: ModerationFilter,
 }
 
 impl Default for SearchPayload {
@@ -100,13 +168,109 @@ impl Default for SearchPayload {
             creator_id: None,
             page: None,
             count: None,
-            sort_by: sort::DEFAULT,
-            order_by: order::ASCENDING,
-            moderation_filter: moderation::NO_FILTER,
+            sort_by: SortBy::Default,
+            order_by: OrderBy::Ascending,
+            moderation_filter: ModerationFilter::NoFilter,
         }
     }
 }
 
+impl SearchPayload {
+    /// Create a new search, which matches the robots on the main CRF page.
+    /// This follows the builder pattern, so functions can be chained.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Search for robots matching text
+    pub fn text(mut self, t: String) -> Self {
+        self.text = Some(t);
+        self
+    }
+
+    /// Retrieve robots with base (movement and structure) CPU in the range min..max (use `None` for no limit)
+    pub fn base_cpu_range(mut self, min: Option<isize>, max: Option<isize>) -> Self {
+        self.base_minimum_cpu = min;
+        self.base_maximum_cpu = max;
+        self
+    }
+
+    /// Retrieve robots with weapon CPU in the range min..max (use `None` for no limit)
+    pub fn weapon_cpu_range(mut self, min: Option<isize>, max: Option<isize>) -> Self {
+        self.weapon_minimum_cpu = min;
+        self.weapon_maximum_cpu = max;
+        self
+    }
+
+    /// Retrieve robots with cosmetic CPU in the range min..max (use `None` for no limit)
+    pub fn cosmetic_cpu_range(mut self, min: Option<isize>, max: Option<isize>) -> Self {
+        self.cosmetic_minimum_cpu = min;
+        self.cosmetic_maximum_cpu = max;
+        self
+    }
+
+    /// Retrieve robots with a cluster count in the range min..max (use `None` for no limit)
+    pub fn cluster_range(mut self, min: Option<isize>, max: Option<isize>) -> Self {
+        self.cluster_minimum = min;
+        self.cluster_maximum = max;
+        self
+    }
+
+    /// Retrieve robots created in the range min..max (use `None` for no limit)
+    pub fn date_range(mut self, min: Option<DateTime<Utc>>, max: Option<DateTime<Utc>>) -> Self {
+        self.date_minimum = min;
+        self.date_maximum = max;
+        self
+    }
+
+    /// Retrieve only robots purchased by the logged-in account
+    pub fn purchased_only(mut self, p: bool) -> Self {
+        self.purchased_only = Some(p);
+        self
+    }
+
+    /// Retrieve only robots created by the account with this id
+    pub fn creator(mut self, creator_id: String) -> Self {
+        self.creator_id = Some(creator_id);
+        self
+    }
+
+    /// Retrieve list page page_number
+    pub fn page(mut self, page_number: isize) -> Self {
+        self.page = Some(page_number);
+        self
+    }
+
+    /// Retrieve page_size items per page
+    pub fn count(mut self, page_size: isize) -> Self {
+        self.count = Some(page_size);
+        self
+    }
+
+    /// Sort robots by sort_type
+    pub fn sort_by(mut self, sort_type: SortBy) -> Self {
+        self.sort_by = sort_type;
+        self
+    }
+
+    /// Order sorted robots by order_type
+    pub fn order_by(mut self, order_type: OrderBy) -> Self {
+        self.order_by = order_type;
+        self
+    }
+
+    /// Retrieve robots with moderation state
+    pub fn moderation_filter(mut self, filter: ModerationFilter) -> Self {
+        self.moderation_filter = filter;
+        self
+    }
+
+    /// Query parameters for dates, in the format used by the CRF API
+    pub(crate) fn date_params(&self) -> (Option<String>, Option<String>) {
+        (self.date_minimum.as_ref().map(optional_date::to_param), self.date_maximum.as_ref().map(optional_date::to_param))
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct SearchResponse {
     #[serde(rename = "results")]
@@ -176,6 +340,13 @@ pub struct RobotInfo {
     pub version: usize,
 }
 
+impl RobotInfo {
+    /// Parse `created`.
+    pub fn created_datetime(&self) -> Result<DateTime<Utc>, chrono::ParseError> {
+        parse_iso_datetime(&self.created)
+    }
+}
+
 impl std::string::ToString for RobotInfo {
     fn to_string(&self) -> String {
         format!("{} ({}) by {} ({})", &self.name, &self.id, &self.creator_name, &self.creator_id)
@@ -226,6 +397,37 @@ pub struct CreateRobotPayload {
     pub maximum_offset_z: f64,
 }
 
+#[cfg(feature = "techblox")]
+impl CreateRobotPayload {
+    /// Create a payload for uploading a vehicle, with all metadata calculated from the vehicle.
+    ///
+    /// The CPU of every block is looked up in `table`, so this fails when the vehicle contains a block id which is not in the table.
+    /// This also fails for vehicles without blocks, which cannot be uploaded.
+    /// The vehicle is encoded with the experimental layout of `libfj::robocraft2::vehicle`, which the CRF may not accept.
+    /// `image` is the base64 robot thumbnail.
+    pub fn from_vehicle(name: String, vehicle: &crate::robocraft2::Vehicle, table: &crate::robocraft2::BlockTable, image: String) -> Result<Self, crate::robocraft2::VehicleError> {
+        let (min, max) = vehicle.bounding_box().ok_or(crate::robocraft2::VehicleError::Empty)?;
+        let cpu = table.cpu(vehicle)?;
+        Ok(Self {
+            name,
+            data: vehicle.encode()?,
+            image,
+            base_cpu: cpu.base as isize,
+            weapon_cpu: cpu.weapon as isize,
+            cosmetic_cpu: cpu.cosmetic as isize,
+            cluster_count: vehicle.cluster_entities.len() as isize,
+            block_counts: vehicle.block_counts().into_iter().map(|(id, count)| (id as usize, count)).collect(),
+            materials_used: vehicle.materials_used().into_iter().map(|m| m as isize).collect(),
+            minimum_offset_x: min[0] as f64,
+            minimum_offset_y: min[1] as f64,
+            minimum_offset_z: min[2] as f64,
+            maximum_offset_x: max[0] as f64,
+            maximum_offset_y: max[1] as f64,
+            maximum_offset_z: max[2] as f64,
+        })
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct CreateRobotResponse {
     #[serde(rename = "header")]
@@ -270,6 +472,14 @@ pub struct PublishRobotResponse {
     pub data: String, // base64
 }
 
+#[cfg(feature = "techblox")]
+impl PublishRobotResponse {
+    /// Decode `data` (experimental, see `libfj::robocraft2::vehicle`).
+    pub fn vehicle(&self) -> Result<crate::robocraft2::Vehicle, crate::robocraft2::VehicleError> {
+        crate::robocraft2::Vehicle::decode(&self.data)
+    }
+}
+
 // get my robots endpoint
 
 // (no payload -- this is a GET request)
@@ -298,6 +508,19 @@ pub struct GetRobotResponse {
     pub moderated: bool,
 }
 
+impl GetRobotResponse {
+    /// Parse `created`.
+    pub fn created_datetime(&self) -> Result<DateTime<Utc>, chrono::ParseError> {
+        parse_iso_datetime(&self.created)
+    }
+
+    /// Decode `data` (experimental, see `libfj::robocraft2::vehicle`).
+    #[cfg(feature = "techblox")]
+    pub fn vehicle(&self) -> Result<crate::robocraft2::Vehicle, crate::robocraft2::VehicleError> {
+        crate::robocraft2::Vehicle::decode(&self.data)
+    }
+}
+
 // moderate robot endpoint
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
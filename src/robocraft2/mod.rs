@@ -1,11 +1,16 @@
 //! Robocraft2 APIs for the CRF.
 //! Subject to change and breakages as RC2 is still in an early development stage.
 
-mod factory;
+pub(crate) mod factory;
 pub use factory::{FactoryAPI, FactoryError};
 
 mod factory_json;
-pub use factory_json::{ErrorPayload, SearchPayload, SearchResponse, SearchResponseItem, RobotInfo, RobotPrice, CreateRobotPayload, CreateRobotResponse, FactoryInfoResponse, PublishRobotPayload, PublishRobotResponse, MyRobotsResponse, GetRobotResponse, ModerateRobotPayload, ReportRobotPayload, order, sort, moderation};
+pub use factory_json::{ErrorPayload, SearchPayload, SearchResponse, SearchResponseItem, RobotInfo, RobotPrice, CreateRobotPayload, CreateRobotResponse, FactoryInfoResponse, PublishRobotPayload, PublishRobotResponse, MyRobotsResponse, GetRobotResponse, ModerateRobotPayload, ReportRobotPayload, OrderBy, SortBy, ModerationFilter};
 
-mod portal;
+pub(crate) mod portal;
 pub use self::portal::{PortalTokenProvider, AccountInfo, PortalCheckResponse, ITokenProvider};
+
+#[cfg(feature = "techblox")]
+pub mod vehicle;
+#[cfg(feature = "techblox")]
+pub use self::vehicle::{Vehicle, VehicleBlock, VehicleCluster, VehicleError, BlockTable, BlockStats, CpuCategory, VehicleCpu, BLOCK_SIZE};
@@ -7,8 +7,10 @@ use serde_json::from_slice;
 use chrono::{DateTime, Utc};
 use base64::Engine;
 
-const GAME_VERSION: &str = "100.0"; // currently, this accepts any version >= current public release
-const GAME_TARGET: &str = "Techblox";
+use crate::retry::RequestPolicy;
+
+pub(crate) const GAME_VERSION: &str = "100.0"; // currently, this accepts any version >= current public release
+pub(crate) const GAME_TARGET: &str = "Techblox";
 
 /// Token generator for authenticated API endpoints
 #[async_trait::async_trait]
@@ -19,6 +21,9 @@ pub trait ITokenProvider {
 
 /// Token provider for an existing Freejam account, authenticated through the web browser portal.
 ///
+/// Portal and token refresh requests are sent through a `RequestPolicy`, and are retried when the policy allows it.
+/// Requests which send the account password are never retried.
+///
 /// Steam and Epic accounts are not supported.
 pub struct PortalTokenProvider {
     /// Login token
@@ -31,6 +36,8 @@ pub struct PortalTokenProvider {
     target: String,
     /// game version
     version: String,
+    /// Retry and rate limit policy
+    policy: RequestPolicy,
 }
 
 impl PortalTokenProvider {
@@ -42,122 +49,136 @@ impl PortalTokenProvider {
     /// Login through the portal with a custom target value
     pub async fn target(value: String, version: String) -> Result<Self, Error> {
         let client = Client::new();
+        let policy = RequestPolicy::default();
         let payload = PortalStartPayload {
             target: value.clone(),
         };
-        let start_response = client.post("https://account.freejamgames.com/api/authenticate/portal/start")
+        let request = client.post("https://account.freejamgames.com/api/authenticate/portal/start")
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await?;
-        let start_res = start_response.json::<PortalStartResponse>().await?;
+            .json(&payload);
+        let start_res = policy.send(request, true).await?.json::<PortalStartResponse>().await?;
 
         println!("GO TO https://account.freejamgames.com/login?theme=rc2&redirect_url=portal?theme=rc2%26portalToken={}", start_res.token);
 
         let payload = PortalCheckPayload {
             token: start_res.token,
         };
-        let mut check_response = client.post("https://account.freejamgames.com/api/authenticate/portal/check")
+        let check_request = client.post("https://account.freejamgames.com/api/authenticate/portal/check")
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await?;
+            .json(&payload);
+        let mut check_response = policy.send(check_request.try_clone().unwrap(), true).await?;
         let mut auth_complete = check_response.status() == 200;
         while !auth_complete {
-            check_response = client.post("https://account.freejamgames.com/api/authenticate/portal/check")
-                .header("Content-Type", "application/json")
-                .json(&payload)
-                .send().await?;
+            check_response = policy.send(check_request.try_clone().unwrap(), true).await?;
             auth_complete = check_response.status() == 200;
         }
         let check_res = check_response.json::<PortalCheckResponse>().await?;
 
         // login with token we just got
-       Self::login_internal(check_res, client, value, version).await
+       Self::login_internal(check_res, client, value, version, policy).await
     }
 
     pub async fn with_email(email: &str, password: &str) -> Result<Self, Error> {
+        Self::with_email_and_policy(email, password, RequestPolicy::default()).await
+    }
+
+    /// Login using the email address and password of a Freejam account, sending requests through `policy`
+    pub async fn with_email_and_policy(email: &str, password: &str, policy: RequestPolicy) -> Result<Self, Error> {
         let client = Client::new();
         let payload = AuthenticationEmailPayload {
             email_address: email.to_string(),
             password: password.to_string(),
         };
-        let response = client.post("https://account.freejamgames.com/api/authenticate/email/web")
+        let request = client.post("https://account.freejamgames.com/api/authenticate/email/web")
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await?;
-        let json_res = response.json::<AuthenticationResponseInfo>().await?;
-        Self::auto_portal(client, GAME_TARGET.to_owned(), json_res.token, GAME_VERSION.to_owned()).await
+            .json(&payload);
+        let json_res = policy.send(request, false).await?.json::<AuthenticationResponseInfo>().await?;
+        Self::auto_portal(client, GAME_TARGET.to_owned(), json_res.token, GAME_VERSION.to_owned(), policy).await
     }
 
     pub async fn with_username(username: &str, password: &str) -> Result<Self, Error> {
+        Self::with_username_and_policy(username, password, RequestPolicy::default()).await
+    }
+
+    /// Login using the display name and password of a Freejam account, sending requests through `policy`
+    pub async fn with_username_and_policy(username: &str, password: &str, policy: RequestPolicy) -> Result<Self, Error> {
         let client = Client::new();
         let payload = AuthenticationUsernamePayload {
             username: username.to_string(),
             password: password.to_string(),
         };
-        let response = client.post("https://account.freejamgames.com/api/authenticate/displayname/web")
+        let request = client.post("https://account.freejamgames.com/api/authenticate/displayname/web")
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await?;
-        let json_res = response.json::<AuthenticationResponseInfo>().await?;
-        Self::auto_portal(client, GAME_TARGET.to_owned(), json_res.token, GAME_VERSION.to_owned()).await
+            .json(&payload);
+        let json_res = policy.send(request, false).await?.json::<AuthenticationResponseInfo>().await?;
+        Self::auto_portal(client, GAME_TARGET.to_owned(), json_res.token, GAME_VERSION.to_owned(), policy).await
     }
 
     /// Automatically validate portal
-    async fn auto_portal(client: Client, value: String, token: String, version: String) -> Result<Self, Error> {
+    async fn auto_portal(client: Client, value: String, token: String, version: String, policy: RequestPolicy) -> Result<Self, Error> {
         let payload = PortalStartPayload {
             target: value.clone(),
         };
-        let start_response = client.post("https://account.freejamgames.com/api/authenticate/portal/start")
+        let request = client.post("https://account.freejamgames.com/api/authenticate/portal/start")
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await?;
-        let start_res = start_response.json::<PortalStartResponse>().await?;
+            .json(&payload);
+        let start_res = policy.send(request, true).await?.json::<PortalStartResponse>().await?;
         let payload = PortalCheckPayload {
             token: start_res.token,
         };
 
-        let _assign_response = client.post("https://account.freejamgames.com/api/authenticate/portal/assign")
+        let request = client.post("https://account.freejamgames.com/api/authenticate/portal/assign")
             .header("Content-Type", "application/json")
             .header("Authorization", "Web ".to_owned() + &token)
-            .json(&payload)
-            .send().await?;
+            .json(&payload);
+        let _assign_response = policy.send(request, true).await?;
 
-        let check_response = client.post("https://account.freejamgames.com/api/authenticate/portal/check")
+        let request = client.post("https://account.freejamgames.com/api/authenticate/portal/check")
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await?;
-        let check_res = check_response.json::<PortalCheckResponse>().await?;
+            .json(&payload);
+        let check_res = policy.send(request, true).await?.json::<PortalCheckResponse>().await?;
 
         // login with token we just got
-       Self::login_internal(check_res, client, value, version).await
+       Self::login_internal(check_res, client, value, version, policy).await
     }
 
-    async fn login_internal(token_data: PortalCheckResponse, client: Client, target: String, version: String) -> Result<Self, Error> {
-        let progress_res = Self::login_step(&token_data, &client, version.clone()).await?;
+    async fn login_internal(token_data: PortalCheckResponse, client: Client, target: String, version: String, policy: RequestPolicy) -> Result<Self, Error> {
+        let progress_res = Self::login_step(&token_data, &client, version.clone(), &policy).await?;
         Ok(Self {
             token: progress_res,
             jwt: token_data,
             client: client,
             target: target,
             version: version,
+            policy,
         })
     }
 
-    async fn login_step(token_data: &PortalCheckResponse, client: &Client, version: String) -> Result<ProgressionLoginResponse, Error> {
+    async fn login_step(token_data: &PortalCheckResponse, client: &Client, version: String, policy: &RequestPolicy) -> Result<ProgressionLoginResponse, Error> {
         let payload = ProgressionLoginPayload {
             token: token_data.token.clone(),
             client_version: version,
         };
-        let progress_response = client.post("https://progression.production.robocraft2.com/login/fj")
+        let request = client.post("https://progression.production.robocraft2.com/login/fj")
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send().await?;
-        progress_response.json::<ProgressionLoginResponse>().await
+            .json(&payload);
+        policy.send(request, true).await?.json::<ProgressionLoginResponse>().await
     }
 
     /// Login using the portal token data from a previous portal authentication
     pub async fn login(token_data: PortalCheckResponse, target: String, version: String) -> Result<Self, Error> {
-        Self::login_internal(token_data, Client::new(), target, version).await
+        Self::login_with_policy(token_data, target, version, RequestPolicy::default()).await
+    }
+
+    /// Login using the portal token data from a previous portal authentication, sending requests through `policy`
+    pub async fn login_with_policy(token_data: PortalCheckResponse, target: String, version: String, policy: RequestPolicy) -> Result<Self, Error> {
+        Self::login_internal(token_data, Client::new(), target, version, policy).await
+    }
+
+    /// Replace the retry and rate limit policy used for token refreshes.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
     }
 
     pub fn get_account_info(&self) -> Result<AccountInfo, Error> {
@@ -184,12 +205,11 @@ impl ITokenProvider for PortalTokenProvider {
                 refresh_token: self.jwt.refresh_token.clone(),
                 public_id: decoded_jwt.public_id,
             };
-            let refresh_response = self.client.post("https://account.freejamgames.com/api/authenticate/token/refresh")
+            let request = self.client.post("https://account.freejamgames.com/api/authenticate/token/refresh")
                 .header("Content-Type", "application/json")
-                .json(&payload)
-                .send().await?;
-            self.jwt = refresh_response.json::<PortalCheckResponse>().await?;
-            self.token = Self::login_step(&self.jwt, &self.client, self.version.clone()).await?;
+                .json(&payload);
+            self.jwt = self.policy.send(request, true).await?.json::<PortalCheckResponse>().await?;
+            self.token = Self::login_step(&self.jwt, &self.client, self.version.clone(), &self.policy).await?;
         }
         Ok(self.token.token.clone().unwrap())
         //Ok(self.jwt.token.clone())
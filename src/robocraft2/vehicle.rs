@@ -0,0 +1,452 @@
+//! Robocraft2 vehicle (robot) data (experimental).
+//!
+//! RC2 shares its engine with Techblox, so this module assumes that vehicle data is stored as Techblox entities,
+//! using the layout of the block section of a Techblox game save (see `libfj::techblox::GameSave`):
+//!
+//! 1. game version, as year, month and day (u32 each)
+//! 2. cluster count (u32), followed by an entity header and block group entity for every cluster
+//! 3. block count (u32), followed by an entity header and block entity for every block
+//!
+//! This layout has not been checked against vehicle data from the CRF, so real RC2 vehicles may fail to decode
+//! or decode incorrectly. It is not officially documented either and may change between RC2 versions.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use base64::Engine;
+use chrono::{naive::NaiveDate, Datelike};
+
+use crate::id_table::IdTable;
+use crate::techblox::{EntityHeader, BlockGroupEntity, SavedBlockGroupIdComponent, BlockGroupTransformEntityComponent,
+SerializedEntityDescriptor, Parsable, UnityFloat3, UnityFloat4, UnityFloat4x4, UnityQuaternion, parse_u32};
+use crate::techblox::blocks::{Block, BlockEntity, DBEntityStruct, PositionEntityStruct, ScalingEntityStruct, RotationEntityStruct,
+SkewComponent, GridRotationStruct, SerializedGridConnectionsEntityStruct, SerializedBlockPlacementInfoStruct,
+SerializedCubeMaterialStruct, SerializedUniformBlockScaleEntityStruct, SerializedColourParameterEntityStruct,
+BlockGroupEntityComponent, DescriptorRegistry};
+
+/// Edge length of an unscaled block, in metres
+pub const BLOCK_SIZE: f32 = 0.2;
+
+// Entity group identifiers used for new clusters and blocks
+const GROUP_ID_CLUSTERS: u32 = 2;
+const GROUP_ID_BLOCKS: u32 = 10677;
+
+/// Error while decoding or processing vehicle data
+#[derive(Debug)]
+pub enum VehicleError {
+    /// Vehicle data is not valid base64
+    Base64(base64::DecodeError),
+    /// Vehicle data is malformed or contains unknown entities
+    Parse(std::io::Error),
+    /// Vehicle contains block ids which are not in the block table
+    UnknownBlocks(Vec<u32>),
+    /// Vehicle has no blocks
+    Empty,
+}
+
+impl std::fmt::Display for VehicleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Base64(e) => write!(f, "Invalid vehicle base64: {}", e),
+            Self::Parse(e) => write!(f, "Invalid vehicle data: {}", e),
+            Self::UnknownBlocks(ids) => write!(f, "Unknown vehicle block ids: {:?}", ids),
+            Self::Empty => write!(f, "Vehicle has no blocks"),
+        }
+    }
+}
+
+impl std::error::Error for VehicleError {}
+
+/// Decoded RC2 vehicle, made of blocks which are grouped into clusters.
+///
+/// This is experimental, since the vehicle data layout is assumed (see the module documentation).
+///
+/// Entities are kept as-is, so a decoded vehicle is re-encoded without losing block-specific data.
+/// Use `blocks()` and `clusters()` for a simpler view of the vehicle.
+pub struct Vehicle {
+    /// Game version that this vehicle was created by.
+    /// This may affect how the rest of the vehicle data was parsed.
+    pub version: NaiveDate,
+
+    /// Entity group descriptors for cluster (block group) entities.
+    /// There must be one header for every entity in `cluster_entities`.
+    pub cluster_headers: Vec<EntityHeader>,
+
+    /// Cluster (block group) entities.
+    pub cluster_entities: Vec<BlockGroupEntity>,
+
+    /// Entity group descriptors for block entities.
+    /// There must be one header for every entity in `block_entities`.
+    pub block_headers: Vec<EntityHeader>,
+
+    /// Blocks
+    pub block_entities: Vec<Box<dyn Block>>,
+}
+
+/// Simplified view of a vehicle block.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VehicleBlock {
+    /// Block database identifier (block type)
+    pub block_id: u32,
+    /// Position, in metres
+    pub position: [f32; 3],
+    /// Rotation quaternion, as (x, y, z, w)
+    pub rotation: [f32; 4],
+    /// Scale along each axis
+    pub scale: [f32; 3],
+    /// Index of colour in the palette
+    pub colour: u8,
+    /// Material identifier
+    pub material: u8,
+    /// Identifier of the cluster which the block belongs to
+    pub cluster: i32,
+}
+
+/// Simplified view of a vehicle cluster.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VehicleCluster {
+    /// Cluster identifier, as used by `VehicleBlock.cluster`
+    pub id: i32,
+    /// Position, in metres
+    pub position: [f32; 3],
+    /// Rotation quaternion, as (x, y, z, w)
+    pub rotation: [f32; 4],
+}
+
+impl Vehicle {
+    /// Create a new vehicle without any blocks.
+    pub fn new(version: NaiveDate) -> Self {
+        Self {
+            version,
+            cluster_headers: Vec::new(),
+            cluster_entities: Vec::new(),
+            block_headers: Vec::new(),
+            block_entities: Vec::new(),
+        }
+    }
+
+    /// Decode base64 vehicle data, like `GetRobotResponse.data`.
+    ///
+    /// The data is expected to use the assumed layout described in the module documentation.
+    pub fn decode(data: &str) -> Result<Self, VehicleError> {
+        Self::decode_with_registry(data, &DescriptorRegistry::new())
+    }
+
+    /// Decode base64 vehicle data, using custom block entity descriptors.
+    ///
+    /// Blocks are parsed using descriptors in `registry` before falling back to built-in libfj blocks.
+    pub fn decode_with_registry(data: &str, registry: &DescriptorRegistry) -> Result<Self, VehicleError> {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(data).map_err(VehicleError::Base64)?;
+        Self::parse_with_registry(&mut bytes.as_slice(), registry).map_err(VehicleError::Parse)
+    }
+
+    /// Encode the vehicle as base64 data, like `CreateRobotPayload.data`.
+    ///
+    /// This fails when there are not exactly as many headers as entities.
+    pub fn encode(&self) -> Result<String, VehicleError> {
+        let mut bytes = Vec::new();
+        self.dump(&mut bytes).map_err(VehicleError::Parse)?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    /// Process raw (not base64) vehicle data, using custom block entity descriptors.
+    pub fn parse_with_registry(data: &mut dyn Read, registry: &DescriptorRegistry) -> std::io::Result<Self> {
+        let year = parse_u32(data)?;
+        let month = parse_u32(data)?;
+        let day = parse_u32(data)?;
+        let version = NaiveDate::from_ymd_opt(year as i32, month, day)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid vehicle version date"))?;
+        let cluster_count = parse_u32(data)?;
+        let mut cluster_headers = Vec::new();
+        let mut cluster_entities = Vec::new();
+        for _i in 0..cluster_count {
+            cluster_headers.push(EntityHeader::parse_versioned(data, &version)?);
+            cluster_entities.push(BlockGroupEntity::parse_versioned(data, &version)?);
+        }
+        let block_count = parse_u32(data)?;
+        let mut block_headers = Vec::new();
+        let mut block_entities = Vec::new();
+        for _i in 0..block_count {
+            let header = EntityHeader::parse_versioned(data, &version)?;
+            block_entities.push(registry.parse(header.hash, data, &version)?);
+            block_headers.push(header);
+        }
+        Ok(Self {
+            version,
+            cluster_headers,
+            cluster_entities,
+            block_headers,
+            block_entities,
+        })
+    }
+
+    /// Amount of blocks in the vehicle.
+    pub fn len(&self) -> usize {
+        self.block_entities.len()
+    }
+
+    /// Returns true when the vehicle has no blocks.
+    pub fn is_empty(&self) -> bool {
+        self.block_entities.is_empty()
+    }
+
+    /// Simplified view of the block at index i.
+    pub fn block(&self, i: usize) -> Option<VehicleBlock> {
+        self.block_entities.get(i).map(|b| VehicleBlock::from_entity((**b).as_ref()))
+    }
+
+    /// Simplified views of all blocks.
+    pub fn blocks(&self) -> impl Iterator<Item = VehicleBlock> + '_ {
+        self.block_entities.iter().map(|b| VehicleBlock::from_entity((**b).as_ref()))
+    }
+
+    /// Simplified views of all clusters.
+    pub fn clusters(&self) -> impl Iterator<Item = VehicleCluster> + '_ {
+        self.cluster_entities.iter().map(|c| VehicleCluster {
+            id: c.saved_block_group_id.saved_block_group_id,
+            position: float3_to_array(&c.block_group_transform.block_group_grid_position),
+            rotation: quaternion_to_array(&c.block_group_transform.block_group_grid_rotation),
+        })
+    }
+
+    /// Add a new cluster, returning its identifier.
+    pub fn push_cluster(&mut self, position: [f32; 3], rotation: [f32; 4]) -> i32 {
+        let id = self.clusters().map(|c| c.id + 1).max().unwrap_or(0);
+        self.cluster_headers.push(EntityHeader::from_name(BlockGroupEntity::HASHNAME, next_entity_id(&self.cluster_headers),
+            GROUP_ID_CLUSTERS, BlockGroupEntity::serialized_components()));
+        self.cluster_entities.push(BlockGroupEntity {
+            saved_block_group_id: SavedBlockGroupIdComponent { saved_block_group_id: id },
+            block_group_transform: BlockGroupTransformEntityComponent {
+                block_group_grid_position: array_to_float3(position),
+                block_group_grid_rotation: array_to_quaternion(rotation),
+            },
+        });
+        id
+    }
+
+    /// Add a new standard block, returning its index.
+    pub fn push_block(&mut self, block: VehicleBlock) -> usize {
+        let index = self.block_entities.len();
+        self.block_headers.push(EntityHeader::from_name(BlockEntity::HASHNAME, next_entity_id(&self.block_headers),
+            GROUP_ID_BLOCKS, BlockEntity::serialized_components()));
+        self.block_entities.push(Box::new(block.to_entity()));
+        index
+    }
+
+    /// Count of blocks for every block id.
+    pub fn block_counts(&self) -> HashMap<u32, usize> {
+        let mut counts = HashMap::new();
+        for block in self.blocks() {
+            *counts.entry(block.block_id).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Material identifiers used by blocks.
+    pub fn materials_used(&self) -> HashSet<u8> {
+        self.blocks().map(|b| b.material).collect()
+    }
+
+    /// Minimum and maximum corners of the axis-aligned box around all blocks, or None when the vehicle has no blocks.
+    ///
+    /// Every block is treated as a box of `BLOCK_SIZE` times its scale, centred on its position.
+    pub fn bounding_box(&self) -> Option<([f32; 3], [f32; 3])> {
+        let mut blocks = self.blocks().map(|block| {
+            let extent = block.half_extent();
+            let mut min = block.position;
+            let mut max = block.position;
+            for axis in 0..3 {
+                min[axis] -= extent[axis];
+                max[axis] += extent[axis];
+            }
+            (min, max)
+        });
+        let first = blocks.next()?;
+        Some(blocks.fold(first, |(mut min, mut max), (block_min, block_max)| {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(block_min[axis]);
+                max[axis] = max[axis].max(block_max[axis]);
+            }
+            (min, max)
+        }))
+    }
+
+    /// Remove the block at index i.
+    pub fn remove_block(&mut self, i: usize) -> Option<Box<dyn Block>> {
+        if i < self.block_entities.len() {
+            self.block_headers.remove(i);
+            Some(self.block_entities.remove(i))
+        } else {
+            None
+        }
+    }
+}
+
+impl Parsable for Vehicle {
+    /// Process raw (not base64) vehicle data.
+    fn parse(data: &mut dyn Read) -> std::io::Result<Self> {
+        Self::parse_with_registry(data, &DescriptorRegistry::new())
+    }
+
+    fn dump(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
+        if self.cluster_headers.len() != self.cluster_entities.len() || self.block_headers.len() != self.block_entities.len() {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Vehicle entity and header counts do not match"));
+        }
+        let mut write_count: usize = 0;
+        write_count += self.version.year().dump(writer)?;
+        write_count += self.version.month().dump(writer)?;
+        write_count += self.version.day().dump(writer)?;
+        write_count += (self.cluster_entities.len() as u32).dump(writer)?;
+        for (header, cluster) in self.cluster_headers.iter().zip(self.cluster_entities.iter()) {
+            write_count += header.dump_versioned(writer, &self.version)?;
+            write_count += cluster.dump_versioned(writer, &self.version)?;
+        }
+        write_count += (self.block_entities.len() as u32).dump(writer)?;
+        for (header, block) in self.block_headers.iter().zip(self.block_entities.iter()) {
+            write_count += header.dump_versioned(writer, &self.version)?;
+            write_count += block.dump_versioned(writer, &self.version)?;
+        }
+        Ok(write_count)
+    }
+}
+
+impl VehicleBlock {
+    /// Half the size of the rotated block along each axis, in metres
+    fn half_extent(&self) -> [f32; 3] {
+        let [x, y, z, w] = self.rotation;
+        // rotation matrix of the quaternion
+        let rotation = [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+            [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+            [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+        ];
+        let size = self.scale.map(|s| s.abs() * BLOCK_SIZE / 2.0);
+        rotation.map(|row| (0..3).map(|i| row[i].abs() * size[i]).sum())
+    }
+
+    fn from_entity(entity: &BlockEntity) -> Self {
+        Self {
+            block_id: entity.db_component.dbid,
+            position: float3_to_array(&entity.pos_component.position),
+            rotation: quaternion_to_array(&entity.rot_component.rotation),
+            scale: float3_to_array(&entity.scale_component.scale),
+            colour: entity.colour_component.index_in_palette,
+            material: entity.material_component.material_id,
+            cluster: entity.group_component.current_block_group,
+        }
+    }
+
+    fn to_entity(self) -> BlockEntity {
+        let position = array_to_float3(self.position);
+        let rotation = array_to_quaternion(self.rotation);
+        BlockEntity {
+            db_component: DBEntityStruct { dbid: self.block_id },
+            pos_component: PositionEntityStruct { position },
+            scale_component: ScalingEntityStruct { scale: array_to_float3(self.scale) },
+            rot_component: RotationEntityStruct { rotation },
+            skew_component: SkewComponent { skew_matrix: identity_matrix() },
+            grid_component: GridRotationStruct { rotation, position },
+            grid_conn_component: SerializedGridConnectionsEntityStruct {},
+            placement_component: SerializedBlockPlacementInfoStruct {},
+            material_component: SerializedCubeMaterialStruct { material_id: self.material },
+            uscale_component: SerializedUniformBlockScaleEntityStruct { scale_factor: 1 },
+            colour_component: SerializedColourParameterEntityStruct { index_in_palette: self.colour },
+            group_component: BlockGroupEntityComponent { current_block_group: self.cluster },
+        }
+    }
+}
+
+/// Entity id after the largest id in use, so that ids stay unique after entities are removed
+fn next_entity_id(headers: &[EntityHeader]) -> u32 {
+    headers.iter().map(|h| h.entity_id + 1).max().unwrap_or(0)
+}
+
+fn float3_to_array(f: &UnityFloat3) -> [f32; 3] {
+    [f.x, f.y, f.z]
+}
+
+fn array_to_float3(a: [f32; 3]) -> UnityFloat3 {
+    UnityFloat3 { x: a[0], y: a[1], z: a[2] }
+}
+
+fn quaternion_to_array(q: &UnityQuaternion) -> [f32; 4] {
+    [q.value.x, q.value.y, q.value.z, q.value.w]
+}
+
+fn array_to_quaternion(a: [f32; 4]) -> UnityQuaternion {
+    UnityQuaternion { value: UnityFloat4 { x: a[0], y: a[1], z: a[2], w: a[3] } }
+}
+
+fn identity_matrix() -> UnityFloat4x4 {
+    UnityFloat4x4 {
+        c0: UnityFloat4 { x: 1.0, y: 0.0, z: 0.0, w: 0.0 },
+        c1: UnityFloat4 { x: 0.0, y: 1.0, z: 0.0, w: 0.0 },
+        c2: UnityFloat4 { x: 0.0, y: 0.0, z: 1.0, w: 0.0 },
+        c3: UnityFloat4 { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
+    }
+}
+
+/// Category of CPU which a block uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum CpuCategory {
+    /// Structure and movement blocks
+    #[default]
+    Base,
+    /// Weapon blocks
+    Weapon,
+    /// Cosmetic blocks
+    Cosmetic,
+}
+
+/// CPU usage of a single block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct BlockStats {
+    /// CPU used by the block
+    pub cpu: u32,
+    /// CPU category of the block
+    pub category: CpuCategory,
+}
+
+/// Per-block-id lookup table of block CPU usage, used for calculating vehicle CPU.
+///
+/// No block stats are built in, since RC2 is still changing them.
+pub type BlockTable = IdTable<BlockStats>;
+
+impl IdTable<BlockStats> {
+    /// Calculate the CPU of a vehicle.
+    ///
+    /// This fails with `VehicleError::UnknownBlocks` when a block id is not in the table, since the total would be wrong.
+    pub fn cpu(&self, vehicle: &Vehicle) -> Result<VehicleCpu, VehicleError> {
+        let mut cpu = VehicleCpu::default();
+        let mut unknown = Vec::new();
+        for (id, count) in vehicle.block_counts() {
+            match self.get(id) {
+                Some(stats) => {
+                    let total = stats.cpu as usize * count;
+                    match stats.category {
+                        CpuCategory::Base => cpu.base += total,
+                        CpuCategory::Weapon => cpu.weapon += total,
+                        CpuCategory::Cosmetic => cpu.cosmetic += total,
+                    }
+                },
+                None => unknown.push(id),
+            }
+        }
+        if unknown.is_empty() {
+            Ok(cpu)
+        } else {
+            unknown.sort_unstable();
+            Err(VehicleError::UnknownBlocks(unknown))
+        }
+    }
+}
+
+/// CPU used by a vehicle, per category.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct VehicleCpu {
+    /// CPU of structure and movement blocks
+    pub base: usize,
+    /// CPU of weapon blocks
+    pub weapon: usize,
+    /// CPU of cosmetic blocks
+    pub cosmetic: usize,
+}
@@ -0,0 +1,178 @@
+use std::sync::Mutex;
+
+use ureq::{Agent, Error, Response};
+use url::Url;
+
+use crate::robocraft2::factory::{FACTORY_DOMAIN, search_url};
+use crate::robocraft2::{ErrorPayload, SearchPayload, SearchResponse, CreateRobotPayload, CreateRobotResponse, FactoryInfoResponse, PublishRobotPayload, PublishRobotResponse, MyRobotsResponse, GetRobotResponse, ModerateRobotPayload, ReportRobotPayload};
+use crate::robocraft2_simple::ITokenProvider;
+use crate::retry::RequestPolicy;
+
+/// Simpler CRF API error
+#[derive(Debug)]
+pub enum FactoryError {
+    /// HTTP, network or JSON error
+    Protocol(Box<Error>),
+    /// Error returned by the CRF
+    Response(ErrorPayload),
+    /// Unsuccessful HTTP status code, without an error payload
+    ResponseCode(std::io::Error, u16)
+}
+
+impl std::fmt::Display for FactoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Protocol(p) => write!(f, "HTTP Error: {}", p),
+            Self::Response(r) => write!(f, "Factory Error #{}: {}", r.error, r.error_message),
+            Self::ResponseCode(p, s) => write!(f, "HTTP Error {}: {}", s, p)
+        }
+    }
+}
+
+impl std::error::Error for FactoryError {}
+
+fn handle_response(result: Result<Response, Box<Error>>) -> Result<Response, FactoryError> {
+    match result.map_err(|e| *e) {
+        Ok(response) => Ok(response),
+        Err(Error::Status(status_code, response)) => match response.into_json::<ErrorPayload>() {
+            Ok(err) => Err(FactoryError::Response(err)),
+            Err(e) => Err(FactoryError::ResponseCode(e, status_code))
+        },
+        Err(e) => Err(FactoryError::Protocol(Box::new(e))),
+    }
+}
+
+fn handle_json_response<D: serde::de::DeserializeOwned>(result: Result<Response, Box<Error>>) -> Result<D, FactoryError> {
+    handle_response(result)?
+        .into_json::<D>()
+        .map_err(|e| FactoryError::Protocol(Box::new(e.into())))
+}
+
+/// Simpler CRF API implementation.
+/// Refer to libfj::robocraft2::FactoryAPI for in-depth documentation.
+/// The only API difference is that this API is blocking (i.e. no async).
+/// This version also works with Wine and Proton since it does not rely on tokio.
+pub struct FactoryAPI {
+    client: Agent,
+    token: Mutex<Box<dyn ITokenProvider + Send>>,
+    policy: RequestPolicy,
+}
+
+impl FactoryAPI {
+    /// Create a new instance using the provided token provider.
+    pub fn with_auth(token_provider: Box<dyn ITokenProvider + Send>) -> FactoryAPI {
+        FactoryAPI {
+            client: Agent::new(),
+            token: Mutex::new(token_provider),
+            policy: RequestPolicy::default(),
+        }
+    }
+
+    /// Replace the retry and rate limit policy used for requests.
+    ///
+    /// Only idempotent requests (searching, getting and deleting robots) are retried.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> FactoryAPI {
+        self.policy = policy;
+        self
+    }
+
+    fn url(path: &str) -> Url {
+        Url::parse(FACTORY_DOMAIN)
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    fn auth(&self) -> Result<String, FactoryError> {
+        let token = self.token.lock().unwrap().token().map_err(FactoryError::Protocol)?;
+        Ok("Bearer ".to_owned() + &token)
+    }
+
+    /// Retrieve CRF robots on the main page.
+    pub fn list(&self) -> Result<SearchResponse, FactoryError> {
+        self.search(SearchPayload::default())
+    }
+
+    /// Search for robots on the CRF which meet the provided parameters
+    pub fn search(&self, params: SearchPayload) -> Result<SearchResponse, FactoryError> {
+        let request = self.client.get(search_url(&params).as_str())
+            .set("Authorization", &self.auth()?);
+        let result = self.policy.send_blocking(&request, None, true);
+        handle_json_response::<SearchResponse>(result)
+    }
+
+    pub fn create_robot(&self, robot: CreateRobotPayload) -> Result<CreateRobotResponse, FactoryError> {
+        let request = self.client.post(Self::url("/v1/foundry/garage").as_str())
+            .set("Authorization", &self.auth()?)
+            .set("Content-Type", "application/json");
+        let result = self.policy.send_blocking(&request, Some(&serde_json::to_string(&robot).unwrap()), false);
+        handle_json_response::<CreateRobotResponse>(result)
+    }
+
+    pub fn publish_robot(&self, robot: PublishRobotPayload, id: String) -> Result<PublishRobotResponse, FactoryError> {
+        let request = self.client.post(Self::url(&format!("/v1/foundry/vehicles/{}/publish", id)).as_str())
+            .set("Authorization", &self.auth()?)
+            .set("Content-Type", "application/json");
+        let result = self.policy.send_blocking(&request, Some(&serde_json::to_string(&robot).unwrap()), false);
+        handle_json_response::<PublishRobotResponse>(result)
+    }
+
+    pub fn unpublish_bot(&self, id: String) -> Result<(), FactoryError> {
+        let request = self.client.post(Self::url(&format!("/v1/foundry/vehicles/{}/unpublish", id)).as_str())
+            .set("Authorization", &self.auth()?);
+        let result = self.policy.send_blocking(&request, None, false);
+        handle_response(result).map(|_| ())
+    }
+
+    pub fn delete_robot(&self, id: String) -> Result<(), FactoryError> {
+        let request = self.client.delete(Self::url(&format!("/v1/foundry/vehicles/{}", id)).as_str())
+            .set("Authorization", &self.auth()?)
+            .set("Content-Type", "application/json");
+        let result = self.policy.send_blocking(&request, None, true);
+        handle_response(result).map(|_| ())
+    }
+
+    pub fn factory_info(&self) -> Result<FactoryInfoResponse, FactoryError> {
+        let request = self.client.get(Self::url("/v1/foundry/info").as_str())
+            .set("Authorization", &self.auth()?);
+        let result = self.policy.send_blocking(&request, None, true);
+        handle_json_response::<FactoryInfoResponse>(result)
+    }
+
+    pub fn my_robots(&self) -> Result<MyRobotsResponse, FactoryError> {
+        let request = self.client.get(Self::url("/v1/foundry/garage").as_str())
+            .set("Authorization", &self.auth()?);
+        let result = self.policy.send_blocking(&request, None, true);
+        handle_json_response::<MyRobotsResponse>(result)
+    }
+
+    pub fn my_published_robots(&self) -> Result<MyRobotsResponse, FactoryError> {
+        let request = self.client.get(Self::url("/v1/foundry/published").as_str())
+            .set("Authorization", &self.auth()?);
+        let result = self.policy.send_blocking(&request, None, true);
+        handle_json_response::<MyRobotsResponse>(result)
+    }
+
+    pub fn get(&self, id: String) -> Result<GetRobotResponse, FactoryError> {
+        let request = self.client.get(Self::url(&format!("/v1/foundry/vehicles/{}", id)).as_str())
+            .set("Authorization", &self.auth()?);
+        let result = self.policy.send_blocking(&request, None, true);
+        handle_json_response::<GetRobotResponse>(result)
+    }
+
+    pub fn moderate(&self, payload: ModerateRobotPayload, id: String) -> Result<(), FactoryError> {
+        let request = self.client.post(Self::url(&format!("/v1/foundry/vehicles/{}/moderate", id)).as_str())
+            .set("Authorization", &self.auth()?)
+            .set("Content-Type", "application/json");
+        let result = self.policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), false);
+        handle_response(result).map(|_| ())
+    }
+
+    pub fn report(&self, payload: ReportRobotPayload, id: String) -> Result<(), FactoryError> {
+        let request = self.client.post(Self::url(&format!("/v1/foundry/vehicles/{}/report", id)).as_str())
+            .set("Authorization", &self.auth()?)
+            .set("Content-Type", "application/json");
+        let result = self.policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), false);
+        handle_response(result).map(|_| ())
+    }
+}
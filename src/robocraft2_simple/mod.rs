@@ -0,0 +1,8 @@
+//! Simple, blocking Robocraft2 APIs for the CRF.
+//! Subject to change and breakages as RC2 is still in an early development stage.
+
+mod factory;
+pub use factory::{FactoryAPI, FactoryError};
+
+mod portal;
+pub use self::portal::{PortalTokenProvider, ITokenProvider};
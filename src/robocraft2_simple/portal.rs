@@ -0,0 +1,180 @@
+use ureq::{Agent, Error};
+use chrono::{DateTime, Utc};
+
+use crate::robocraft2::AccountInfo;
+use crate::robocraft2::portal::{GAME_TARGET, GAME_VERSION, AuthenticationEmailPayload, AuthenticationUsernamePayload,
+AuthenticationResponseInfo, PortalStartPayload, PortalStartResponse, PortalCheckPayload, PortalCheckResponse,
+ProgressionLoginPayload, ProgressionLoginResponse, RefreshTokenPayload};
+use crate::retry::RequestPolicy;
+
+/// Token generator for authenticated API endpoints
+pub trait ITokenProvider {
+    /// Retrieve the token to use
+    fn token(&mut self) -> Result<String, Box<Error>>;
+}
+
+/// Simpler token provider for an existing Freejam account.
+/// Refer to libfj::robocraft2::PortalTokenProvider for in-depth documentation.
+/// The only API difference is that this API is blocking (i.e. no async), and there is no browser portal login.
+///
+/// Portal and token refresh requests are sent through a `RequestPolicy`, and are retried when the policy allows it.
+/// Requests which send the account password are never retried.
+///
+/// Steam and Epic accounts are not supported.
+pub struct PortalTokenProvider {
+    /// Login token
+    token: ProgressionLoginResponse,
+    /// User info token
+    jwt: PortalCheckResponse,
+    /// Ureq HTTP client
+    client: Agent,
+    /// target game
+    target: String,
+    /// game version
+    version: String,
+    /// Retry and rate limit policy
+    policy: RequestPolicy,
+}
+
+impl PortalTokenProvider {
+    /// Login using the email address and password of a Freejam account
+    pub fn with_email(email: &str, password: &str) -> Result<Self, Box<Error>> {
+        Self::with_email_and_policy(email, password, RequestPolicy::default())
+    }
+
+    /// Login using the email address and password of a Freejam account, sending requests through `policy`
+    pub fn with_email_and_policy(email: &str, password: &str, policy: RequestPolicy) -> Result<Self, Box<Error>> {
+        let client = Agent::new();
+        let payload = AuthenticationEmailPayload {
+            email_address: email.to_string(),
+            password: password.to_string(),
+        };
+        let request = client.post("https://account.freejamgames.com/api/authenticate/email/web")
+            .set("Content-Type", "application/json");
+        let json_res = policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), false)?
+            .into_json::<AuthenticationResponseInfo>()
+            .map_err(Error::from)?;
+        Self::auto_portal(client, GAME_TARGET.to_owned(), json_res.token, GAME_VERSION.to_owned(), policy)
+    }
+
+    /// Login using the display name and password of a Freejam account
+    pub fn with_username(username: &str, password: &str) -> Result<Self, Box<Error>> {
+        Self::with_username_and_policy(username, password, RequestPolicy::default())
+    }
+
+    /// Login using the display name and password of a Freejam account, sending requests through `policy`
+    pub fn with_username_and_policy(username: &str, password: &str, policy: RequestPolicy) -> Result<Self, Box<Error>> {
+        let client = Agent::new();
+        let payload = AuthenticationUsernamePayload {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        let request = client.post("https://account.freejamgames.com/api/authenticate/displayname/web")
+            .set("Content-Type", "application/json");
+        let json_res = policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), false)?
+            .into_json::<AuthenticationResponseInfo>()
+            .map_err(Error::from)?;
+        Self::auto_portal(client, GAME_TARGET.to_owned(), json_res.token, GAME_VERSION.to_owned(), policy)
+    }
+
+    /// Automatically validate portal
+    fn auto_portal(client: Agent, value: String, token: String, version: String, policy: RequestPolicy) -> Result<Self, Box<Error>> {
+        let payload = PortalStartPayload {
+            target: value.clone(),
+        };
+        let request = client.post("https://account.freejamgames.com/api/authenticate/portal/start")
+            .set("Content-Type", "application/json");
+        let start_res = policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), true)?
+            .into_json::<PortalStartResponse>()
+            .map_err(Error::from)?;
+        let payload = PortalCheckPayload {
+            token: start_res.token,
+        };
+
+        let request = client.post("https://account.freejamgames.com/api/authenticate/portal/assign")
+            .set("Content-Type", "application/json")
+            .set("Authorization", &("Web ".to_owned() + &token));
+        let _assign_response = policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), true)?;
+
+        let request = client.post("https://account.freejamgames.com/api/authenticate/portal/check")
+            .set("Content-Type", "application/json");
+        let check_res = policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), true)?
+            .into_json::<PortalCheckResponse>()
+            .map_err(Error::from)?;
+
+        // login with token we just got
+        Self::login_internal(check_res, client, value, version, policy)
+    }
+
+    fn login_internal(token_data: PortalCheckResponse, client: Agent, target: String, version: String, policy: RequestPolicy) -> Result<Self, Box<Error>> {
+        let progress_res = Self::login_step(&token_data, &client, version.clone(), &policy)?;
+        Ok(Self {
+            token: progress_res,
+            jwt: token_data,
+            client,
+            target,
+            version,
+            policy,
+        })
+    }
+
+    fn login_step(token_data: &PortalCheckResponse, client: &Agent, version: String, policy: &RequestPolicy) -> Result<ProgressionLoginResponse, Box<Error>> {
+        let payload = ProgressionLoginPayload {
+            token: token_data.token.clone(),
+            client_version: version,
+        };
+        let request = client.post("https://progression.production.robocraft2.com/login/fj")
+            .set("Content-Type", "application/json");
+        let progress_res = policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), true)?
+            .into_json::<ProgressionLoginResponse>()
+            .map_err(Error::from)?;
+        Ok(progress_res)
+    }
+
+    /// Login using the portal token data from a previous portal authentication
+    pub fn login(token_data: PortalCheckResponse, target: String, version: String) -> Result<Self, Box<Error>> {
+        Self::login_with_policy(token_data, target, version, RequestPolicy::default())
+    }
+
+    /// Login using the portal token data from a previous portal authentication, sending requests through `policy`
+    pub fn login_with_policy(token_data: PortalCheckResponse, target: String, version: String, policy: RequestPolicy) -> Result<Self, Box<Error>> {
+        Self::login_internal(token_data, Agent::new(), target, version, policy)
+    }
+
+    /// Replace the retry and rate limit policy used for token refreshes.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn get_account_info(&self) -> Result<AccountInfo, Box<Error>> {
+        Ok(self.jwt.decode_jwt_data())
+    }
+
+    pub fn token_data(&self) -> &'_ PortalCheckResponse {
+        &self.jwt
+    }
+}
+
+impl ITokenProvider for PortalTokenProvider {
+    fn token(&mut self) -> Result<String, Box<Error>> {
+        let decoded_jwt = self.jwt.decode_jwt_data();
+        let expiry = DateTime::<Utc>::from_timestamp(decoded_jwt.exp as i64, 0).unwrap();
+        let now = Utc::now();
+        if now >= expiry || self.token.token.is_none() {
+            // refresh token when expired
+            let payload = RefreshTokenPayload {
+                target: self.target.clone(),
+                refresh_token: self.jwt.refresh_token.clone(),
+                public_id: decoded_jwt.public_id,
+            };
+            let request = self.client.post("https://account.freejamgames.com/api/authenticate/token/refresh")
+                .set("Content-Type", "application/json");
+            self.jwt = self.policy.send_blocking(&request, Some(&serde_json::to_string(&payload).unwrap()), true)?
+                .into_json::<PortalCheckResponse>()
+                .map_err(Error::from)?;
+            self.token = Self::login_step(&self.jwt, &self.client, self.version.clone(), &self.policy)?;
+        }
+        Ok(self.token.token.clone().unwrap())
+    }
+}
@@ -5,6 +5,7 @@ use serde_json::{to_string};
 use crate::robocraft::{ITokenProvider, DefaultTokenProvider, FACTORY_DOMAIN, FactoryInfo, RoboShopItemsInfo, FactoryRobotGetInfo};
 use crate::robocraft::{ListPayload};
 use crate::robocraft_simple::FactorySearchBuilder;
+use crate::retry::RequestPolicy;
 
 /// Simpler CRF API implementation.
 /// Refer to libfj::robocraft::FactoryAPI for in-depth documentation.
@@ -13,6 +14,7 @@ use crate::robocraft_simple::FactorySearchBuilder;
 pub struct FactoryAPI {
     client: Agent,
     token: Box<dyn ITokenProvider>,
+    policy: RequestPolicy,
 }
 
 impl FactoryAPI {
@@ -21,8 +23,17 @@ impl FactoryAPI {
         FactoryAPI {
             client: Agent::new(),
             token: Box::new(DefaultTokenProvider{}),
+            policy: RequestPolicy::default(),
         }
     }
+
+    /// Replace the retry and rate limit policy used for requests.
+    ///
+    /// Listing and getting robots are both retried, since they do not change anything.
+    pub fn with_policy(mut self, policy: RequestPolicy) -> FactoryAPI {
+        self.policy = policy;
+        self
+    }
     
     /// List CRF robots
     pub fn list(&self) -> Result<FactoryInfo<RoboShopItemsInfo>, Error> {
@@ -36,7 +47,8 @@ impl FactoryAPI {
         if let Ok(token) = self.token.token() {
             request_builder = request_builder.set("Authorization", &("Web ".to_owned() + &token));
         }
-        let result = request_builder.send_string(&to_string(&payload).unwrap());
+        let body = to_string(&payload).unwrap();
+        let result = self.policy.send_blocking(&request_builder, Some(&body), true);
         if let Ok(response) = result {
             let json_res = response.into_json::<FactoryInfo<RoboShopItemsInfo>>();
             if let Ok(json) = json_res {
@@ -44,7 +56,7 @@ impl FactoryAPI {
             }
             return Err(Error::Status(500, Response::new(500, "Malformed JSON", "").unwrap())); // server returned malformed data
         }
-        Err(result.err().unwrap())
+        Err(*result.err().unwrap())
     }
     
     /// Build a list query
@@ -58,7 +70,7 @@ impl FactoryAPI {
             token_opt = Some(token);
         }
         let request_builder = self.client.post(url.as_str());
-        FactorySearchBuilder::new(request_builder, token_opt)
+        FactorySearchBuilder::new(request_builder, token_opt, self.policy.clone())
     }
     
     /// Get complete information on a robot.
@@ -71,7 +83,7 @@ impl FactoryAPI {
         if let Ok(token) = self.token.token() {
             request_builder = request_builder.set("Authorization", &("Web ".to_owned() + &token));
         }
-        let result = request_builder.call();
+        let result = self.policy.send_blocking(&request_builder, None, true);
         if let Ok(response) = result {
             let json_res = response.into_json::<FactoryInfo<FactoryRobotGetInfo>>();
             if let Ok(json) = json_res {
@@ -79,6 +91,6 @@ impl FactoryAPI {
             }
             return Err(Error::Status(500, Response::new(500, "Malformed JSON", "").unwrap())); // server returned malformed data
         }
-        Err(result.err().unwrap())
+        Err(*result.err().unwrap())
     }
 }
@@ -1,7 +1,8 @@
 use ureq::{Request, Response, Error};
 
-use crate::robocraft::{FactoryInfo, RoboShopItemsInfo, FactoryTextSearchType, FactoryWeaponType, FactoryMovementType, FactoryOrderType};
-use crate::robocraft::{ListPayload};
+use crate::robocraft::{FactoryInfo, RoboShopItemsInfo, FactoryRobotListInfo, FactoryTextSearchType, FactoryWeaponType, FactoryMovementType, FactoryOrderType, FactoryTypeFilter, FactoryQuery};
+use crate::paging::Pager;
+use crate::retry::RequestPolicy;
 
 /// Factory API list query builder.
 /// This is the simpler, blocking equivalent of libfj::robocraft::FactorySearchBuilder.
@@ -9,34 +10,47 @@ use crate::robocraft::{ListPayload};
 #[derive(Clone)]
 pub struct FactorySearchBuilder {
     reqwest_builder: Request,
-    payload: ListPayload,
+    query: FactoryQuery,
     token: Option<String>,
+    policy: RequestPolicy,
 }
 
 impl FactorySearchBuilder {
-    pub(crate) fn new(request_builder: Request, token: Option<String>) -> FactorySearchBuilder {
+    pub(crate) fn new(request_builder: Request, token: Option<String>, policy: RequestPolicy) -> FactorySearchBuilder {
         FactorySearchBuilder {
             reqwest_builder: request_builder.set("Content-Type", "application/json"),
-            payload: ListPayload::empty(),
+            query: FactoryQuery::new(),
             token,
+            policy,
         }
     }
+
+    /// Replace all query parameters
+    pub fn with_query(mut self, query: FactoryQuery) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Get query parameters
+    pub fn query(&self) -> &FactoryQuery {
+        &self.query
+    }
     
     /// Set page number
     pub fn page(mut self, page_number: isize) -> Self {
-        self.payload.page = page_number;
+        self.query = self.query.page(page_number);
         self
     }
     
     /// Set page size
     pub fn items_per_page(mut self, page_size: isize) -> Self {
-        self.payload.page_size = page_size;
+        self.query = self.query.items_per_page(page_size);
         self
     }
     
     /// Set results ordering
     pub fn order(mut self, order_type: FactoryOrderType) -> Self {
-        self.payload.order = order_type as isize;
+        self.query = self.query.order(order_type);
         self
     }
     
@@ -48,101 +62,122 @@ impl FactorySearchBuilder {
     }
     */
     
-    /// Override movement filter
+    /// Override movement filter, using comma-separated ids
     pub fn movement_raw(mut self, filter: String) -> Self {
-        self.payload.movement_filter = filter.clone();
-        self.payload.movement_category_filter = filter.clone();
+        self.query = self.query.movement_raw(filter);
         self
     }
     
     /// Add allowed movement type
     pub fn movement_or(mut self, movement_type: FactoryMovementType) -> Self {
-        if self.payload.movement_filter == "" {
-            self.payload.movement_filter = format!("{},{}", &self.payload.movement_filter, movement_type as isize);
-        } else {
-            self.payload.movement_filter = (movement_type as isize).to_string();
-        }
-        self.payload.movement_category_filter = self.payload.movement_filter.clone();
+        self.query = self.query.movement_or(movement_type);
         self
     }
+
+    /// Add allowed movement sub-category id
+    pub fn movement_subcategory_or(mut self, movement_type: FactoryMovementType, id: u32) -> Self {
+        self.query = self.query.movement_subcategory_or(movement_type, id);
+        self
+    }
+
+    /// Override movement filter
+    pub fn movement_filter(mut self, filter: FactoryTypeFilter<FactoryMovementType>) -> Self {
+        self.query = self.query.movement_filter(filter);
+        self
+    }
+
+    /// Get movement filter
+    pub fn movements(&self) -> &FactoryTypeFilter<FactoryMovementType> {
+        self.query.movements()
+    }
     
-    /// Override weapon filter
+    /// Override weapon filter, using comma-separated ids
     pub fn weapon_raw(mut self, filter: String) -> Self {
-        self.payload.weapon_filter = filter.clone();
-        self.payload.weapon_category_filter = filter.clone();
+        self.query = self.query.weapon_raw(filter);
         self
     }
     
     /// Add allowed weapon type
     pub fn weapon_or(mut self, weapon_type: FactoryWeaponType) -> Self {
-        if self.payload.weapon_filter == "" {
-            self.payload.weapon_filter = format!("{},{}", &self.payload.weapon_filter, weapon_type as isize);
-        } else {
-            self.payload.weapon_filter = (weapon_type as isize).to_string();
-        }
-        self.payload.weapon_category_filter = self.payload.weapon_filter.clone();
+        self.query = self.query.weapon_or(weapon_type);
         self
     }
-    
+
+    /// Add allowed weapon sub-category id
+    pub fn weapon_subcategory_or(mut self, weapon_type: FactoryWeaponType, id: u32) -> Self {
+        self.query = self.query.weapon_subcategory_or(weapon_type, id);
+        self
+    }
+
+    /// Override weapon filter
+    pub fn weapon_filter(mut self, filter: FactoryTypeFilter<FactoryWeaponType>) -> Self {
+        self.query = self.query.weapon_filter(filter);
+        self
+    }
+
+    /// Get weapon filter
+    pub fn weapons(&self) -> &FactoryTypeFilter<FactoryWeaponType> {
+        self.query.weapons()
+    }
+
     /// Set CPU value min and max
     pub fn cpu_range(mut self, min: isize, max: isize) -> Self {
-        self.payload.minimum_cpu = min;
-        self.payload.maximum_cpu = max;
+        self.query = self.query.cpu_range(min, max);
         self
     }
     
     /// Set CPU minimum value
     pub fn min_cpu(mut self, min: isize) -> Self {
-        self.payload.minimum_cpu = min;
+        self.query = self.query.min_cpu(min);
         self
     }
     
     /// Set CPU maximum value
     pub fn max_cpu(mut self, max: isize) -> Self {
-        self.payload.maximum_cpu = max;
+        self.query = self.query.max_cpu(max);
         self
     }
     
     /// Removem minimum CPU limit
     pub fn no_minimum_cpu(mut self) -> Self {
-        self.payload.minimum_cpu = -1;
+        self.query = self.query.no_minimum_cpu();
         self
     }
     
     /// Remove maximum CPU limit
     pub fn no_maximum_cpu(mut self) -> Self {
-        self.payload.maximum_cpu = -1;
+        self.query = self.query.no_maximum_cpu();
         self
     }
     
     /// Set text filter
     pub fn text(mut self, t: String) -> Self {
-        self.payload.text_filter = t;
+        self.query = self.query.text(t);
         self
     }
     
     /// Set fields which text filter searches
     pub fn text_search_type(mut self, search_type: FactoryTextSearchType) -> Self {
-        self.payload.text_search_field = search_type as isize;
+        self.query = self.query.text_search_type(search_type);
         self
     }
     
     // setting buyable to false while using the default token provider will cause HTTP status 500 error
     /// Only search robots which can be bought by the current account?
     pub fn buyable(mut self, b: bool) -> Self {
-        self.payload.buyable = b;
+        self.query = self.query.buyable(b);
         self
     }
     
     /// Prepend a featured robot to the response?
     pub fn prepend_featured(mut self, b: bool) -> Self {
-        self.payload.prepend_featured_robot = b;
+        self.query = self.query.prepend_featured(b);
         self
     }
     
     /// Retrieve default CRF page?
     pub fn default_page(mut self, b: bool) -> Self {
-        self.payload.default_page = b;
+        self.query = self.query.default_page(b);
         self
     }
     
@@ -152,7 +187,8 @@ impl FactorySearchBuilder {
         if let Some(token) = self.token.clone() {
             self.reqwest_builder = self.reqwest_builder.set("Authorization", &("Web ".to_owned() + &token));
         }
-        let result = self.reqwest_builder.send_string(&serde_json::to_string(&self.payload).unwrap());
+        let body = serde_json::to_string(&self.query.payload).unwrap();
+        let result = self.policy.send_blocking(&self.reqwest_builder, Some(&body), true);
         if let Ok(response) = result {
             let json_res = response.into_json::<FactoryInfo<RoboShopItemsInfo>>();
             if let Ok(json) = json_res {
@@ -160,6 +196,47 @@ impl FactorySearchBuilder {
             }
             return Err(Error::Status(500, Response::new(500, "Malformed JSON", "").unwrap())); // server returned malformed data
         }
-        Err(result.err().unwrap())
+        Err(*result.err().unwrap())
+    }
+
+    /// Execute list query for every page.
+    ///
+    /// This is the blocking equivalent of libfj::robocraft::FactorySearchBuilder.stream().
+    pub fn iter(self) -> FactorySearchIter {
+        FactorySearchIter {
+            pager: Pager::new(self.query.payload.page, |robot: &FactoryRobotListInfo| robot.item_id),
+            builder: self,
+        }
+    }
+}
+
+/// Blocking iterator over every robot of a list query, retrieving pages as needed.
+///
+/// Robots which already appeared on an earlier page are skipped.
+/// Iteration ends when a page is empty (or contains no new robots), or after the first error.
+pub struct FactorySearchIter {
+    builder: FactorySearchBuilder,
+    pager: Pager<FactoryRobotListInfo, usize>,
+}
+
+impl Iterator for FactorySearchIter {
+    type Item = Result<FactoryRobotListInfo, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(robot) = self.pager.next_item() {
+                return Some(Ok(robot));
+            }
+            if self.pager.is_done() {
+                return None;
+            }
+            match self.builder.clone().page(self.pager.page()).send() {
+                Ok(info) => self.pager.add_page(info.response.roboshop_items),
+                Err(e) => {
+                    self.pager.stop();
+                    return Some(Err(e));
+                }
+            }
+        }
     }
 }
@@ -3,4 +3,4 @@
 mod factory;
 mod factory_request_builder;
 pub use factory::{FactoryAPI};
-pub use factory_request_builder::{FactorySearchBuilder};
+pub use factory_request_builder::{FactorySearchBuilder, FactorySearchIter};
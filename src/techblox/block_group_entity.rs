@@ -2,7 +2,8 @@ use crate::techblox::{UnityFloat3, UnityQuaternion, SerializedEntityComponent, S
 use libfj_parsable_macro_derive::*;
 
 /// Block group entity descriptor.
-#[derive(Clone, Copy, Parsable)]
+#[derive(Clone, Copy, Parsable, EntityDescriptor)]
+#[descriptor(name = "BlockGroupEntityDescriptorV0")]
 pub struct BlockGroupEntity {
     /// Block group identifier
     pub saved_block_group_id: SavedBlockGroupIdComponent,
@@ -12,26 +13,6 @@ pub struct BlockGroupEntity {
 
 impl BlockGroupEntity {}
 
-impl SerializedEntityDescriptor for BlockGroupEntity {
-    fn serialized_components() -> u8 {
-        2
-    }
-
-    fn components<'a>(&'a self) -> Vec<&'a dyn SerializedEntityComponent> {
-        vec![&self.saved_block_group_id,
-        &self.block_group_transform]
-    }
-
-    fn components_mut<'a>(&'a mut self) -> Vec<&'a mut dyn SerializedEntityComponent> {
-        vec![&mut self.saved_block_group_id,
-        &mut self.block_group_transform]
-    }
-
-    fn hash_name(&self) -> u32 {
-        Self::hash("BlockGroupEntityDescriptorV0")
-    }
-}
-
 /// Saved block group identifier entity component.
 #[derive(Clone, Copy, Parsable)]
 pub struct SavedBlockGroupIdComponent {
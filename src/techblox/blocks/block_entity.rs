@@ -8,7 +8,8 @@ BlockGroupEntityComponent};
 use libfj_parsable_macro_derive::*;
 
 /// Block entity descriptor.
-#[derive(Copy, Clone, Parsable)]
+#[derive(Copy, Clone, Parsable, EntityDescriptor)]
+#[descriptor(name = "StandardBlockEntityDescriptorV4", block)] // 1357220432
 pub struct BlockEntity {
     /// Database component
     pub db_component: DBEntityStruct,
@@ -38,46 +39,6 @@ pub struct BlockEntity {
     pub group_component: BlockGroupEntityComponent,
 }
 
-impl SerializedEntityDescriptor for BlockEntity {
-    fn serialized_components() -> u8 {
-        12
-    }
-
-    fn components<'a>(&'a self) -> Vec<&'a dyn SerializedEntityComponent> {
-        vec![&self.db_component,
-        &self.pos_component,
-        &self.scale_component,
-        &self.rot_component,
-        &self.skew_component,
-        &self.grid_component,
-        &self.grid_conn_component,
-        &self.placement_component,
-        &self.material_component,
-        &self.uscale_component,
-        &self.colour_component,
-        &self.group_component]
-    }
-
-    fn components_mut<'a>(&'a mut self) -> Vec<&'a mut dyn SerializedEntityComponent> {
-        vec![&mut self.db_component,
-        &mut self.pos_component,
-        &mut self.scale_component,
-        &mut self.rot_component,
-        &mut self.skew_component,
-        &mut self.grid_component,
-        &mut self.grid_conn_component,
-        &mut self.placement_component,
-        &mut self.material_component,
-        &mut self.uscale_component,
-        &mut self.colour_component,
-        &mut self.group_component]
-    }
-
-    fn hash_name(&self) -> u32 {
-        Self::hash("StandardBlockEntityDescriptorV4") // 1357220432
-    }
-}
-
 impl AsRef<BlockEntity> for BlockEntity {
     fn as_ref(&self) -> &Self {
         self
@@ -1,11 +1,10 @@
-use std::convert::AsRef;
-
 use crate::techblox::{SerializedEntityDescriptor, Parsable, SerializedEntityComponent,
 blocks::{BlockEntity, Block}};
 use libfj_parsable_macro_derive::*;
 
 /// Engine entity descriptor
-#[derive(Copy, Clone, Parsable)]
+#[derive(Copy, Clone, Parsable, EntityDescriptor)]
+#[descriptor(name = "EngineBlockEntityDescriptor", parent = block)] // 1757314505
 pub struct EngineBlockEntity {
     /// parent block entity
     pub block: BlockEntity,
@@ -13,36 +12,6 @@ pub struct EngineBlockEntity {
     pub tweak_component: EngineBlockTweakableComponent,
 }
 
-impl SerializedEntityDescriptor for EngineBlockEntity {
-    fn serialized_components() -> u8 {
-        BlockEntity::serialized_components() + 1
-    }
-
-    fn components<'a>(&'a self) -> Vec<&'a dyn SerializedEntityComponent> {
-        let mut c = self.block.components();
-        c.push(&self.tweak_component);
-        return c;
-    }
-
-    fn components_mut<'a>(&'a mut self) -> Vec<&'a mut dyn SerializedEntityComponent> {
-        let mut c = self.block.components_mut();
-        c.push(&mut self.tweak_component);
-        return c;
-    }
-
-    fn hash_name(&self) -> u32 {
-        Self::hash("EngineBlockEntityDescriptor") // 1757314505
-    }
-}
-
-impl AsRef<BlockEntity> for EngineBlockEntity {
-    fn as_ref(&self) -> &BlockEntity {
-        &self.block
-    }
-}
-
-impl Block for EngineBlockEntity {}
-
 /// Engine settings entity component.
 #[derive(Copy, Clone, Parsable)]
 pub struct EngineBlockTweakableComponent  {
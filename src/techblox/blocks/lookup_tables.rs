@@ -1,6 +1,8 @@
+use std::collections::HashMap;
 use std::io::Read;
+use std::sync::OnceLock;
+use chrono::naive::NaiveDate;
 
-use crate::techblox::{Parsable};
 use crate::techblox::blocks::*;
 
 const HASHNAMES: &[&str] = &[
@@ -70,32 +72,60 @@ const HASHNAMES: &[&str] = &[
     "CharacterCameraEntityDescriptorV1",
 ];
 
+/// Parse a block entity descriptor type, for use as a `BlockParser`.
+pub fn parse_block<B: Block + 'static>(data: &mut dyn Read, version: &NaiveDate) -> std::io::Result<Box<dyn Block>> {
+    Ok(Box::new(B::parse_versioned(data, version)?))
+}
+
+/// Block entity descriptor which can be parsed by `lookup_hashname(...)`.
+///
+/// These are registered by `#[derive(EntityDescriptor)]`.
+pub struct BuiltinDescriptor {
+    /// Entity descriptor name
+    pub name: &'static str,
+    /// Hash of the entity descriptor name
+    pub hash: u32,
+    /// Block entity parser
+    pub parser: BlockParser,
+}
+
+inventory::collect!(BuiltinDescriptor);
+
+fn builtin_parsers() -> &'static HashMap<u32, BlockParser> {
+    static PARSERS: OnceLock<HashMap<u32, BlockParser>> = OnceLock::new();
+    PARSERS.get_or_init(|| inventory::iter::<BuiltinDescriptor>.into_iter()
+        .map(|descriptor| (descriptor.hash, descriptor.parser))
+        .collect())
+}
+
+fn known_names() -> &'static HashMap<u32, &'static str> {
+    static NAMES: OnceLock<HashMap<u32, &'static str>> = OnceLock::new();
+    NAMES.get_or_init(|| HASHNAMES.iter()
+        .map(|name| (crate::techblox::hashname(name), *name))
+        .chain(inventory::iter::<BuiltinDescriptor>.into_iter().map(|descriptor| (descriptor.hash, descriptor.name)))
+        .collect())
+}
+
+/// Parse the built-in block entity with the descriptor name hash, for the newest game version.
+///
+/// Use `lookup_hashname_versioned(...)` for older game saves.
 pub fn lookup_hashname(hash: u32, data: &mut dyn Read) ->
     std::io::Result<Box<dyn Block>> {
-    Ok(match hash {
-        1357220432 /*StandardBlockEntityDescriptorV4*/ => Box::new(BlockEntity::parse(data)?),
-        2281299333 /*PilotSeatEntityDescriptorV4*/ => Box::new(PilotSeatEntity::parse(data)?),
-        1360086092 /*PassengerSeatEntityDescriptorV4*/ => Box::new(PassengerSeatEntity::parse(data)?),
-        1757314505 /*EngineBlockEntityDescriptor*/ => Box::new(EngineBlockEntity::parse(data)?),
-        3586818581 /*JointBlockEntityDescriptorV3*/ => Box::new(JointBlockEntity::parse(data)?),
-        3789998433 /*DampedAngularSpringEntityDescriptorV4*/ => Box::new(DampedAngularSpringEntity::parse(data)?),
-        2892049599 /*DampedSpringEntityDescriptorV5*/ => Box::new(DampedSpringEntity::parse(data)?),
-        1156723746 /*WheelRigEntityDescriptor*/ => Box::new(WheelRigEntity::parse(data)?),
-        1864425618 /*WheelRigSteerableEntityDescriptor*/ => Box::new(WheelRigSteerableEntity::parse(data)?),
-        1517625162 /*TyreEntityDescriptorV1*/ => Box::new(TyreEntity::parse(data)?),
-        _ => {
-            #[cfg(debug_assertions)]
-            println!("Unknown hash ID {} (missing entry for {})", hash, lookup_name_by_hash(hash).unwrap_or("<Unknown>"));
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Unrecognised hash {}", hash)))
-        }
-    })
+    lookup_hashname_versioned(hash, data, &NaiveDate::MAX)
 }
 
-pub fn lookup_name_by_hash(hash: u32) -> Option<&'static str> {
-    for name in HASHNAMES {
-        if crate::techblox::hashname(name) == hash {
-            return Some(name);
-        }
+/// Parse the built-in block entity with the descriptor name hash, created by game version `version`.
+pub fn lookup_hashname_versioned(hash: u32, data: &mut dyn Read, version: &NaiveDate) ->
+    std::io::Result<Box<dyn Block>> {
+    if let Some(parser) = builtin_parsers().get(&hash) {
+        return parser(data, version);
     }
-    None
+    #[cfg(debug_assertions)]
+    println!("Unknown hash ID {} (missing entry for {})", hash, lookup_name_by_hash(hash).unwrap_or("<Unknown>"));
+    Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Unrecognised hash {}", hash)))
+}
+
+/// Find the descriptor name of a hash, for known entity descriptors.
+pub fn lookup_name_by_hash(hash: u32) -> Option<&'static str> {
+    known_names().get(&hash).copied()
 }
@@ -7,6 +7,7 @@ mod joint;
 mod lookup_tables;
 mod pilot_seat;
 mod passenger_seat;
+mod registry;
 mod spring;
 mod tyre;
 mod wheel_rig;
@@ -21,7 +22,12 @@ pub use engine::{EngineBlockEntity, EngineBlockTweakableComponent};
 pub use joint::{JointBlockEntity};
 pub use pilot_seat::{PilotSeatEntity, SeatFollowCamComponent};
 pub use passenger_seat::PassengerSeatEntity;
-pub(crate) use lookup_tables::*;
+pub use lookup_tables::{lookup_hashname, lookup_hashname_versioned, lookup_name_by_hash};
+#[doc(hidden)]
+pub use lookup_tables::{parse_block, BuiltinDescriptor};
+#[doc(hidden)]
+pub use inventory;
+pub use registry::{DescriptorRegistry, BlockParser};
 pub use spring::{DampedAngularSpringEntity, TweakableJointDampingComponent, DampedAngularSpringROStruct,
 DampedSpringEntity, DampedSpringROStruct};
 pub use tyre::{TyreEntity};
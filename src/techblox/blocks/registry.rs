@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::io::Read;
+use chrono::naive::NaiveDate;
+
+use crate::techblox::{hashname, blocks::{Block, lookup_hashname_versioned, parse_block}};
+
+/// Block entity descriptor parser.
+///
+/// This processes a block entity from raw data created by the provided game version.
+pub type BlockParser = fn(&mut dyn Read, &NaiveDate) -> std::io::Result<Box<dyn Block>>;
+
+/// Collection of custom block entity descriptors, for parsing game saves with modded blocks.
+///
+/// Descriptors registered here take priority over the built-in libfj blocks.
+/// Use `GameSave::parse_with_registry(...)` to parse a game save with custom descriptors.
+#[derive(Clone, Default)]
+pub struct DescriptorRegistry {
+    parsers: HashMap<u32, BlockParser>,
+}
+
+impl DescriptorRegistry {
+    /// Create a new registry without any custom descriptors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a block entity descriptor type by descriptor name.
+    ///
+    /// For types using `#[derive(EntityDescriptor)]`, use `registry.register::<T>(T::HASHNAME)`.
+    /// Returns the parser previously registered for the same name, if any.
+    pub fn register<B: Block + 'static>(&mut self, name: &str) -> Option<BlockParser> {
+        self.register_name(name, parse_block::<B>)
+    }
+
+    /// Register a parser by descriptor name.
+    ///
+    /// Returns the parser previously registered for the same name, if any.
+    pub fn register_name(&mut self, name: &str, parser: BlockParser) -> Option<BlockParser> {
+        self.register_hash(hashname(name), parser)
+    }
+
+    /// Register a parser by descriptor name hash, for descriptors with an unknown name.
+    ///
+    /// Returns the parser previously registered for the same hash, if any.
+    pub fn register_hash(&mut self, hash: u32, parser: BlockParser) -> Option<BlockParser> {
+        self.parsers.insert(hash, parser)
+    }
+
+    /// Remove the parser registered for the descriptor name hash.
+    pub fn unregister_hash(&mut self, hash: u32) -> Option<BlockParser> {
+        self.parsers.remove(&hash)
+    }
+
+    /// Retrieve the custom parser registered for the descriptor name hash.
+    ///
+    /// This does not include built-in libfj blocks.
+    pub fn lookup(&self, hash: u32) -> Option<BlockParser> {
+        self.parsers.get(&hash).copied()
+    }
+
+    /// Is a custom parser registered for the descriptor name hash?
+    pub fn contains(&self, hash: u32) -> bool {
+        self.parsers.contains_key(&hash)
+    }
+
+    /// Amount of registered custom descriptors.
+    pub fn len(&self) -> usize {
+        self.parsers.len()
+    }
+
+    /// Are there no registered custom descriptors?
+    pub fn is_empty(&self) -> bool {
+        self.parsers.is_empty()
+    }
+
+    /// Parse the block entity with the descriptor name hash.
+    ///
+    /// Custom descriptors are tried first, then built-in libfj blocks.
+    pub fn parse(&self, hash: u32, data: &mut dyn Read, version: &NaiveDate) -> std::io::Result<Box<dyn Block>> {
+        match self.lookup(hash) {
+            Some(parser) => parser(data, version),
+            None => lookup_hashname_versioned(hash, data, version),
+        }
+    }
+}
@@ -1,11 +1,10 @@
-use std::convert::AsRef;
-
 use crate::techblox::{SerializedEntityDescriptor, Parsable, SerializedEntityComponent,
 blocks::{BlockEntity, Block}};
 use libfj_parsable_macro_derive::*;
 
 /// Damped angular spring entity descriptor
-#[derive(Copy, Clone, Parsable)]
+#[derive(Copy, Clone, Parsable, EntityDescriptor)]
+#[descriptor(name = "DampedAngularSpringEntityDescriptorV4", parent = block)] // 3789998433
 pub struct DampedAngularSpringEntity {
     /// parent block entity
     pub block: BlockEntity,
@@ -15,40 +14,9 @@ pub struct DampedAngularSpringEntity {
     pub spring_component: DampedAngularSpringROStruct,
 }
 
-impl SerializedEntityDescriptor for DampedAngularSpringEntity {
-    fn serialized_components() -> u8 {
-        BlockEntity::serialized_components() + 2
-    }
-
-    fn components<'a>(&'a self) -> Vec<&'a dyn SerializedEntityComponent> {
-        let mut c = self.block.components();
-        c.push(&self.tweak_component);
-        c.push(&self.spring_component);
-        return c;
-    }
-
-    fn components_mut<'a>(&'a mut self) -> Vec<&'a mut dyn SerializedEntityComponent> {
-        let mut c = self.block.components_mut();
-        c.push(&mut self.tweak_component);
-        c.push(&mut self.spring_component);
-        return c;
-    }
-
-    fn hash_name(&self) -> u32 {
-        Self::hash("DampedAngularSpringEntityDescriptorV4") // 3789998433
-    }
-}
-
-impl AsRef<BlockEntity> for DampedAngularSpringEntity {
-    fn as_ref(&self) -> &BlockEntity {
-        &self.block
-    }
-}
-
-impl Block for DampedAngularSpringEntity {}
-
 /// Damped spring entity descriptor
-#[derive(Copy, Clone, Parsable)]
+#[derive(Copy, Clone, Parsable, EntityDescriptor)]
+#[descriptor(name = "DampedSpringEntityDescriptorV5", parent = block)] // 2892049599
 pub struct DampedSpringEntity {
     /// parent block entity
     pub block: BlockEntity,
@@ -58,38 +26,6 @@ pub struct DampedSpringEntity {
     pub spring_component: DampedSpringROStruct,
 }
 
-impl SerializedEntityDescriptor for DampedSpringEntity {
-    fn serialized_components() -> u8 {
-        BlockEntity::serialized_components() + 2
-    }
-
-    fn components<'a>(&'a self) -> Vec<&'a dyn SerializedEntityComponent> {
-        let mut c = self.block.components();
-        c.push(&self.tweak_component);
-        c.push(&self.spring_component);
-        return c;
-    }
-
-    fn components_mut<'a>(&'a mut self) -> Vec<&'a mut dyn SerializedEntityComponent> {
-        let mut c = self.block.components_mut();
-        c.push(&mut self.tweak_component);
-        c.push(&mut self.spring_component);
-        return c;
-    }
-
-    fn hash_name(&self) -> u32 {
-        Self::hash("DampedSpringEntityDescriptorV5") // 2892049599
-    }
-}
-
-impl AsRef<BlockEntity> for DampedSpringEntity {
-    fn as_ref(&self) -> &BlockEntity {
-        &self.block
-    }
-}
-
-impl Block for DampedSpringEntity {}
-
 /// Joint settings entity component.
 #[derive(Copy, Clone, Parsable)]
 pub struct TweakableJointDampingComponent  {
@@ -1,4 +1,4 @@
-use crate::techblox::{hashname, brute_force, Parsable, blocks::lookup_name_by_hash};
+use crate::techblox::{hashname, brute_force, HashReverser, Parsable, blocks::lookup_name_by_hash};
 use libfj_parsable_macro_derive::*;
 
 /// An entity's header information.
@@ -20,10 +20,18 @@ impl EntityHeader {
     /// Guess the original name from the hashed value by brute-force.
     ///
     /// This is slow and cannot guarantee a correct result. Use is discouraged.
-    pub fn guess_name(&self) -> String {
+    /// If no name is found within 30 seconds, None is returned instead.
+    pub fn guess_name(&self) -> Option<String> {
         brute_force(self.hash)
     }
 
+    /// Guess possible original names from the hashed value, using a custom hash reverser.
+    ///
+    /// Multiple names may be found, since the hash function has collisions.
+    pub fn guess_names(&self, reverser: &HashReverser) -> Vec<String> {
+        reverser.reverse_all(self.hash)
+    }
+
     /// Lookup the name from the header's hash from a list of known entity names.
     ///
     /// This is much faster than guess_name() and is guaranteed to return a correct result if one exists.
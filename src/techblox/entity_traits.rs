@@ -1,5 +1,6 @@
 use std::io::{Read, Write};
 use std::any::Any;
+use chrono::naive::NaiveDate;
 
 /// Standard trait for parsing Techblox game save data.
 pub trait Parsable {
@@ -7,6 +8,18 @@ pub trait Parsable {
     fn parse(reader: &mut dyn Read) -> std::io::Result<Self> where Self: Sized;
     /// Convert struct data back into raw bytes
     fn dump(&self, writer: &mut dyn Write) -> std::io::Result<usize>;
+    /// Process information from raw data created by game version `version`.
+    ///
+    /// By default, this ignores the version and is the same as `parse(...)`.
+    fn parse_versioned(reader: &mut dyn Read, _version: &NaiveDate) -> std::io::Result<Self> where Self: Sized {
+        Self::parse(reader)
+    }
+    /// Convert struct data back into raw bytes for game version `version`.
+    ///
+    /// By default, this ignores the version and is the same as `dump(...)`.
+    fn dump_versioned(&self, writer: &mut dyn Write, _version: &NaiveDate) -> std::io::Result<usize> {
+        self.dump(writer)
+    }
 }
 
 /// Entity descriptor containing serialized components.
@@ -3,7 +3,7 @@ use std::io::{Read, Write};
 
 use crate::techblox::{EntityHeader, BlockGroupEntity, parse_i64, parse_u32, Parsable,
 SerializedFlyCamEntity, SerializedPhysicsCameraEntity};
-use crate::techblox::blocks::{lookup_hashname, SerializedWireEntity, SerializedGlobalWireSettingsEntity, Block};
+use crate::techblox::blocks::{DescriptorRegistry, SerializedWireEntity, SerializedGlobalWireSettingsEntity, Block};
 
 /// A collection of cubes and other data from a GameSave.techblox file
 //#[derive(Clone)]
@@ -67,9 +67,12 @@ pub struct GameSave {
     pub phycam_entity: SerializedPhysicsCameraEntity,
 }
 
-impl Parsable for GameSave {
-    /// Process a Techblox save file from raw bytes.
-    fn parse(data: &mut dyn Read) -> std::io::Result<Self> {
+impl GameSave {
+    /// Process a Techblox save file from raw bytes, using custom block entity descriptors.
+    ///
+    /// Blocks are parsed using descriptors in `registry` before falling back to built-in libfj blocks.
+    /// This allows for parsing game saves which contain modded blocks.
+    pub fn parse_with_registry(data: &mut dyn Read, registry: &DescriptorRegistry) -> std::io::Result<Self> {
         // parse version
         let year = parse_u32(data)?; // parsed as i32 in-game for some reason
         let month = parse_u32(data)?;
@@ -83,19 +86,19 @@ impl Parsable for GameSave {
         let mut groups_h = Vec::<EntityHeader>::with_capacity(group_count as usize);
         let mut groups_e = Vec::<BlockGroupEntity>::with_capacity(group_count as usize);
         for _i in 0..group_count {
-            groups_h.push(EntityHeader::parse(data)?);
-            groups_e.push(BlockGroupEntity::parse(data)?);
+            groups_h.push(EntityHeader::parse_versioned(data, &date)?);
+            groups_e.push(BlockGroupEntity::parse_versioned(data, &date)?);
         }
 
         // parse cube data
         let mut cubes_h = Vec::<EntityHeader>::with_capacity(cube_count as usize);
         let mut cubes_e = Vec::<Box<dyn Block>>::with_capacity(cube_count as usize);
         for _i in 0..cube_count {
-            let header = EntityHeader::parse(data)?;
+            let header = EntityHeader::parse_versioned(data, &date)?;
             let hash = header.hash;
             //println!("Handling block {} (hash: {} id:{}/{} components: {})", cubes_h.len(), hash, header.entity_id, header.group_id, header.component_count);
             cubes_h.push(header);
-            cubes_e.push(lookup_hashname(hash, data)?);
+            cubes_e.push(registry.parse(hash, data, &date)?);
         }
 
         // parse wire data
@@ -103,20 +106,20 @@ impl Parsable for GameSave {
         let mut wires_h = Vec::<EntityHeader>::with_capacity(wire_count as usize);
         let mut wires_e = Vec::<SerializedWireEntity>::with_capacity(wire_count as usize);
         for _i in 0..wire_count {
-            wires_h.push(EntityHeader::parse(data)?);
-            wires_e.push(SerializedWireEntity::parse(data)?);
+            wires_h.push(EntityHeader::parse_versioned(data, &date)?);
+            wires_e.push(SerializedWireEntity::parse_versioned(data, &date)?);
         }
 
         // parse global wire settings
-        let wire_settings_h = EntityHeader::parse(data)?;
-        let wire_settings_e = SerializedGlobalWireSettingsEntity::parse(data)?;
+        let wire_settings_h = EntityHeader::parse_versioned(data, &date)?;
+        let wire_settings_e = SerializedGlobalWireSettingsEntity::parse_versioned(data, &date)?;
 
         // parse player cameras
-        let flycam_h = EntityHeader::parse(data)?;
-        let flycam_e = SerializedFlyCamEntity::parse(data)?;
+        let flycam_h = EntityHeader::parse_versioned(data, &date)?;
+        let flycam_e = SerializedFlyCamEntity::parse_versioned(data, &date)?;
 
-        let phycam_h = EntityHeader::parse(data)?;
-        let phycam_e = SerializedPhysicsCameraEntity::parse(data)?;
+        let phycam_h = EntityHeader::parse_versioned(data, &date)?;
+        let phycam_e = SerializedPhysicsCameraEntity::parse_versioned(data, &date)?;
 
         // build struct
         Ok(Self {
@@ -140,6 +143,13 @@ impl Parsable for GameSave {
             phycam_entity: phycam_e,
         })
     }
+}
+
+impl Parsable for GameSave {
+    /// Process a Techblox save file from raw bytes.
+    fn parse(data: &mut dyn Read) -> std::io::Result<Self> {
+        Self::parse_with_registry(data, &DescriptorRegistry::new())
+    }
 
     fn dump(&self, writer: &mut dyn Write) -> std::io::Result<usize> {
         let mut write_count: usize = 0;
@@ -156,33 +166,33 @@ impl Parsable for GameSave {
 
         // dump block groups
         for i in 0..self.group_len as usize {
-            write_count += self.group_headers[i].dump(writer)?;
-            write_count += self.cube_groups[i].dump(writer)?;
+            write_count += self.group_headers[i].dump_versioned(writer, &self.version)?;
+            write_count += self.cube_groups[i].dump_versioned(writer, &self.version)?;
         }
 
         // dump cube data
         for i in 0..self.cube_len as usize {
-            write_count += self.cube_headers[i].dump(writer)?;
-            write_count += self.cube_entities[i].dump(writer)?;
+            write_count += self.cube_headers[i].dump_versioned(writer, &self.version)?;
+            write_count += self.cube_entities[i].dump_versioned(writer, &self.version)?;
         }
 
         // dump wire data
         write_count += self.wire_len.dump(writer)?;
         for i in 0..self.wire_len as usize {
-            write_count += self.wire_headers[i].dump(writer)?;
-            write_count += self.wire_entities[i].dump(writer)?;
+            write_count += self.wire_headers[i].dump_versioned(writer, &self.version)?;
+            write_count += self.wire_entities[i].dump_versioned(writer, &self.version)?;
         }
 
         // dump global wire settings
-        write_count += self.wire_settings_header.dump(writer)?;
-        write_count += self.wire_settings_entity.dump(writer)?;
+        write_count += self.wire_settings_header.dump_versioned(writer, &self.version)?;
+        write_count += self.wire_settings_entity.dump_versioned(writer, &self.version)?;
 
         // dump player cameras
-        write_count += self.flycam_header.dump(writer)?;
-        write_count += self.flycam_entity.dump(writer)?;
+        write_count += self.flycam_header.dump_versioned(writer, &self.version)?;
+        write_count += self.flycam_entity.dump_versioned(writer, &self.version)?;
 
-        write_count += self.phycam_header.dump(writer)?;
-        write_count += self.phycam_entity.dump(writer)?;
+        write_count += self.phycam_header.dump_versioned(writer, &self.version)?;
+        write_count += self.phycam_entity.dump_versioned(writer, &self.version)?;
         Ok(write_count)
     }
 }
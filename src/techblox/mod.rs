@@ -20,3 +20,4 @@ pub use block_group_entity::{BlockGroupEntity, BlockGroupTransformEntityComponen
 pub use unity_types::{UnityFloat3, UnityHalf3, UnityFloat4, UnityQuaternion, UnityFloat4x4};
 pub(crate) use parsing_tools::*;
 pub(crate) use murmur::*;
+pub use murmur::{HashReverser, CancelToken};
@@ -1,74 +1,294 @@
 use highhash::murmur::hash32_with_seed;
-use std::sync::mpsc::{channel, Sender};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 const HASH_SEED: u32 = 4919;
 
-const ASCII_LETTERS: &[u8] = &[65,66,67,68,69,70,71,72,73,74,75,76,77,78,79,80,81,82,83,84,85,86,87,88,89,90, // A..Z
-97,98,99,100,101,102,103,104,105,106,107,108,109,110,111,112,113,114,115,116,117,118,119,120,121,122]; // a..z
+/// Words commonly found in entity descriptor names
+const DEFAULT_TOKENS: &[&str] = &[
+    "Block", "Entity", "Descriptor", "Group", "Standard", "Battery", "Motor", "Lever", "Button",
+    "Joint", "Servo", "Piston", "Damped", "Angular", "Spring", "Spawn", "Point", "Building",
+    "Trigger", "Pilot", "Seat", "Text", "Passenger", "Logic", "Tyre", "Wheel", "Rig", "Steerable",
+    "Object", "ID", "Mover", "Rotator", "Damper", "Advanced", "CoM", "Filter", "Constrainer",
+    "Number", "To", "Centre", "Hud", "Objective", "Game", "Stats", "Over", "Timer", "Bit",
+    "Constant", "Counter", "Simple", "Looped", "Sfx", "Music", "Projectile", "Damage", "Score",
+    "Team", "Engine", "Wire", "Global", "Settings", "Fly", "Cam", "Camera", "Character", "Physics",
+    "Mock",
+];
 
-const ASCII_NUMBERS: &[u8] = &[48, 49, 50, 51, 52, 53, 54, 55, 56, 57]; // 0..9
+/// Endings commonly found in entity descriptor names, without the version
+const DEFAULT_ENDINGS: &[&str] = &["", "Descriptor", "EntityDescriptor", "EntityDescriptorMock"];
 
-const HASHNAME_ENDING: &[u8] = &[69, 110, 116, 105, 116, 121, // Entity
-68, 101, 115, 99, 114, 105, 112, 116, 111, 114, // Descriptor
-86, 42]; // EntityDescriptorV0
+const DEFAULT_MAX_TOKENS: usize = 3;
 
-const MAX_LENGTH: usize = 10;
+const DEFAULT_MAX_VERSION: u8 = 9;
 
 pub fn hashname(name: &str) -> u32 {
     hash32_with_seed(name, HASH_SEED)
 }
 
-pub fn brute_force(hash: u32) -> String {
-    let (tx, rx) = channel::<String>();
-    let mut start = Vec::<u8>::new();
-    thread::spawn(move || brute_force_letter(hash, &mut start, &tx, 1));
-    //println!("All brute force possibilities explored");
-    if let Ok(res) = rx.recv_timeout(std::time::Duration::from_secs(30)) {
-        return res;
-    } else {
-        return "".to_string();
+/// Guess the name of `hash` using the default dictionary, giving up after 30 seconds.
+pub fn brute_force(hash: u32) -> Option<String> {
+    HashReverser::new()
+        .timeout(Duration::from_secs(30))
+        .reverse(hash)
+}
+
+/// Cancellation flag for a running hash reversal.
+///
+/// Clones share the same flag, so one can be given to another thread to stop the search early.
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a new, uncancelled token.
+    pub fn new() -> Self {
+        Self::default()
     }
+
+    /// Stop all searches using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Has cancel() been called?
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Dictionary-based entity descriptor name hash reverser.
+///
+/// Candidate names are built from up to `max_tokens` dictionary words, followed by an ending
+/// (e.g. `EntityDescriptor`) and an optional version (`V0`, `V1`, ...).
+/// This cannot guarantee a correct result, since different names can share the same hash.
+#[derive(Clone, Debug)]
+pub struct HashReverser {
+    tokens: Vec<String>,
+    endings: Vec<String>,
+    max_tokens: usize,
+    max_version: u8,
+    threads: usize,
+    max_results: usize,
+    timeout: Option<Duration>,
+    cancel: CancelToken,
 }
 
-fn brute_force_letter(hash: u32, start: &mut Vec<u8>, tx: &Sender<String>, threadity: usize) {
-    if start.len() > 0 {
-        brute_force_endings(hash, start, tx);
-    }
-    if start.len() >= MAX_LENGTH { // do not continue extending forever
-        //handles.pop().unwrap().join().unwrap();
-        return;
-    }
-    let mut handles = Vec::<thread::JoinHandle::<_>>::new();
-    start.push(65); // add letter
-    let last_elem = start.len()-1;
-    for letter in ASCII_LETTERS {
-        start[last_elem] = *letter;
-        if threadity > 0 {
-            //thread::sleep(std::time::Duration::from_millis(50));
-            let mut new_start = start.clone();
-            let new_tx = tx.clone();
-            handles.push(thread::spawn(move || brute_force_letter(hash, &mut new_start, &new_tx, threadity-1)));
-        } else {
-            brute_force_letter(hash, start, tx, threadity);
+impl HashReverser {
+    /// Create a new hash reverser with the default dictionary and configuration.
+    pub fn new() -> Self {
+        Self {
+            tokens: DEFAULT_TOKENS.iter().map(|t| t.to_string()).collect(),
+            endings: DEFAULT_ENDINGS.iter().map(|e| e.to_string()).collect(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            max_version: DEFAULT_MAX_VERSION,
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            max_results: 1,
+            timeout: None,
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// Add a word to the dictionary.
+    pub fn token(mut self, token: &str) -> Self {
+        if !self.tokens.iter().any(|t| t == token) {
+            self.tokens.push(token.to_string());
+        }
+        self
+    }
+
+    /// Add words to the dictionary.
+    pub fn tokens(mut self, tokens: &[&str]) -> Self {
+        for token in tokens {
+            self = self.token(token);
+        }
+        self
+    }
+
+    /// Replace the dictionary.
+    pub fn tokens_only(mut self, tokens: &[&str]) -> Self {
+        self.tokens.clear();
+        self.tokens(tokens)
+    }
+
+    /// Add a name ending, which is placed after the dictionary words and before the version.
+    pub fn ending(mut self, ending: &str) -> Self {
+        if !self.endings.iter().any(|e| e == ending) {
+            self.endings.push(ending.to_string());
+        }
+        self
+    }
+
+    /// Maximum amount of dictionary words in a name (default 3).
+    ///
+    /// Search time grows exponentially with this value.
+    pub fn max_tokens(mut self, max: usize) -> Self {
+        self.max_tokens = max;
+        self
+    }
+
+    /// Maximum version number to try (default 9).
+    pub fn max_version(mut self, max: u8) -> Self {
+        self.max_version = max;
+        self
+    }
+
+    /// Amount of worker threads (default is the available parallelism).
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Stop searching after this many matches are found (default 1).
+    pub fn max_results(mut self, max: usize) -> Self {
+        self.max_results = max.max(1);
+        self
+    }
+
+    /// Stop searching after this much time has elapsed (default never).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Use a cancellation token, to stop the search from another thread.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Find the first name which matches `hash`.
+    pub fn reverse(&self, hash: u32) -> Option<String> {
+        self.reverse_all(hash).into_iter().next()
+    }
+
+    /// Find all names which match `hash`, up to `max_results`.
+    ///
+    /// Names with fewer dictionary words are found first, and names with the same amount of words are sorted alphabetically.
+    /// Every name with the same amount of words is tried before stopping, so the results do not depend on the amount of threads.
+    pub fn reverse_all(&self, hash: u32) -> Vec<String> {
+        let suffixes = self.suffixes();
+        let deadline = self.timeout.map(|t| Instant::now() + t);
+        let mut found = Vec::new();
+        for depth in 1..=self.max_tokens {
+            let search = Search {
+                hash,
+                depth,
+                tokens: &self.tokens,
+                suffixes: &suffixes,
+                deadline,
+                cancel: &self.cancel,
+                next_job: AtomicUsize::new(0),
+                results: Mutex::new(Vec::new()),
+            };
+            // each job is a first word
+            let jobs = self.tokens.len();
+            thread::scope(|s| {
+                for _ in 0..self.threads.min(jobs.max(1)) {
+                    s.spawn(|| search.work(jobs));
+                }
+            });
+            let stopped = search.should_stop();
+            let mut results = search.results.into_inner().unwrap();
+            results.sort();
+            results.dedup();
+            for name in results {
+                if !found.contains(&name) {
+                    found.push(name);
+                }
+            }
+            if found.len() >= self.max_results || stopped {
+                break;
+            }
+        }
+        found.truncate(self.max_results);
+        found
+    }
+
+    fn suffixes(&self) -> Vec<Vec<u8>> {
+        let mut suffixes = Vec::new();
+        for ending in &self.endings {
+            suffixes.push(ending.as_bytes().to_vec());
+            for v in 0..=self.max_version {
+                suffixes.push(format!("{}V{}", ending, v).into_bytes());
+            }
         }
+        suffixes
     }
-    for handle in handles {
-        handle.join().unwrap()
+}
+
+impl Default for HashReverser {
+    fn default() -> Self {
+        Self::new()
     }
-    start.truncate(last_elem);
 }
 
-fn brute_force_endings(hash: u32, start: &mut Vec<u8>, tx: &Sender<String>) {
-    start.extend(HASHNAME_ENDING); // add ending
-    let last_elem = start.len()-1;
-    for num in ASCII_NUMBERS {
-        start[last_elem] = *num;
-        if hash32_with_seed(&start, HASH_SEED) == hash {
-            let result = String::from_utf8(start.clone()).unwrap();
-            println!("Found match `{}`", result);
-            tx.send(result).unwrap();
+struct Search<'a> {
+    hash: u32,
+    /// Amount of dictionary words in each name
+    depth: usize,
+    tokens: &'a [String],
+    suffixes: &'a [Vec<u8>],
+    deadline: Option<Instant>,
+    cancel: &'a CancelToken,
+    next_job: AtomicUsize,
+    results: Mutex<Vec<String>>,
+}
+
+impl<'a> Search<'a> {
+    fn should_stop(&self) -> bool {
+        self.cancel.is_cancelled()
+            || self.deadline.map(|d| Instant::now() >= d).unwrap_or(false)
+    }
+
+    /// Process jobs until there are none left; each job is a first word
+    fn work(&self, jobs: usize) {
+        let mut buf = Vec::new();
+        loop {
+            let first = self.next_job.fetch_add(1, Ordering::Relaxed);
+            if first >= jobs || self.should_stop() {
+                return;
+            }
+            buf.clear();
+            buf.extend_from_slice(self.tokens[first].as_bytes());
+            let mut indices = vec![0usize; self.depth - 1];
+            loop {
+                let prefix_len = buf.len();
+                for index in &indices {
+                    buf.extend_from_slice(self.tokens[*index].as_bytes());
+                }
+                self.try_suffixes(&mut buf);
+                buf.truncate(prefix_len);
+                if !Self::advance(&mut indices, self.tokens.len()) || self.should_stop() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn try_suffixes(&self, buf: &mut Vec<u8>) {
+        let base_len = buf.len();
+        for suffix in self.suffixes {
+            buf.extend_from_slice(suffix);
+            if hash32_with_seed(buf.as_slice(), HASH_SEED) == self.hash {
+                self.results.lock().unwrap().push(String::from_utf8_lossy(buf).into_owned());
+            }
+            buf.truncate(base_len);
+        }
+    }
+    /// Increment `indices` like an odometer, returning false once every combination is exhausted
+    fn advance(indices: &mut [usize], radix: usize) -> bool {
+        for index in indices.iter_mut().rev() {
+            *index += 1;
+            if *index < radix {
+                return true;
+            }
+            *index = 0;
         }
+        false
     }
-    start.truncate(start.len()-HASHNAME_ENDING.len()); // remove ending
 }
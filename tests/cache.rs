@@ -0,0 +1,98 @@
+#[cfg(feature = "robocraft")]
+use libfj::cache::{ResponseCache, CacheStats};
+#[cfg(feature = "robocraft")]
+use std::time::Duration;
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn cache_lru_eviction() -> Result<(), ()> {
+    let cache = ResponseCache::<String>::new(2, Duration::from_secs(60));
+    assert!(cache.is_empty());
+    cache.insert("a", "first".to_owned());
+    cache.insert("b", "second".to_owned());
+    assert_eq!(cache.get("a").as_deref(), Some("first"));
+    // "b" is now the least recently used
+    cache.insert("c", "third".to_owned());
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get("b"), None);
+    assert_eq!(cache.get("a").as_deref(), Some("first"));
+    assert_eq!(cache.get("c").as_deref(), Some("third"));
+    // replacing a response does not evict anything
+    cache.insert("c", "fourth".to_owned());
+    assert_eq!(cache.get("c").as_deref(), Some("fourth"));
+    assert_eq!(cache.stats(), CacheStats { memory_hits: 4, disk_hits: 0, misses: 1, evictions: 1 });
+    assert_eq!(cache.stats().hit_rate(), 0.8);
+    cache.remove("a");
+    assert_eq!(cache.get("a"), None);
+    cache.clear();
+    assert!(cache.is_empty());
+    cache.reset_stats();
+    assert_eq!(cache.stats(), CacheStats::default());
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn cache_ttl() -> Result<(), ()> {
+    let cache = ResponseCache::<u32>::new(8, Duration::ZERO);
+    cache.insert("1", 1);
+    assert_eq!(cache.get("1"), None);
+    assert!(cache.is_empty());
+    assert_eq!(cache.stats().misses, 1);
+    let cache = ResponseCache::<u32>::new(0, Duration::from_secs(60));
+    cache.insert("1", 1);
+    assert_eq!(cache.get("1"), None);
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn cache_disk() -> Result<(), ()> {
+    let dir = std::env::temp_dir().join(format!("libfj-cache-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = ResponseCache::<Vec<u32>>::new(8, Duration::from_secs(60)).with_directory(&dir);
+    cache.insert("abc-123", vec![1, 2, 3]);
+    cache.insert("../escape", vec![4]);
+    cache.insert("___escape", vec![5]);
+    // file names are the hex encoded keys, so similar keys do not share a file
+    assert!(dir.join("6162632d313233.json").exists());
+    assert!(dir.join("2e2e2f657363617065.json").exists());
+    assert!(dir.join("5f5f5f657363617065.json").exists());
+    std::fs::write(dir.join("unrelated.json"), "{\"fetched\":0,\"value\":[]}").map_err(|_| ())?;
+    // a new cache (like after a restart) reads the responses from disk
+    let reloaded = ResponseCache::<Vec<u32>>::new(8, Duration::from_secs(60)).with_directory(&dir);
+    assert_eq!(reloaded.get("abc-123"), Some(vec![1, 2, 3]));
+    assert_eq!(reloaded.get("abc-123"), Some(vec![1, 2, 3]));
+    assert_eq!(reloaded.stats(), CacheStats { memory_hits: 1, disk_hits: 1, misses: 0, evictions: 0 });
+    assert_eq!(reloaded.get("../escape"), Some(vec![4]));
+    assert_eq!(reloaded.get("___escape"), Some(vec![5]));
+    reloaded.remove("abc-123");
+    assert!(!dir.join("6162632d313233.json").exists());
+    // expired responses on disk are deleted when they are looked up, or all at once
+    let expired = ResponseCache::<Vec<u32>>::new(8, Duration::ZERO).with_directory(&dir);
+    assert_eq!(expired.get("../escape"), None);
+    assert!(!dir.join("2e2e2f657363617065.json").exists());
+    assert_eq!(expired.remove_expired(), 1);
+    assert!(!dir.join("5f5f5f657363617065.json").exists());
+    // files which were not written by the cache are kept
+    assert!(dir.join("unrelated.json").exists());
+    std::fs::remove_dir_all(&dir).map_err(|_| ())?;
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[tokio::test]
+async fn cache_disk_async() -> Result<(), ()> {
+    let dir = std::env::temp_dir().join(format!("libfj-cache-async-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = ResponseCache::<Vec<u32>>::new(8, Duration::from_secs(60)).with_directory(&dir);
+    cache.insert_async("robot", vec![1, 2]).await;
+    assert_eq!(cache.get_async("robot").await, Some(vec![1, 2]));
+    let reloaded = ResponseCache::<Vec<u32>>::new(8, Duration::from_secs(60)).with_directory(&dir);
+    assert_eq!(reloaded.get_async("robot").await, Some(vec![1, 2]));
+    assert_eq!(reloaded.get_async("missing").await, None);
+    assert_eq!(reloaded.stats(), CacheStats { memory_hits: 0, disk_hits: 1, misses: 1, evictions: 0 });
+    assert_eq!(reloaded.remove_expired(), 0);
+    std::fs::remove_dir_all(&dir).map_err(|_| ())?;
+    Ok(())
+}
@@ -1,5 +1,8 @@
 #[cfg(feature = "cardlife")]
 use libfj::cardlife;
+mod common;
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+use common::{serve, http_response};
 
 #[cfg(feature = "cardlife")]
 const EMAIL: &str = "";
@@ -51,3 +54,50 @@ async fn live_api_lobbies() -> Result<(), ()> {
     }*/
     Ok(())
 }
+
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+#[test]
+fn live_api_init_auth_simple() -> Result<(), ()> {
+    let live = libfj::cardlife_simple::LiveAPI::login_email(EMAIL, PASSWORD);
+    assert!(live.is_err()); // invalid credentials
+    Ok(())
+}
+
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+#[test]
+fn live_api_lobbies_simple() -> Result<(), ()> {
+    let live = libfj::cardlife_simple::LiveAPI::new();
+    let result = live.lobbies();
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+const AUTH_JSON: &str = r#"{"PublicId":"public-id","EmailAddress":"test@example.com","DisplayName":"Tester","Purchases":[],"Flags":[],"Confirmed":true,"Token":"token","SteamId":null,"ID":42}"#;
+
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+const LOBBY_JSON: &str = r#"{"Games":[{"Id":7,"WorldName":"Test World","MaxPlayers":10,"CurrentPlayers":3,"GameVersion":"1.0","Ping":20,"HasPlayed":false,"HasPassword":false,"IsPvp":true,"IsAntiCheatEnabled":true,"IsOfficial":false,"ModInfo":"","Region":"EU"}]}"#;
+
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+#[test]
+fn live_api_offline_simple() -> Result<(), ()> {
+    assert!(libfj::cardlife_simple::LiveAPI::with_domains("not a url", "http://localhost/").is_err());
+    let (auth_addr, auth_request) = serve(vec![http_response("200 OK", "", AUTH_JSON)]);
+    let (lobby_addr, lobby_request) = serve(vec![http_response("200 OK", "", LOBBY_JSON)]);
+    let mut live = libfj::cardlife_simple::LiveAPI::with_domains(&format!("{}/", auth_addr), &format!("{}/", lobby_addr)).map_err(|_| ())?;
+    let auth = live.authenticate_email("test@example.com", "hunter2").map_err(|_| ())?;
+    assert_eq!(auth.display_name, "Tester");
+    assert_eq!(auth.id, 42);
+    let request = auth_request.recv().map_err(|_| ())?;
+    assert!(request.starts_with("POST /api/auth/authenticate HTTP/1.1"));
+    assert!(request.contains(r#""EmailAddress":"test@example.com""#));
+    assert!(request.contains(r#""Password":"hunter2""#));
+    let lobbies = live.lobbies().map_err(|_| ())?;
+    assert_eq!(lobbies.games.len(), 1);
+    assert_eq!(lobbies.games[0].to_string(), "Test World (7):3/10");
+    let request = lobby_request.recv().map_err(|_| ())?;
+    assert!(request.starts_with("POST /api/client/games HTTP/1.1"));
+    // the lobby request identifies the logged in account
+    assert!(request.contains(r#""PublicId":"public-id""#));
+    Ok(())
+}
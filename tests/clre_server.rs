@@ -8,6 +8,14 @@ fn clre_server_init() -> Result<(), ()> {
     Ok(())
 }
 
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+#[test]
+fn clre_server_init_simple() -> Result<(), ()> {
+    assert!(libfj::cardlife_simple::CLreServer::new("http://localhost:5030/c/game.json").is_ok());
+    assert!(libfj::cardlife_simple::CLreServer::new("not a url").is_err());
+    Ok(())
+}
+
 /*
 #[cfg(feature = "cardlife")]
 #[tokio::test]
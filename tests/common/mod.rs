@@ -0,0 +1,84 @@
+// Helpers shared by several test crates; not every test crate uses every helper.
+#![allow(dead_code)]
+
+#[cfg(feature = "robocraft")]
+use libfj::robocraft;
+
+/// Factory robot info for `cubes`, as returned by the Robocraft factory
+#[cfg(feature = "robocraft")]
+pub fn test_robot_info(cubes: &robocraft::Cubes, cpu: usize, cube_amounts: &str) -> robocraft::FactoryRobotGetInfo {
+    use base64::Engine;
+    let (cube_data, colour_data) = cubes.dump();
+    serde_json::from_value(serde_json::json!({
+        "id": 42,
+        "name": "Test Bot",
+        "description": "A robot for testing",
+        "thumbnail": "",
+        "addedBy": "tester",
+        "addedByDisplayName": "Tester",
+        "addedDate": "2021-01-01T00:00:00",
+        "expiryDate": "2022-01-01T00:00:00",
+        "cpu": cpu,
+        "totalRobotRanking": 0,
+        "rentCount": 0,
+        "buyCount": 0,
+        "buyable": true,
+        "removedDate": null,
+        "banDate": null,
+        "featured": false,
+        "bannerMessage": null,
+        "combatRating": 1.5,
+        "cosmeticRating": 2.5,
+        "cubeData": base64::engine::general_purpose::STANDARD.encode(cube_data),
+        "colourData": base64::engine::general_purpose::STANDARD.encode(colour_data),
+        "cubeAmounts": cube_amounts,
+    })).unwrap()
+}
+
+/// Serve canned HTTP responses on localhost, one per connection.
+///
+/// Returns the server's base URL (without a trailing slash) and a receiver for the requests it received.
+pub fn serve(responses: Vec<String>) -> (String, std::sync::mpsc::Receiver<String>) {
+    use std::io::{Read, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // read the headers, then as much body as Content-Length says
+            while !request_complete(&request) {
+                let len = stream.read(&mut buf).unwrap_or(0);
+                if len == 0 {
+                    break;
+                }
+                request.extend(&buf[..len]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            let _ = sender.send(String::from_utf8_lossy(&request).into_owned());
+        }
+    });
+    (addr, receiver)
+}
+
+fn request_complete(request: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(request);
+    match text.find("\r\n\r\n") {
+        Some(end) => {
+            let content_length = text[..end].lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            request.len() >= end + 4 + content_length
+        },
+        None => false,
+    }
+}
+
+/// HTTP response with a JSON body
+pub fn http_response(status: &str, headers: &str, body: &str) -> String {
+    format!("HTTP/1.1 {}\r\nConnection: close\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\n\r\n{}", status, headers, body.len(), body)
+}
@@ -1,5 +1,8 @@
+mod common;
 #[cfg(all(feature = "robocraft", feature = "convert"))]
-use libfj::convert::cubes_to_model;
+use common::test_robot_info;
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+use libfj::convert::{cubes_to_model, cubes_to_model_with_library, cube_rotation_to_quat, cubes_to_model_optimised, CubeShape, ShapeLibrary, ModelOptions, colour_material_name, colour_to_rgb, try_colour_to_rgb, FALLBACK_COLOUR, PROVISIONAL_ROBOCRAFT_PALETTE, cubes_to_gltf, cubes_to_vox, vox_to_cubes, VoxImportOptions};
 #[cfg(all(feature = "robocraft", feature = "convert"))]
 use libfj::robocraft;
 
@@ -18,3 +21,463 @@ async fn convert_to_obj() -> Result<(), ()> {
     assert!(save_result.is_ok());
     Ok(())
 }
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+fn test_cubes(cubes: &[(u32, u8, u8, u8, u8)]) -> robocraft::Cubes {
+    test_cubes_coloured(&cubes.iter().map(|(id, x, y, z, o)| (*id, *x, *y, *z, *o, 0)).collect::<Vec<_>>())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+fn test_cubes_coloured(cubes: &[(u32, u8, u8, u8, u8, u8)]) -> robocraft::Cubes {
+    let mut cube_data = (cubes.len() as u32).to_le_bytes().to_vec();
+    let mut colour_data = cube_data.clone();
+    for (id, x, y, z, orientation, colour) in cubes {
+        cube_data.extend(&id.to_le_bytes());
+        cube_data.extend(&[*x, *y, *z, *orientation]);
+        colour_data.extend(&[*colour, *x, *y, *z]);
+    }
+    robocraft::Cubes::parse(&mut cube_data, &mut colour_data).unwrap()
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_rotations_unique() -> Result<(), ()> {
+    use cgmath::{Vector3, Rotation};
+    let mut seen = Vec::new();
+    for orientation in 0..24 {
+        let rotation = cube_rotation_to_quat(orientation);
+        let axes: Vec<[i32; 3]> = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()].iter()
+            .map(|axis| {
+                let v = rotation.rotate_vector(*axis);
+                [v.x.round() as i32, v.y.round() as i32, v.z.round() as i32]
+            }).collect();
+        for axis in &axes {
+            // every axis must map onto an axis
+            assert_eq!(axis.iter().map(|a| a.abs()).sum::<i32>(), 1);
+        }
+        assert!(!seen.contains(&axes), "orientation {} is a duplicate", orientation);
+        seen.push(axes);
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_rotations_match_original_table() -> Result<(), ()> {
+    use cgmath::{Vector3, Rotation, Quaternion, Euler, Deg};
+    // orientations which were not duplicates in libfj 0.7.5's table must not change
+    let original: [(u8, [f32; 3]); 17] = [
+        (0, [0.0, 0.0, 0.0]), (1, [0.0, 0.0, 90.0]), (2, [0.0, 0.0, 180.0]), (3, [0.0, 0.0, -90.0]),
+        (4, [0.0, 90.0, 0.0]), (5, [0.0, 90.0, 90.0]), (6, [-90.0, -90.0, 0.0]), (7, [0.0, 90.0, -90.0]),
+        (9, [0.0, -90.0, -90.0]), (13, [0.0, 90.0, 180.0]), (14, [0.0, 180.0, 0.0]), (15, [0.0, 180.0, 90.0]),
+        (17, [0.0, 180.0, 180.0]), (19, [0.0, -90.0, 0.0]), (20, [90.0, 0.0, 180.0]), (21, [90.0, 180.0, 0.0]),
+        (22, [-90.0, 0.0, 0.0]),
+    ];
+    let round = |v: Vector3<f32>| [v.x.round() as i32, v.y.round() as i32, v.z.round() as i32];
+    for (orientation, [x, y, z]) in original {
+        let expected: Quaternion<f32> = Euler { x: Deg(x), y: Deg(y), z: Deg(z) }.into();
+        let actual = cube_rotation_to_quat(orientation);
+        for v in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()] {
+            assert_eq!(round(actual.rotate_vector(v)), round(expected.rotate_vector(v)), "orientation {}", orientation);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_rotate_orientations() -> Result<(), ()> {
+    use cgmath::{Vector3, Rotation, Rotation3, Quaternion, Deg};
+    let round = |v: Vector3<f32>| [v.x.round() as i32, v.y.round() as i32, v.z.round() as i32];
+    let axes = [
+        (robocraft::CubeAxis::X, Vector3::unit_x()),
+        (robocraft::CubeAxis::Y, Vector3::unit_y()),
+        (robocraft::CubeAxis::Z, Vector3::unit_z()),
+    ];
+    for orientation in 0..24 {
+        for (axis, axis_vec) in axes.iter() {
+            let mut cubes = test_cubes(&[(1, 100, 100, 100, orientation)]);
+            cubes.rotate(*axis, 1).map_err(|_| ())?;
+            let rotated = cubes.get(0).unwrap().orientation;
+            let expected = Quaternion::from_axis_angle(*axis_vec, Deg(90.0)) * cube_rotation_to_quat(orientation);
+            let actual = cube_rotation_to_quat(rotated);
+            for v in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()] {
+                assert_eq!(round(actual.rotate_vector(v)), round(expected.rotate_vector(v)), "orientation {} around {:?}", orientation, axis);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_shapes_closed() -> Result<(), ()> {
+    use genmesh::{Polygon, Vertex};
+    let key = |v: &Vertex| [(v.pos.x * 1000.0).round() as i32, (v.pos.y * 1000.0).round() as i32, (v.pos.z * 1000.0).round() as i32];
+    for shape in [CubeShape::Cube, CubeShape::Prism, CubeShape::Tetra, CubeShape::Inner, CubeShape::Wheel, CubeShape::Proxy] {
+        let mut edges = Vec::new();
+        for poly in shape.polygons() {
+            let points: Vec<Vertex> = match poly {
+                Polygon::PolyTri(t) => vec![t.x, t.y, t.z],
+                Polygon::PolyQuad(q) => vec![q.x, q.y, q.z, q.w],
+            };
+            for i in 0..points.len() {
+                edges.push((key(&points[i]), key(&points[(i + 1) % points.len()])));
+            }
+        }
+        // consistently wound closed shapes use every edge once in each direction
+        for (a, b) in &edges {
+            assert_eq!(edges.iter().filter(|e| e == &&(*b, *a)).count(), 1, "{:?} is not closed", shape);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_to_obj_with_library() -> Result<(), ()> {
+    let cubes = test_cubes(&[(1, 0, 0, 0, 0), (2, 1, 0, 0, 5), (3, 2, 0, 0, 17)]);
+    let library = ShapeLibrary::from_pairs(&[(2, CubeShape::Prism), (3, CubeShape::Tetra)]);
+    assert_eq!(library.shape(1), CubeShape::Cube);
+    assert_eq!(library.shape(2), CubeShape::Prism);
+    let obj = cubes_to_model_with_library(cubes.clone(), &library);
+    assert_eq!(obj.data.objects.len(), 3);
+    assert_eq!(obj.data.objects[0].groups[0].polys.len(), 6);
+    assert_eq!(obj.data.objects[1].groups[0].polys.len(), 5);
+    assert_eq!(obj.data.objects[2].groups[0].polys.len(), 5);
+    assert_eq!(obj.data.position.len(), 6 * 4 + (3 * 4 + 2 * 3) + (4 + 4 * 3));
+    let obj = cubes_to_model(cubes);
+    assert_eq!(obj.data.position.len(), 3 * 6 * 4);
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_to_obj_optimised() -> Result<(), ()> {
+    let library = ShapeLibrary::new();
+    let slab = test_cubes(&[(1, 0, 0, 0, 0), (1, 1, 0, 0, 0), (1, 0, 0, 1, 0), (1, 1, 0, 1, 0)]);
+    let polys = |obj: &obj::Obj| obj.data.objects.iter().map(|o| o.groups.iter().map(|g| g.polys.len()).sum::<usize>()).sum::<usize>();
+    let no_opt = ModelOptions { cull_hidden_faces: false, merge_faces: false, dedupe_vertices: false, ..Default::default() };
+    let obj = cubes_to_model_optimised(&slab, &library, &no_opt);
+    assert_eq!(polys(&obj), 4 * 6);
+    assert_eq!(obj.data.position.len(), 4 * 6 * 4);
+    let obj = cubes_to_model_optimised(&slab, &library, &ModelOptions { cull_hidden_faces: true, ..no_opt });
+    assert_eq!(polys(&obj), 4 * 6 - 8);
+    let obj = cubes_to_model_optimised(&slab, &library, &ModelOptions::default());
+    assert_eq!(obj.data.objects.len(), 1);
+    assert_eq!(polys(&obj), 6);
+    assert_eq!(obj.data.position.len(), 8);
+    assert_eq!(obj.data.normal.len(), 6);
+    // faces of different colours are not merged
+    let striped = test_cubes_coloured(&[(1, 0, 0, 0, 0, 0), (1, 1, 0, 0, 0, 1), (1, 0, 0, 1, 0, 0), (1, 1, 0, 1, 0, 1)]);
+    let obj = cubes_to_model_optimised(&striped, &library, &ModelOptions::default());
+    assert_eq!(polys(&obj), 2 * 2 + 2 * 2 + 2 * 1); // y, z and x faces
+    // other shapes are kept as-is
+    let library = ShapeLibrary::from_pairs(&[(2, CubeShape::Prism)]);
+    let mixed = test_cubes(&[(1, 0, 0, 0, 0), (2, 1, 0, 0, 0)]);
+    let obj = cubes_to_model_optimised(&mixed, &library, &ModelOptions::default());
+    assert_eq!(polys(&obj), 6 + 5);
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_to_obj_materials() -> Result<(), ()> {
+    assert_eq!(PROVISIONAL_ROBOCRAFT_PALETTE.len(), 24);
+    assert_eq!(colour_to_rgb(200), colour_to_rgb(FALLBACK_COLOUR));
+    assert_eq!(try_colour_to_rgb(200), None);
+    assert_eq!(try_colour_to_rgb(4), Some(PROVISIONAL_ROBOCRAFT_PALETTE[4].1));
+    let cubes = test_cubes_coloured(&[(1, 0, 0, 0, 0, 4), (1, 1, 0, 0, 0, 14), (1, 2, 0, 0, 0, 4)]);
+    let obj = cubes_to_model(cubes.clone());
+    assert_eq!(obj.data.material_libs.len(), 1);
+    assert_eq!(obj.data.material_libs[0].materials.len(), 2);
+    for (object, colour) in obj.data.objects.iter().zip([4, 14, 4]) {
+        match &object.groups[0].material {
+            Some(obj::ObjMaterial::Mtl(material)) => assert_eq!(material.name, colour_material_name(colour)),
+            _ => panic!("Missing material for {}", object.name),
+        }
+    }
+    let obj = cubes_to_model_optimised(&cubes, &ShapeLibrary::new(), &ModelOptions { material_lib: "test.mtl".to_string(), ..Default::default() });
+    assert_eq!(obj.data.objects[0].groups.len(), 2);
+    let mut obj_buf = Vec::new();
+    obj.data.write_to_buf(&mut obj_buf).map_err(|_| ())?;
+    let obj_text = String::from_utf8(obj_buf).map_err(|_| ())?;
+    assert!(obj_text.contains("mtllib test.mtl"));
+    assert!(obj_text.contains(&format!("usemtl {}", colour_material_name(14))));
+    let mut mtl_buf = Vec::new();
+    obj.data.material_libs[0].write_to_buf(&mut mtl_buf).map_err(|_| ())?;
+    let mtl_text = String::from_utf8(mtl_buf).map_err(|_| ())?;
+    assert!(mtl_text.contains(&format!("newmtl {}", colour_material_name(4))));
+    assert!(!mtl_text.contains(&format!("newmtl {}", colour_material_name(0))));
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+fn assert_normals_and_winding(obj: &obj::Obj) {
+    let data = &obj.data;
+    for object in &data.objects {
+        for group in &object.groups {
+            for poly in &group.polys {
+                let p: Vec<[f32; 3]> = poly.0.iter().map(|i| data.position[i.0]).collect();
+                let u = [p[1][0] - p[0][0], p[1][1] - p[0][1], p[1][2] - p[0][2]];
+                let v = [p[2][0] - p[0][0], p[2][1] - p[0][1], p[2][2] - p[0][2]];
+                let face_normal = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+                for index in &poly.0 {
+                    let n = data.normal[index.2.expect("Missing normal index")];
+                    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                    assert!((len - 1.0).abs() < 1e-4, "Normal {:?} has length {}", n, len);
+                    // counter-clockwise winding means the face normal points the same way as the vertex normal
+                    let dot = n[0] * face_normal[0] + n[1] * face_normal[1] + n[2] * face_normal[2];
+                    assert!(dot > 0.0, "Face in {} is wound clockwise", object.name);
+                    assert!(index.1.expect("Missing texture index") < data.texture.len());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_normals_and_winding() -> Result<(), ()> {
+    let library = ShapeLibrary::from_pairs(&[
+        (2, CubeShape::Prism), (3, CubeShape::Tetra), (4, CubeShape::Inner), (5, CubeShape::Wheel), (6, CubeShape::Proxy),
+    ]);
+    let mut cubes = Vec::new();
+    for orientation in 0..24 {
+        for id in 1..7 {
+            cubes.push((id, orientation * 2, id as u8 * 2, 0, orientation));
+        }
+    }
+    let cubes = test_cubes(&cubes);
+    let obj = cubes_to_model_with_library(cubes.clone(), &library);
+    assert_normals_and_winding(&obj);
+    assert_eq!(obj.data.texture.len(), obj.data.position.len());
+    // full cube faces get the whole texture
+    let obj = cubes_to_model(test_cubes(&[(1, 3, 4, 5, 0)]));
+    for uv in &obj.data.texture {
+        assert!((uv[0].abs() == 0.0 || uv[0].abs() == 1.0) && (uv[1] == 0.0 || uv[1] == 1.0), "Unexpected UV {:?}", uv);
+    }
+    let obj = cubes_to_model_optimised(&cubes, &library, &ModelOptions::default());
+    assert_normals_and_winding(&obj);
+    let slab = test_cubes(&[(1, 0, 0, 0, 0), (1, 1, 0, 0, 0), (1, 0, 1, 0, 0), (1, 1, 1, 0, 0)]);
+    assert_normals_and_winding(&cubes_to_model_optimised(&slab, &library, &ModelOptions::default()));
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_to_gltf() -> Result<(), ()> {
+    let read_u32 = |bytes: &[u8], offset: usize| u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+    let library = ShapeLibrary::from_pairs(&[(2, CubeShape::Prism)]);
+    let cubes = test_cubes_coloured(&[(1, 0, 0, 0, 0, 4), (1, 1, 0, 0, 3, 4), (2, 2, 0, 0, 5, 14), (1, 3, 0, 0, 0, 14)]);
+    let info = test_robot_info(&cubes, 123, "{}");
+    let glb = cubes_to_gltf(&cubes, &library, Some(&info));
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(read_u32(&glb, 4), 2);
+    assert_eq!(read_u32(&glb, 8) as usize, glb.len());
+    let json_len = read_u32(&glb, 12) as usize;
+    assert_eq!(&glb[16..20], b"JSON");
+    let document: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).map_err(|_| ())?;
+    let bin_len = read_u32(&glb, 20 + json_len) as usize;
+    assert_eq!(&glb[24 + json_len..28 + json_len], b"BIN\0");
+    assert_eq!(28 + json_len + bin_len, glb.len());
+    assert!(document["buffers"][0]["byteLength"].as_u64().unwrap() as usize <= bin_len);
+    // 2 colours, 3 (id, colour) pairs, 4 cubes + 2 cube types + 1 root
+    assert_eq!(document["materials"].as_array().unwrap().len(), 2);
+    assert_eq!(document["meshes"].as_array().unwrap().len(), 3);
+    let nodes = document["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 4 + 2 + 1);
+    let root = &nodes[document["scenes"][0]["nodes"][0].as_u64().unwrap() as usize];
+    assert_eq!(root["name"], "Test Bot");
+    assert_eq!(root["extras"]["cpu"], 123);
+    assert_eq!(root["extras"]["creator"], "tester");
+    assert_eq!(root["extras"]["cube_count"], 4);
+    assert_eq!(root["children"].as_array().unwrap().len(), 2);
+    for view in document["bufferViews"].as_array().unwrap() {
+        let end = view["byteOffset"].as_u64().unwrap() + view["byteLength"].as_u64().unwrap();
+        assert!(end as usize <= bin_len);
+    }
+    let glb = cubes_to_gltf(&cubes, &library, None);
+    assert_eq!(&glb[0..4], b"glTF");
+    // empty robots must not produce empty arrays
+    let glb = cubes_to_gltf(&test_cubes(&[]), &library, None);
+    let json_len = read_u32(&glb, 12) as usize;
+    assert_eq!(20 + json_len, glb.len());
+    let document: serde_json::Value = serde_json::from_slice(&glb[20..]).map_err(|_| ())?;
+    for key in ["materials", "meshes", "accessors", "bufferViews", "buffers"] {
+        assert!(document.get(key).is_none(), "{} should be left out", key);
+    }
+    assert!(document["nodes"][0].get("children").is_none());
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_vox_round_trip() -> Result<(), ()> {
+    let cubes = test_cubes_coloured(&[(7, 0, 0, 0, 0, 4), (7, 1, 2, 3, 0, 14), (7, 5, 0, 9, 0, 23), (7, 2, 6, 1, 0, 0)]);
+    let vox = cubes_to_vox(&cubes);
+    assert_eq!(&vox[0..4], b"VOX ");
+    let options = VoxImportOptions { cube_id: 7, ..Default::default() };
+    let imported = vox_to_cubes(&vox, &options).map_err(|_| ())?;
+    assert_eq!(imported.len(), cubes.len());
+    assert_eq!(imported.provided_len, cubes.provided_len);
+    assert_eq!(imported.dump(), cubes.dump());
+    // files without a palette use MagicaVoxel's default palette, where 1 is white and 36 is red
+    let mut no_palette = vox.clone();
+    let rgba = no_palette.windows(4).position(|w| w == b"RGBA").unwrap();
+    no_palette.truncate(rgba);
+    let children_len = (no_palette.len() - 20) as u32;
+    no_palette[16..20].copy_from_slice(&children_len.to_le_bytes());
+    let xyzi = no_palette.windows(4).position(|w| w == b"XYZI").unwrap() + 16;
+    no_palette[xyzi + 3] = 1;
+    no_palette[xyzi + 7] = 36;
+    let imported = vox_to_cubes(&no_palette, &options).map_err(|_| ())?;
+    assert_eq!(imported.get(0).unwrap().colour, 0);
+    assert_eq!(imported.get(1).unwrap().colour, 4);
+    // the largest coordinate is still inside the model
+    let corner = test_cubes_coloured(&[(7, 255, 255, 255, 0, 0)]);
+    let vox = cubes_to_vox(&corner);
+    let size = vox.windows(4).position(|w| w == b"SIZE").unwrap() + 12;
+    for axis in 0..3 {
+        assert_eq!(&vox[size + axis * 4..size + axis * 4 + 4], &256i32.to_le_bytes());
+    }
+    assert_eq!(vox_to_cubes(&vox, &options).map_err(|_| ())?.dump(), corner.dump());
+    assert!(vox_to_cubes(b"not a vox file", &options).is_err());
+    assert!(vox_to_cubes(&vox[..vox.len() - 10], &options).is_err());
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert", feature = "techblox"))]
+#[test]
+fn convert_robocraft_to_techblox() -> Result<(), ()> {
+    use libfj::convert::{robocraft_to_techblox, robocraft_to_techblox_with_template, TechbloxConvertOptions, TECHBLOX_CUBE_DBID};
+    use libfj::techblox::{GameSave, Parsable, SerializedEntityDescriptor, blocks::BlockEntity};
+    use std::io::Read;
+    let cubes = test_cubes_coloured(&[(1, 0, 0, 0, 0, 4), (2, 1, 0, 0, 3, 14), (1, 5, 2, 1, 0, 0)]);
+    let mut options = TechbloxConvertOptions::default();
+    options.dbids.insert(2, 8);
+    options.colours[14] = 42;
+    let save = robocraft_to_techblox(&cubes, &options);
+    let mut buf = Vec::new();
+    save.dump(&mut buf).map_err(|_| ())?;
+    let parsed = GameSave::parse(&mut buf.as_slice()).map_err(|_| ())?;
+    assert_eq!(parsed.cube_len, 3);
+    assert_eq!(parsed.group_len, 3);
+    let blocks: Vec<&BlockEntity> = parsed.cube_entities.iter().map(|b| b.as_ref().as_ref()).collect();
+    assert_eq!(blocks[0].db_component.dbid, TECHBLOX_CUBE_DBID);
+    assert_eq!(blocks[1].db_component.dbid, 8);
+    // Robocraft red becomes a shade of Techblox red (paint 9)
+    assert_eq!(blocks[0].colour_component.index_in_palette, options.colours[4]);
+    assert_eq!(blocks[0].colour_component.index_in_palette % 10, 9);
+    assert_eq!(blocks[1].colour_component.index_in_palette, 42);
+    assert!((blocks[2].pos_component.position.x - 1.0).abs() < 1e-6);
+    assert!((blocks[2].pos_component.position.y - 0.4).abs() < 1e-6);
+    for (block, group) in blocks.iter().zip(parsed.cube_groups.iter()) {
+        assert_eq!(block.group_component.current_block_group, group.saved_block_group_id.saved_block_group_id);
+    }
+    for (header, block) in parsed.cube_headers.iter().zip(parsed.cube_entities.iter()) {
+        assert_eq!(header.hash, block.hash_name());
+        assert_eq!(header.component_count, BlockEntity::serialized_components());
+    }
+    // headers must match a real game save of the same version, which is built from standard cubes
+    let mut real_buf = Vec::new();
+    std::fs::File::open("tests/GameSave.Techblox").map_err(|_| ())?.read_to_end(&mut real_buf).map_err(|_| ())?;
+    let real = GameSave::parse(&mut real_buf.as_slice()).map_err(|_| ())?;
+    assert_eq!(real.version, save.version);
+    for header in &parsed.cube_headers {
+        let real_header = &real.cube_headers[0];
+        assert_eq!((header.hash, header.group_id, header.component_count), (real_header.hash, real_header.group_id, real_header.component_count));
+    }
+    for header in &parsed.group_headers {
+        let real_header = &real.group_headers[0];
+        assert_eq!((header.hash, header.group_id, header.component_count), (real_header.hash, real_header.group_id, real_header.component_count));
+    }
+    for (header, real_header) in [(&parsed.wire_settings_header, &real.wire_settings_header), (&parsed.flycam_header, &real.flycam_header), (&parsed.phycam_header, &real.phycam_header)] {
+        assert_eq!((header.hash, header.group_id, header.component_count), (real_header.hash, real_header.group_id, real_header.component_count));
+    }
+    assert!(real.cube_entities.iter().all(|block| (**block).as_ref().db_component.dbid == TECHBLOX_CUBE_DBID));
+    // nearest palette colours
+    let options = TechbloxConvertOptions::default().with_palette(&[[255, 255, 255], [10, 10, 10], [250, 0, 0], [0, 0, 250]]);
+    assert_eq!(options.colours.len(), PROVISIONAL_ROBOCRAFT_PALETTE.len());
+    assert_eq!(options.colours[0], 0); // white
+    assert_eq!(options.colours[3], 1); // black
+    assert_eq!(options.colours[4], 2); // red
+    assert_eq!(options.colours[14], 3); // blue
+    let options = TechbloxConvertOptions::default();
+    // templates keep the game version
+    let mut template_buf = Vec::new();
+    std::fs::File::open("tests/GameSave.Techblox").map_err(|_| ())?.read_to_end(&mut template_buf).map_err(|_| ())?;
+    let template = GameSave::parse(&mut template_buf.as_slice()).map_err(|_| ())?;
+    let save = robocraft_to_techblox_with_template(&cubes, &options, &template);
+    assert_eq!(save.version, template.version);
+    assert_eq!(save.cube_headers[0].group_id, template.cube_headers[0].group_id);
+    let mut buf = Vec::new();
+    save.dump(&mut buf).map_err(|_| ())?;
+    assert_eq!(GameSave::parse(&mut buf.as_slice()).map_err(|_| ())?.cube_len, 3);
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+fn decode_png(data: &[u8]) -> (png::OutputInfo, Vec<u8>) {
+    let mut reader = png::Decoder::new(data).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    (info, pixels)
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert"))]
+#[test]
+fn convert_to_png() -> Result<(), ()> {
+    use libfj::convert::{cubes_to_png, RenderOptions};
+    let cubes = test_cubes_coloured(&[(1, 0, 0, 0, 0, 4), (1, 1, 0, 0, 0, 4), (2, 0, 1, 0, 0, 14), (1, 0, 0, 1, 0, 7)]);
+    let library = ShapeLibrary::from_pairs(&[(2, CubeShape::Prism)]);
+    let options = RenderOptions { width: 64, height: 48, ..Default::default() };
+    let image = cubes_to_png(&cubes, &library, &options).map_err(|_| ())?;
+    let (info, pixels) = decode_png(&image);
+    assert_eq!((info.width, info.height), (64, 48));
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    // transparent background around an opaque robot
+    assert_eq!(pixels[3], 0);
+    let centre = (24 * 64 + 32) * 4;
+    assert_eq!(pixels[centre + 3], 255);
+    let red = PROVISIONAL_ROBOCRAFT_PALETTE[4].1;
+    assert!(pixels.chunks(4).any(|p| p[3] == 255 && p[0] > p[1] && p[0] > p[2] && p[0] <= red[0]));
+    // faces are shaded differently
+    let mut shades: Vec<&[u8]> = pixels.chunks(4).filter(|p| p[3] == 255).collect();
+    shades.sort();
+    shades.dedup();
+    assert!(shades.len() > 3);
+    let options = RenderOptions { background: [255, 255, 255, 255], supersampling: 1, ..Default::default() };
+    let (_, pixels) = decode_png(&cubes_to_png(&cubes, &library, &options).map_err(|_| ())?);
+    assert!(pixels.chunks(4).all(|p| p[3] == 255));
+    assert_eq!(&pixels[..4], &[255, 255, 255, 255]);
+    let (_, pixels) = decode_png(&cubes_to_png(&test_cubes(&[]), &library, &options).map_err(|_| ())?);
+    assert!(pixels.chunks(4).all(|p| p == [255, 255, 255, 255]));
+    // invalid sizes are errors
+    assert!(cubes_to_png(&cubes, &library, &RenderOptions { width: 0, ..Default::default() }).is_err());
+    assert!(cubes_to_png(&cubes, &library, &RenderOptions { height: 0, ..Default::default() }).is_err());
+    assert!(cubes_to_png(&cubes, &library, &RenderOptions { width: u32::MAX, supersampling: 2, ..Default::default() }).is_err());
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft", feature = "convert", feature = "robocraft2", feature = "techblox"))]
+#[test]
+fn convert_vehicle_to_png() -> Result<(), ()> {
+    use libfj::convert::{vehicle_to_png, RenderOptions};
+    use libfj::robocraft2::{Vehicle, VehicleBlock};
+    let mut vehicle = Vehicle::new(chrono::NaiveDate::from_ymd_opt(2022, 10, 1).unwrap());
+    let cluster = vehicle.push_cluster([0.0; 3], [0.0, 0.0, 0.0, 1.0]);
+    // Techblox blue, which is black in Robocraft's palette
+    let block = VehicleBlock { block_id: 1, position: [0.0; 3], rotation: [0.0, 0.0, 0.0, 1.0], scale: [1.0; 3], colour: 3, material: 0, cluster };
+    vehicle.push_block(block);
+    vehicle.push_block(VehicleBlock { position: [0.2, 0.0, 0.0], scale: [1.0, 2.0, 1.0], ..block });
+    let (info, pixels) = decode_png(&vehicle_to_png(&vehicle, &RenderOptions::default()).map_err(|_| ())?);
+    assert_eq!((info.width, info.height), (256, 256));
+    let centre = (128 * 256 + 128) * 4;
+    assert_eq!(pixels[centre + 3], 255);
+    assert!(pixels[centre + 2] > pixels[centre]); // blue
+    assert_eq!(pixels[3], 0);
+    assert_eq!(libfj::convert::techblox_palette()[3], libfj::convert::PROVISIONAL_TECHBLOX_PAINTS[3].1);
+    assert!(vehicle_to_png(&vehicle, &RenderOptions { height: 0, ..Default::default() }).is_err());
+    Ok(())
+}
@@ -0,0 +1,189 @@
+#[cfg(feature = "cardlife")]
+use libfj::retry::{RetryPolicy, RateLimiter, RequestPolicy, parse_retry_after};
+#[cfg(feature = "cardlife")]
+use std::time::{Duration, Instant};
+mod common;
+#[cfg(feature = "cardlife")]
+use common::{serve, http_response};
+
+#[cfg(feature = "cardlife")]
+#[test]
+fn retry_backoff() -> Result<(), ()> {
+    let policy = RetryPolicy::new()
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_secs(1))
+        .jitter(false);
+    assert_eq!(policy.backoff(0), Duration::from_millis(100));
+    assert_eq!(policy.backoff(1), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(800));
+    assert_eq!(policy.backoff(4), Duration::from_secs(1));
+    assert_eq!(policy.backoff(100), Duration::from_secs(1));
+    let jittered = policy.clone().jitter(true);
+    for retry in 0..5 {
+        let delay = jittered.backoff(retry);
+        assert!(delay >= policy.backoff(retry) / 2);
+        assert!(delay <= policy.backoff(retry));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cardlife")]
+#[test]
+fn retry_status_codes() -> Result<(), ()> {
+    let policy = RetryPolicy::new()
+        .max_retries(2)
+        .base_delay(Duration::from_millis(100))
+        .jitter(false);
+    assert_eq!(policy.retry_delay(0, 503, None), Some(Duration::from_millis(100)));
+    assert_eq!(policy.retry_delay(1, 500, None), Some(Duration::from_millis(200)));
+    assert_eq!(policy.retry_delay(2, 503, None), None);
+    assert_eq!(policy.retry_delay(0, 200, None), None);
+    assert_eq!(policy.retry_delay(0, 404, None), None);
+    assert_eq!(policy.retry_delay(0, 429, Some("7")), Some(Duration::from_secs(7)));
+    assert_eq!(policy.retry_delay(0, 429, Some("3600")), None);
+    assert_eq!(policy.retry_delay(0, 429, Some("soon")), Some(Duration::from_millis(100)));
+    assert_eq!(RetryPolicy::never().retry_delay(0, 503, None), None);
+    Ok(())
+}
+
+#[cfg(feature = "cardlife")]
+#[test]
+fn retry_after_header() -> Result<(), ()> {
+    assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+    assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT"), Some(Duration::ZERO));
+    let future = parse_retry_after("Fri, 01 Jan 2300 00:00:00 GMT").unwrap();
+    assert!(future > Duration::from_secs(86400 * 365 * 200));
+    assert_eq!(parse_retry_after("Sun, 06 Foo 1994 08:49:37 GMT"), None);
+    assert_eq!(parse_retry_after("-1"), None);
+    assert_eq!(parse_retry_after(""), None);
+    Ok(())
+}
+
+#[cfg(feature = "cardlife")]
+#[test]
+#[should_panic]
+fn rate_limiter_zero_rate() {
+    RateLimiter::per_second(0.0);
+}
+
+#[cfg(feature = "cardlife")]
+#[test]
+#[should_panic]
+fn rate_limiter_nan_rate() {
+    RateLimiter::per_second(f64::NAN);
+}
+
+#[cfg(feature = "cardlife")]
+#[test]
+fn rate_limiter_reserve() -> Result<(), ()> {
+    let limiter = RateLimiter::per_second(10.0);
+    assert_eq!(limiter.interval(), Duration::from_millis(100));
+    let shared = limiter.clone();
+    assert_eq!(limiter.reserve(), Duration::ZERO);
+    let second = shared.reserve();
+    assert!(second > Duration::from_millis(90) && second <= Duration::from_millis(100));
+    let third = limiter.reserve();
+    assert!(third > Duration::from_millis(190) && third <= Duration::from_millis(200));
+    Ok(())
+}
+
+#[cfg(feature = "cardlife")]
+const STATUS_JSON: &str = r#"{"PlayersMax":10,"PlayerCount":0,"Status":"Online","OnlinePlayers":[]}"#;
+
+#[cfg(feature = "cardlife")]
+#[tokio::test]
+async fn retry_transient_errors() -> Result<(), ()> {
+    let (addr, _) = serve(vec![
+        http_response("503 Service Unavailable", "", ""),
+        http_response("429 Too Many Requests", "Retry-After: 0\r\n", ""),
+        http_response("200 OK", "", STATUS_JSON),
+    ]);
+    let policy = RequestPolicy::new()
+        .retry(RetryPolicy::new().base_delay(Duration::from_millis(10)));
+    let server = libfj::cardlife::CLreServer::new(addr.as_str()).unwrap().with_policy(policy);
+    let status = server.status_info().await.map_err(|_| ())?;
+    assert_eq!(status.status, "Online");
+    Ok(())
+}
+
+#[cfg(feature = "cardlife")]
+#[tokio::test]
+async fn retry_gives_up() -> Result<(), ()> {
+    let (addr, _) = serve(vec![
+        http_response("502 Bad Gateway", "", ""),
+        http_response("502 Bad Gateway", "", ""),
+        http_response("200 OK", "", STATUS_JSON),
+    ]);
+    let policy = RequestPolicy::new()
+        .retry(RetryPolicy::new().max_retries(1).base_delay(Duration::from_millis(10)));
+    let server = libfj::cardlife::CLreServer::new(addr.as_str()).unwrap().with_policy(policy);
+    assert!(server.status_info().await.is_err());
+    Ok(())
+}
+
+#[cfg(feature = "cardlife")]
+#[tokio::test]
+async fn retry_rate_limit() -> Result<(), ()> {
+    let (addr, _) = serve(vec![
+        http_response("200 OK", "", STATUS_JSON),
+        http_response("200 OK", "", STATUS_JSON),
+        http_response("200 OK", "", STATUS_JSON),
+    ]);
+    let policy = RequestPolicy::new()
+        .rate_limit(RateLimiter::with_interval(Duration::from_millis(100)));
+    let server = libfj::cardlife::CLreServer::new(addr.as_str()).unwrap().with_policy(policy);
+    let start = Instant::now();
+    for _ in 0..3 {
+        server.status_info().await.map_err(|_| ())?;
+    }
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    Ok(())
+}
+
+#[cfg(feature = "cardlife")]
+#[tokio::test]
+async fn retry_disabled_by_default() -> Result<(), ()> {
+    assert_eq!(RequestPolicy::default().retry, RetryPolicy::never());
+    let (addr, _) = serve(vec![
+        http_response("503 Service Unavailable", "", ""),
+        http_response("200 OK", "", STATUS_JSON),
+    ]);
+    let server = libfj::cardlife::CLreServer::new(addr.as_str()).unwrap();
+    assert!(server.status_info().await.is_err());
+    Ok(())
+}
+
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+#[test]
+fn retry_transient_errors_simple() -> Result<(), ()> {
+    let (addr, _) = serve(vec![
+        http_response("503 Service Unavailable", "", ""),
+        http_response("429 Too Many Requests", "Retry-After: 0\r\n", ""),
+        http_response("200 OK", "", STATUS_JSON),
+    ]);
+    let policy = RequestPolicy::new()
+        .retry(RetryPolicy::new().base_delay(Duration::from_millis(10)));
+    let server = libfj::cardlife_simple::CLreServer::new(addr.as_str()).unwrap().with_policy(policy);
+    let status = server.status_info().map_err(|_| ())?;
+    assert_eq!(status.status, "Online");
+    Ok(())
+}
+
+#[cfg(all(feature = "simple", feature = "cardlife"))]
+#[test]
+fn retry_gives_up_simple() -> Result<(), ()> {
+    let (addr, _) = serve(vec![
+        http_response("502 Bad Gateway", "", ""),
+        http_response("502 Bad Gateway", "", ""),
+        http_response("200 OK", "", STATUS_JSON),
+    ]);
+    let policy = RequestPolicy::new()
+        .retry(RetryPolicy::new().max_retries(1).base_delay(Duration::from_millis(10)))
+        .rate_limit(RateLimiter::with_interval(Duration::from_millis(50)));
+    let server = libfj::cardlife_simple::CLreServer::new(addr.as_str()).unwrap().with_policy(policy);
+    let start = Instant::now();
+    assert!(server.status_info().is_err());
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    Ok(())
+}
@@ -52,6 +52,24 @@ async fn robocraft2_factory_default_query() -> Result<(), ()> {
     Ok(())
 }
 
+#[cfg(feature = "robocraft2")]
+//#[tokio::test]
+#[allow(dead_code)]
+async fn robocraft2_factory_stream_query() -> Result<(), ()> {
+    use futures_util::StreamExt;
+    let api = builder().await;
+    let mut params = robocraft2::SearchPayload::default();
+    params.count = Some(10);
+    let robots: Vec<_> = api.search_stream(params).take(25).collect().await;
+    let mut ids = std::collections::HashSet::new();
+    for robot in robots {
+        let robot = unwrap_factory2(robot);
+        assert!(ids.insert(robot.robot.id.clone()), "robot {} appeared twice", robot.robot.id);
+    }
+    assert_ne!(ids.len(), 0);
+    Ok(())
+}
+
 #[cfg(feature = "robocraft2")]
 #[tokio::test]
 #[allow(dead_code)]
@@ -68,9 +86,9 @@ async fn robocraft2_factory_info_query() -> Result<(), ()> {
 #[tokio::test]
 async fn robocraft2_factory_sort() -> Result<(), ()> {
     let api = builder().await;
-    let mut query = robocraft2::SearchPayload::default();
-    query.sort_by = robocraft2::sort::DATE;
-    query.order_by = robocraft2::order::ASCENDING;
+    let query = robocraft2::SearchPayload::new()
+        .sort_by(robocraft2::SortBy::Date)
+        .order_by(robocraft2::OrderBy::Ascending);
     let result = api.search(query).await;
     let robo_info = unwrap_factory2(result);
     assert_ne!(robo_info.results.len(), 0);
@@ -251,3 +269,210 @@ fn unwrap_factory2<T>(result: Result<T, robocraft2::FactoryError>) -> T {
         }
     }
 }
+
+#[cfg(feature = "robocraft2")]
+#[test]
+fn robocraft2_factory_dates() -> Result<(), ()> {
+    use chrono::Datelike;
+    let robot: robocraft2::GetRobotResponse = serde_json::from_value(serde_json::json!({
+        "header": {
+            "id": "08dab2c9-7a72-4ec4-843c-154fe8768e91",
+            "name": "Test Bot",
+            "parentId": null,
+            "creatorId": "08dab2c9-7a72-4ec4-843c-154fe8768e92",
+            "creatorName": "Tester",
+            "created": "2022-10-20T12:34:56.789Z",
+            "image": null,
+            "baseCpu": 1,
+            "weaponCpu": 0,
+            "cosmeticCpu": 0,
+            "clusterCount": 1,
+            "blockCounts": {"1": 1},
+            "materialsUsed": [0],
+            "minimumOffsetX": 0.0,
+            "minimumOffsetY": 0.0,
+            "minimumOffsetZ": 0.0,
+            "maximumOffsetX": 0.0,
+            "maximumOffsetY": 0.0,
+            "maximumOffsetZ": 0.0,
+            "version": 1,
+        },
+        "data": "",
+        "description": "",
+        "created": "2022-10-21T00:00:00",
+        "moderated": false,
+    })).map_err(|_| ())?;
+    assert_eq!(robot.header.created_datetime().map_err(|_| ())?.day(), 20);
+    assert_eq!(robot.created_datetime().map_err(|_| ())?.day(), 21);
+    Ok(())
+}
+
+#[cfg(feature = "robocraft2")]
+#[test]
+fn robocraft2_factory_saved_query() -> Result<(), ()> {
+    let query = robocraft2::SearchPayload::new()
+        .text("tank".to_owned())
+        .sort_by(robocraft2::SortBy::MostPurchased)
+        .order_by(robocraft2::OrderBy::Descending)
+        .moderation_filter(robocraft2::ModerationFilter::ModeratedOnly);
+    let json = serde_json::to_string(&query).map_err(|_| ())?;
+    let parsed: robocraft2::SearchPayload = serde_json::from_str(&json).map_err(|_| ())?;
+    assert_eq!(parsed, query);
+    let parsed: robocraft2::SearchPayload = serde_json::from_str("{}").map_err(|_| ())?;
+    assert_eq!(parsed, robocraft2::SearchPayload::default());
+    assert!(serde_json::from_str::<robocraft2::SearchPayload>(r#"{"sortBy": "unknown"}"#).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "robocraft2")]
+#[test]
+fn robocraft2_factory_typed_query() -> Result<(), ()> {
+    use chrono::TimeZone;
+    let min = chrono::Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+    let query = robocraft2::SearchPayload::new()
+        .base_cpu_range(Some(100), None)
+        .date_range(Some(min), None)
+        .page(1)
+        .count(50);
+    let json = serde_json::to_value(&query).map_err(|_| ())?;
+    assert_eq!(json["dateMinimum"], "2023-01-01T00:00:00Z");
+    assert_eq!(json["sortBy"], "default");
+    assert_eq!(json["modFilter"], "none");
+    let parsed: robocraft2::SearchPayload = serde_json::from_value(json).map_err(|_| ())?;
+    assert_eq!(parsed, query);
+    let config: robocraft2::SearchPayload = serde_json::from_str(r#"{"sortBy": "cpuPower", "orderBy": "descending", "modFilter": "needsModeration", "dateMaximum": "2023-06-01T12:00:00"}"#).map_err(|_| ())?;
+    assert_eq!(config.sort_by, robocraft2::SortBy::CpuPower);
+    assert_eq!(config.order_by, robocraft2::OrderBy::Descending);
+    assert_eq!(config.moderation_filter, robocraft2::ModerationFilter::NeedsModeration);
+    assert_eq!(config.date_maximum, Some(chrono::Utc.with_ymd_and_hms(2023, 6, 1, 12, 0, 0).unwrap()));
+    assert_eq!(robocraft2::SortBy::MostPurchased.as_str(), "mostPurchased");
+    assert!(serde_json::from_str::<robocraft2::SearchPayload>(r#"{"dateMinimum": "yesterday"}"#).is_err());
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft2", feature = "techblox"))]
+#[test]
+fn robocraft2_vehicle_data() -> Result<(), ()> {
+    let mut vehicle = robocraft2::Vehicle::new(chrono::NaiveDate::from_ymd_opt(2022, 10, 1).unwrap());
+    assert!(vehicle.is_empty());
+    let cluster = vehicle.push_cluster([0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+    let block = robocraft2::VehicleBlock {
+        block_id: 42,
+        position: [0.2, 0.0, -0.4],
+        rotation: [0.0, 0.7071068, 0.0, 0.7071068],
+        scale: [1.0, 1.0, 1.0],
+        colour: 3,
+        material: 8,
+        cluster,
+    };
+    assert_eq!(vehicle.push_block(block), 0);
+    vehicle.push_block(robocraft2::VehicleBlock { block_id: 3, position: [0.0, 0.2, 0.0], ..block });
+    let data = vehicle.encode().map_err(|_| ())?;
+    let decoded = robocraft2::Vehicle::decode(&data).map_err(|_| ())?;
+    assert_eq!(decoded.version, vehicle.version);
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded.block(0), Some(block));
+    assert_eq!(decoded.blocks().collect::<Vec<_>>(), vehicle.blocks().collect::<Vec<_>>());
+    assert_eq!(decoded.clusters().collect::<Vec<_>>(), vehicle.clusters().collect::<Vec<_>>());
+    assert_eq!(decoded.encode().map_err(|_| ())?, data);
+    assert!(matches!(robocraft2::Vehicle::decode("not base64!"), Err(robocraft2::VehicleError::Base64(_))));
+    assert!(matches!(robocraft2::Vehicle::decode("AAAA"), Err(robocraft2::VehicleError::Parse(_))));
+    let mut vehicle = decoded;
+    assert!(vehicle.remove_block(0).is_some());
+    assert!(vehicle.remove_block(1).is_none());
+    assert_eq!(vehicle.block(0).map(|b| b.block_id), Some(3));
+    // entity ids stay unique after removing blocks
+    vehicle.push_block(block);
+    assert_ne!(vehicle.block_headers[0].entity_id, vehicle.block_headers[1].entity_id);
+    // headers and entities must match up
+    vehicle.block_headers.pop();
+    assert!(matches!(vehicle.encode(), Err(robocraft2::VehicleError::Parse(_))));
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft2", feature = "techblox"))]
+#[test]
+fn robocraft2_vehicle_techblox_layout() -> Result<(), ()> {
+    use libfj::techblox::{GameSave, Parsable};
+    use base64::Engine;
+    use chrono::Datelike;
+    use std::io::Read;
+    // No vehicle data captured from the CRF is available, so this only checks that the assumed layout
+    // (the block section of a Techblox game save) round-trips real Techblox blocks.
+    // It does not show that RC2 vehicles use this layout.
+    let mut buf = Vec::new();
+    std::fs::File::open("tests/All.Techblox").map_err(|_| ())?.read_to_end(&mut buf).map_err(|_| ())?;
+    let save = GameSave::parse(&mut buf.as_slice()).map_err(|_| ())?;
+    let mut data = Vec::new();
+    for value in [save.version.year() as u32, save.version.month(), save.version.day(), save.group_len] {
+        data.extend(value.to_le_bytes());
+    }
+    for (header, group) in save.group_headers.iter().zip(save.cube_groups.iter()) {
+        header.dump_versioned(&mut data, &save.version).map_err(|_| ())?;
+        group.dump_versioned(&mut data, &save.version).map_err(|_| ())?;
+    }
+    data.extend(save.cube_len.to_le_bytes());
+    for (header, block) in save.cube_headers.iter().zip(save.cube_entities.iter()) {
+        header.dump_versioned(&mut data, &save.version).map_err(|_| ())?;
+        block.dump_versioned(&mut data, &save.version).map_err(|_| ())?;
+    }
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&data);
+    let vehicle = robocraft2::Vehicle::decode(&encoded).map_err(|_| ())?;
+    assert_eq!(vehicle.version, save.version);
+    assert_eq!(vehicle.len(), save.cube_len as usize);
+    assert_eq!(vehicle.clusters().count(), save.group_len as usize);
+    for (block, entity) in vehicle.blocks().zip(save.cube_entities.iter()) {
+        assert_eq!(block.block_id, (**entity).as_ref().db_component.dbid);
+    }
+    assert_eq!(vehicle.encode().map_err(|_| ())?, encoded);
+    Ok(())
+}
+
+#[cfg(all(feature = "robocraft2", feature = "techblox"))]
+#[test]
+fn robocraft2_vehicle_upload_payload() -> Result<(), ()> {
+    let mut vehicle = robocraft2::Vehicle::new(chrono::NaiveDate::from_ymd_opt(2022, 10, 1).unwrap());
+    let result = robocraft2::CreateRobotPayload::from_vehicle("Test".to_owned(), &vehicle, &robocraft2::BlockTable::new(), "".to_owned());
+    assert!(matches!(result, Err(robocraft2::VehicleError::Empty)));
+    let cluster = vehicle.push_cluster([0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+    let block = robocraft2::VehicleBlock {
+        block_id: 42,
+        position: [0.0, 0.0, 0.0],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+        scale: [1.0, 1.0, 1.0],
+        colour: 0,
+        material: 8,
+        cluster,
+    };
+    vehicle.push_block(block);
+    vehicle.push_block(robocraft2::VehicleBlock { position: [0.2, 0.4, -0.2], ..block });
+    vehicle.push_block(robocraft2::VehicleBlock { block_id: 3, position: [-0.2, 0.2, 0.0], material: 4, ..block });
+    let mut table = robocraft2::BlockTable::from_pairs(&[
+        (42, robocraft2::BlockStats { cpu: 10, category: robocraft2::CpuCategory::Base }),
+    ]);
+    let result = robocraft2::CreateRobotPayload::from_vehicle("Test".to_owned(), &vehicle, &table, "".to_owned());
+    assert!(matches!(result, Err(robocraft2::VehicleError::UnknownBlocks(ref ids)) if ids == &vec![3]));
+    table.insert(3, robocraft2::BlockStats { cpu: 5, category: robocraft2::CpuCategory::Weapon });
+    let payload = robocraft2::CreateRobotPayload::from_vehicle("Test".to_owned(), &vehicle, &table, "".to_owned()).map_err(|_| ())?;
+    assert_eq!(payload.base_cpu, 20);
+    assert_eq!(payload.weapon_cpu, 5);
+    assert_eq!(payload.cosmetic_cpu, 0);
+    assert_eq!(payload.cluster_count, 1);
+    assert_eq!(payload.block_counts.get(&42), Some(&2));
+    assert_eq!(payload.block_counts.get(&3), Some(&1));
+    assert_eq!(payload.materials_used, vec![8, 4].into_iter().collect());
+    // block positions plus half a block
+    let close = |a: [f64; 3], b: [f64; 3]| (0..3).all(|i| (a[i] - b[i]).abs() < 1e-6);
+    assert!(close([payload.minimum_offset_x, payload.minimum_offset_y, payload.minimum_offset_z], [-0.3, -0.1, -0.3]));
+    assert!(close([payload.maximum_offset_x, payload.maximum_offset_y, payload.maximum_offset_z], [0.3, 0.5, 0.1]));
+    assert_eq!(robocraft2::Vehicle::decode(&payload.data).map_err(|_| ())?.len(), 3);
+    // a long block turned 90 degrees around the y axis
+    let mut long = robocraft2::Vehicle::new(chrono::NaiveDate::from_ymd_opt(2022, 10, 1).unwrap());
+    let half_turn = std::f32::consts::FRAC_1_SQRT_2;
+    long.push_block(robocraft2::VehicleBlock { rotation: [0.0, half_turn, 0.0, half_turn], scale: [3.0, 1.0, 1.0], ..block });
+    let (min, max) = long.bounding_box().ok_or(())?;
+    let close = |a: [f32; 3], b: [f32; 3]| (0..3).all(|i| (a[i] - b[i]).abs() < 1e-6);
+    assert!(close(min, [-0.1, -0.1, -0.3]));
+    assert!(close(max, [0.1, 0.1, 0.3]));
+    Ok(())
+}
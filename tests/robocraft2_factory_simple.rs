@@ -0,0 +1,52 @@
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+use libfj::robocraft2_simple;
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+use libfj::robocraft2;
+
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+fn builder() -> robocraft2_simple::FactoryAPI {
+    let token = robocraft2_simple::PortalTokenProvider::with_username("FJAPIC00L", "P4$$w0rd").unwrap();
+    robocraft2_simple::FactoryAPI::with_auth(Box::new(token))
+}
+
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+#[test]
+fn robocraft2_factory_default_query_simple() -> Result<(), ()> {
+    let api = builder();
+    let result = api.list();
+    assert!(result.is_ok());
+    let robo_info = result.unwrap();
+    assert_ne!(robo_info.results.len(), 0);
+    for robot in &robo_info.results {
+        assert_ne!(robot.robot.name, "");
+        assert_ne!(robot.robot.creator_id, "");
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+#[test]
+fn robocraft2_factory_sort_simple() -> Result<(), ()> {
+    let api = builder();
+    let query = robocraft2::SearchPayload::new()
+        .sort_by(robocraft2::SortBy::Date)
+        .order_by(robocraft2::OrderBy::Descending)
+        .count(10);
+    let robo_info = api.search(query).map_err(|_| ())?;
+    assert_ne!(robo_info.results.len(), 0);
+    let robot = api.get(robo_info.results[0].robot.id.clone()).map_err(|_| ())?;
+    assert_eq!(robot.header.id, robo_info.results[0].robot.id);
+    Ok(())
+}
+
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+//#[test]
+#[allow(dead_code)]
+fn robocraft2_factory_my_bots_simple() -> Result<(), ()> {
+    let api = builder();
+    let result = api.my_robots().map_err(|_| ())?;
+    for robot in &result.vehicles {
+        assert_ne!(robot.name, "");
+    }
+    Ok(())
+}
@@ -62,3 +62,14 @@ async fn robocraft2_simple_account() -> Result<(), ()> {
     assert_eq!(account.created_date, "2019-01-18T14:48:09");
     Ok(())
 }
+
+#[cfg(all(feature = "simple", feature = "robocraft2"))]
+#[test]
+fn robocraft2_blocking_account() -> Result<(), ()> {
+    let token_maybe = libfj::robocraft2_simple::PortalTokenProvider::with_username("FJAPIC00L", "P4$$w0rd");
+    assert!(token_maybe.is_ok());
+    let token_provider = token_maybe.unwrap();
+    let account = token_provider.get_account_info().map_err(|_| ())?;
+    assert_eq!(account.display_name, "FJAPIC00L");
+    Ok(())
+}
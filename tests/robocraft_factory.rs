@@ -1,3 +1,6 @@
+mod common;
+#[cfg(feature = "robocraft")]
+use common::test_robot_info;
 #[cfg(feature = "robocraft")]
 use libfj::robocraft;
 #[cfg(feature = "robocraft")]
@@ -74,6 +77,26 @@ async fn robocraft_factory_custom_query() -> Result<(), ()> {
     Ok(())
 }
 
+#[cfg(feature = "robocraft")]
+#[tokio::test]
+async fn robocraft_factory_stream_query() -> Result<(), ()> {
+    use futures_util::StreamExt;
+    let robots: Vec<_> = builder()
+        .movement_or(robocraft::FactoryMovementType::Wheels)
+        .items_per_page(10)
+        .stream()
+        .take(25)
+        .collect().await;
+    assert_eq!(robots.len(), 25);
+    let mut ids = std::collections::HashSet::new();
+    for robot in robots {
+        let robot = robot.map_err(|_| ())?;
+        assert_ne!(robot.item_name, "");
+        assert!(ids.insert(robot.item_id), "robot {} appeared twice", robot.item_id);
+    }
+    Ok(())
+}
+
 #[cfg(feature = "robocraft")]
 //#[tokio::test]
 #[allow(dead_code)]
@@ -120,3 +143,232 @@ async fn robocraft_factory_robot_cubes() -> Result<(), ()> {
     assert_eq!(colour_str, bot_info.response.colour_data);
     Ok(())
 }
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn robocraft_cubes_editing() -> Result<(), ()> {
+    let cube = |id, x, y, z, colour| robocraft::Cube { id, x, y, z, orientation: 0, colour };
+    let mut cubes = robocraft::Cubes::from(vec![cube(1, 0, 0, 0, 3), cube(1, 1, 0, 0, 3), cube(2, 1, 2, 0, 5)]);
+    assert_eq!(cubes.provided_len, 3);
+    cubes.push(cube(3, 4, 0, 1, 3));
+    assert_eq!(cubes.provided_len, 4);
+    assert_eq!(cubes.get_at(1, 2, 0).map(|c| c.id), Some(2));
+    assert!(cubes.get_at(2, 2, 2).is_none());
+    assert_eq!(cubes.bounding_box(), Some(([0, 0, 0], [4, 2, 1])));
+    assert_eq!(cubes.replace_colour(3, 7), 3);
+    assert_eq!(cubes.iter().filter(|c| c.colour == 7).count(), 3);
+    for c in cubes.iter_mut() {
+        c.colour = 1;
+    }
+    assert!(cubes.iter().all(|c| c.colour == 1));
+    // translation
+    assert_eq!(cubes.translate(-1, 0, 0), Err(robocraft::CubeOutOfRange));
+    assert!(cubes.translate(0, 254, 0).is_err());
+    assert_eq!(cubes.bounding_box(), Some(([0, 0, 0], [4, 2, 1])));
+    cubes.translate(2, 1, 3).map_err(|_| ())?;
+    assert_eq!(cubes.bounding_box(), Some(([2, 1, 3], [6, 3, 4])));
+    // removal
+    assert_eq!(cubes.remove_at(6, 1, 4).map(|c| c.id), Some(3));
+    assert_eq!(cubes.provided_len, 3);
+    assert!(cubes.remove(3).is_none());
+    cubes.retain(|c| c.id == 1);
+    assert_eq!(cubes.provided_len, 2);
+    assert_eq!(cubes.len(), 2);
+    let (cube_data, colour_data) = cubes.dump();
+    assert_eq!(&cube_data[..4], &2u32.to_le_bytes());
+    assert_eq!(&colour_data[..4], &2u32.to_le_bytes());
+    cubes.clear();
+    assert!(cubes.is_empty());
+    assert_eq!(cubes.provided_len, 0);
+    assert_eq!(cubes.bounding_box(), None);
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn robocraft_cubes_transform() -> Result<(), ()> {
+    let cube = |x, y, z, orientation| robocraft::Cube { id: 1, x, y, z, orientation, colour: 0 };
+    let original = robocraft::Cubes::from(vec![cube(10, 5, 20, 0), cube(12, 5, 20, 1), cube(10, 6, 21, 4)]);
+    let mut cubes = original.clone();
+    // rotation keeps the minimum corner
+    cubes.rotate(robocraft::CubeAxis::Y, 1).map_err(|_| ())?;
+    assert_eq!(cubes.bounding_box(), Some(([10, 5, 20], [11, 6, 22])));
+    // a full turn gets back to the start
+    cubes.rotate(robocraft::CubeAxis::Y, 3).map_err(|_| ())?;
+    assert_eq!(cubes.iter().copied().collect::<Vec<_>>(), original.iter().copied().collect::<Vec<_>>());
+    cubes.rotate(robocraft::CubeAxis::X, -1).map_err(|_| ())?;
+    cubes.rotate(robocraft::CubeAxis::X, 1).map_err(|_| ())?;
+    assert_eq!(cubes.iter().copied().collect::<Vec<_>>(), original.iter().copied().collect::<Vec<_>>());
+    // mirroring twice gets back to the start
+    cubes.mirror(robocraft::CubeAxis::X).map_err(|_| ())?;
+    assert_eq!(cubes.get(0).map(|c| (c.x, c.y, c.z)), Some((12, 5, 20)));
+    assert_eq!(cubes.get(1).map(|c| (c.x, c.orientation)), Some((10, 3)));
+    cubes.mirror(robocraft::CubeAxis::X).map_err(|_| ())?;
+    assert_eq!(cubes.iter().copied().collect::<Vec<_>>(), original.iter().copied().collect::<Vec<_>>());
+    // rotated robot must fit
+    let mut tall = robocraft::Cubes::from(vec![cube(250, 0, 0, 0), cube(250, 20, 0, 0)]);
+    assert!(tall.rotate(robocraft::CubeAxis::Z, 1).is_err());
+    assert_eq!(tall.get(1).map(|c| (c.x, c.y)), Some((250, 20)));
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn robocraft_robot_analysis() -> Result<(), ()> {
+    let cube = |id, x, y, z| robocraft::Cube { id, x, y, z, orientation: 0, colour: 0 };
+    let amounts = robocraft::parse_cube_amounts(r#"{"1": 3, "2": 1}"#).map_err(|_| ())?;
+    assert_eq!(amounts.get(&1), Some(&3));
+    assert_eq!(amounts.get(&2), Some(&1));
+    assert!(robocraft::parse_cube_amounts(r#"{"cube": 3}"#).is_err());
+    let table = robocraft::CubeTable::from_pairs(&[
+        (1, robocraft::CubeStats { cpu: 1, robot_ranking: 100 }),
+        (2, robocraft::CubeStats { cpu: 20, robot_ranking: 5000 }),
+    ]);
+    assert_eq!(table.len(), 2);
+    assert!(table.contains(2) && !table.contains(3));
+    assert_eq!(table.get(3), None);
+    let mut fallback_table = table.clone();
+    fallback_table.set_default(robocraft::CubeStats { cpu: 7, robot_ranking: 0 });
+    assert_eq!(fallback_table.get(3).map(|s| s.cpu), Some(7));
+    assert_eq!(fallback_table.get(1).map(|s| s.cpu), Some(1));
+    // valid robot
+    let cubes = robocraft::Cubes::from(vec![cube(1, 0, 0, 0), cube(1, 1, 0, 0), cube(1, 1, 1, 0), cube(2, 1, 1, 1)]);
+    assert_eq!(robocraft::cube_counts(&cubes), amounts);
+    let estimate = table.estimate(&cubes);
+    assert!(estimate.is_complete());
+    assert_eq!(estimate.cpu, 23);
+    assert_eq!(estimate.robot_ranking, 5300);
+    let analysis = robocraft::analyse_robot(&test_robot_info(&cubes, 23, r#"{"1": 3, "2": 1}"#), &table);
+    assert!(analysis.is_ok(), "unexpected issues {:?}", analysis.issues);
+    assert_eq!(analysis.island_count, 1);
+    assert_eq!(analysis.cube_amounts, Some(amounts));
+    // broken robot
+    let mut cubes = cubes;
+    cubes.push(cube(3, 5, 5, 5));
+    cubes.push(cube(1, 5, 5, 5));
+    cubes.provided_len = 7;
+    let islands = robocraft::islands(&cubes);
+    assert_eq!(islands, vec![vec![0, 1, 2, 3], vec![4, 5]]);
+    let analysis = robocraft::analyse_robot(&test_robot_info(&cubes, 23, r#"{"1": 3, "2": 1}"#), &table);
+    assert!(!analysis.estimate.is_complete());
+    assert_eq!(analysis.issues, vec![
+        robocraft::RobotIssue::LengthMismatch { provided: 7, parsed: 6 },
+        robocraft::RobotIssue::OverlappingCubes { count: 1 },
+        robocraft::RobotIssue::Disconnected { islands: 2 },
+        robocraft::RobotIssue::CubeAmountsMismatch,
+    ]);
+    // reported CPU is only checked when all cubes are known
+    cubes.retain(|c| c.id != 3);
+    let analysis = robocraft::analyse_robot(&test_robot_info(&cubes, 23, "not json"), &table);
+    assert!(analysis.issues.contains(&robocraft::RobotIssue::CpuMismatch { reported: 23, estimated: 24 }));
+    assert!(analysis.issues.contains(&robocraft::RobotIssue::InvalidCubeAmounts));
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn robocraft_typed_fields() -> Result<(), ()> {
+    let cubes = robocraft::Cubes::from(vec![robocraft::Cube::default()]);
+    let mut info = test_robot_info(&cubes, 1, r#"{"0": 1}"#);
+    assert_eq!(info.cube_amounts_map().map_err(|_| ())?.get(&0), Some(&1));
+    info.cube_amounts = "[]".to_owned();
+    assert!(info.cube_amounts_map().is_err());
+    #[cfg(feature = "chrono")]
+    {
+        use chrono::{Datelike, Timelike};
+        let added = info.added_datetime().map_err(|_| ())?;
+        assert_eq!((added.year(), added.month(), added.day(), added.hour()), (2021, 1, 1, 0));
+        info.expiry_date = "2022-03-04T05:06:07.89+01:00".to_owned();
+        let expiry = info.expiry_datetime().map_err(|_| ())?;
+        assert_eq!((expiry.day(), expiry.hour(), expiry.minute()), (4, 4, 6));
+        assert!(info.removed_datetime().is_none());
+        info.ban_date = Some("not a date".to_owned());
+        assert!(info.ban_datetime().map(|d| d.is_err()).unwrap_or(false));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn robocraft_factory_type_filters() -> Result<(), ()> {
+    let query = builder()
+        .movement_or(robocraft::FactoryMovementType::Aerofoils)
+        .movement_or(robocraft::FactoryMovementType::Wheels)
+        .movement_subcategory_or(robocraft::FactoryMovementType::Hovers, 200001)
+        .movement_subcategory_or(robocraft::FactoryMovementType::Wheels, 100002)
+        .weapon_or(robocraft::FactoryWeaponType::Laser)
+        .weapon_or(robocraft::FactoryWeaponType::Laser);
+    let movements = query.movements();
+    assert_eq!(movements.categories().collect::<Vec<_>>(), vec![robocraft::FactoryMovementType::Wheels, robocraft::FactoryMovementType::Aerofoils]);
+    assert!(movements.contains(robocraft::FactoryMovementType::Hovers));
+    assert!(!movements.contains(robocraft::FactoryMovementType::Rotors));
+    assert_eq!(movements.filter_string(), "100000,200001,300000");
+    assert_eq!(movements.category_filter_string(), "100000,200000,300000");
+    assert_eq!(query.weapons().filter_string(), "10000000");
+    let mut filter = query.movements().clone();
+    assert!(filter.remove(robocraft::FactoryMovementType::Hovers));
+    assert!(!filter.remove(robocraft::FactoryMovementType::Hovers));
+    assert_eq!(filter.category_filter_string(), "100000,300000");
+    let query = query.movement_raw("700000".to_owned());
+    assert!(query.movements().is_empty());
+    let query = query.movement_filter(filter.clone());
+    assert_eq!(query.movements(), &filter);
+    assert_eq!(robocraft::FactoryTypeFilter::<robocraft::FactoryWeaponType>::new().filter_string(), "");
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[test]
+fn robocraft_factory_saved_query() -> Result<(), ()> {
+    let query = robocraft::FactoryQuery::new()
+        .page(2)
+        .items_per_page(25)
+        .order(robocraft::FactoryOrderType::MostBought)
+        .movement_or(robocraft::FactoryMovementType::Wheels)
+        .movement_subcategory_or(robocraft::FactoryMovementType::Hovers, 200001)
+        .weapon_or(robocraft::FactoryWeaponType::Laser)
+        .cpu_range(100, 1000)
+        .text("Spell".to_string())
+        .text_search_type(robocraft::FactoryTextSearchType::Player);
+    let json = serde_json::to_string(&query).map_err(|_| ())?;
+    let parsed: robocraft::FactoryQuery = serde_json::from_str(&json).map_err(|_| ())?;
+    assert_eq!(parsed, query);
+    assert_eq!(parsed.movements().filter_string(), "100000,200001");
+    assert_ne!(parsed, query.clone().page(3));
+    // editing only the types of a saved query also changes the filter strings
+    let mut saved: serde_json::Value = serde_json::from_str(&json).map_err(|_| ())?;
+    let plasma = serde_json::to_value(robocraft::FactoryQuery::new().weapon_or(robocraft::FactoryWeaponType::PlasmaLauncher)).map_err(|_| ())?;
+    saved["weaponTypes"] = plasma["weaponTypes"].clone();
+    let edited: robocraft::FactoryQuery = serde_json::from_value(saved).map_err(|_| ())?;
+    let edited_json = serde_json::to_value(&edited).map_err(|_| ())?;
+    assert_eq!(edited_json["weaponFilter"], plasma["weaponFilter"]);
+    assert_eq!(edited_json["weaponCategoryFilter"], plasma["weaponCategoryFilter"]);
+    assert_eq!(edited_json["movementFilter"], serde_json::to_value(&query).map_err(|_| ())?["movementFilter"]);
+    // raw filters are kept
+    let raw = robocraft::FactoryQuery::new().weapon_raw("10000000".to_string());
+    let parsed: robocraft::FactoryQuery = serde_json::from_str(&serde_json::to_string(&raw).map_err(|_| ())?).map_err(|_| ())?;
+    assert_eq!(parsed, raw);
+    let search = builder().with_query(query.clone());
+    assert_eq!(search.query(), &query);
+    assert_eq!(search.weapon_or(robocraft::FactoryWeaponType::PlasmaLauncher).query().weapons().filter_string(), "10000000,20000000");
+    Ok(())
+}
+
+#[cfg(feature = "robocraft")]
+#[tokio::test]
+async fn robocraft_factory_cached_get() -> Result<(), ()> {
+    let cube = robocraft::Cube { id: 1, x: 0, y: 0, z: 0, orientation: 0, colour: 0 };
+    let info = robocraft::FactoryInfo {
+        response: test_robot_info(&robocraft::Cubes::from(vec![cube]), 1, r#"{"1": 1}"#),
+        status_code: 200,
+    };
+    let cache = libfj::cache::ResponseCache::new(16, std::time::Duration::from_secs(60));
+    cache.insert("42", info);
+    let api = robocraft::FactoryAPI::new().with_cache(cache.clone());
+    // cached robots are returned without a request
+    let robot = api.get(42).await.map_err(|_| ())?;
+    assert_eq!(robot.response.item_name, "Test Bot");
+    assert_eq!(cache.stats().memory_hits, 1);
+    assert_eq!(api.cache().map(|c| c.stats().misses), Some(0));
+    Ok(())
+}
@@ -65,6 +65,19 @@ fn robocraft_factory_custom_query_simple() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+#[cfg(all(feature = "simple", feature = "robocraft"))]
+fn robocraft_factory_iter_query_simple() -> Result<(), ()> {
+    let mut ids = std::collections::HashSet::new();
+    for robot in builder().items_per_page(10).iter().take(25) {
+        let robot = robot.map_err(|_| ())?;
+        assert_ne!(robot.item_name, "");
+        assert!(ids.insert(robot.item_id), "robot {} appeared twice", robot.item_id);
+    }
+    assert_eq!(ids.len(), 25);
+    Ok(())
+}
+
 #[test]
 #[cfg(all(feature = "simple", feature = "robocraft"))]
 fn robocraft_factory_player_query() -> Result<(), ()> {
@@ -98,3 +111,18 @@ fn robocraft_factory_robot_query() -> Result<(), ()> {
     assert_ne!(bot_info.response.colour_data, "");
     Ok(())
 }
+
+#[test]
+#[cfg(all(feature = "simple", feature = "robocraft"))]
+fn robocraft_factory_type_filters_simple() -> Result<(), ()> {
+    let query = builder()
+        .movement_or(robocraft::FactoryMovementType::Wheels)
+        .movement_or(robocraft::FactoryMovementType::Aerofoils)
+        .weapon_subcategory_or(robocraft::FactoryWeaponType::RailCannon, 30000001)
+        .weapon_or(robocraft::FactoryWeaponType::Laser);
+    assert_eq!(query.movements().filter_string(), "100000,300000");
+    assert_eq!(query.weapons().filter_string(), "10000000,30000001");
+    assert_eq!(query.weapons().category_filter_string(), "10000000,30000000");
+    assert_eq!(query.weapons().subcategories().collect::<Vec<_>>(), vec![(robocraft::FactoryWeaponType::RailCannon, 30000001)]);
+    Ok(())
+}
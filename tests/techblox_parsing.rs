@@ -1,13 +1,19 @@
 #[cfg(feature = "techblox")]
 use libfj::techblox;
 #[cfg(feature = "techblox")]
-use libfj::techblox::{SerializedEntityDescriptor, Parsable, blocks, EntityHeader};
+use libfj::techblox::{SerializedEntityDescriptor, SerializedEntityComponent, Parsable, blocks, EntityHeader};
+#[cfg(feature = "techblox")]
+use libfj::techblox::blocks::{Block, BlockEntity};
 #[cfg(feature = "techblox")]
 use std::io::{Read, Seek};
 #[cfg(feature = "techblox")]
 use std::fs::{File, OpenOptions};
 #[cfg(feature = "techblox")]
 use std::convert::AsRef;
+#[cfg(feature = "techblox")]
+use libfj_parsable_macro_derive::{Parsable, EntityDescriptor};
+#[cfg(feature = "techblox")]
+use chrono::naive::NaiveDate;
 
 #[cfg(feature = "techblox")]
 const GAMESAVE_PATH: &str = "tests/GameSave.Techblox";
@@ -130,16 +136,42 @@ fn techblox_gamesave_parse() -> Result<(), ()> {
     Ok(())
 }
 
-#[allow(dead_code)]
 #[cfg(feature = "techblox")]
-//#[test]
+#[test]
 fn techblox_gamesave_brute_force() -> Result<(), ()> {
-    // this is slow and not very important, so it's probably better to not test this
     let mut f = File::open(GAMESAVE_PATH).map_err(|_| ())?;
     let mut buf = Vec::new();
     f.read_to_end(&mut buf).map_err(|_| ())?;
     let gs = techblox::GameSave::parse(&mut buf.as_slice()).map_err(|_| ())?;
-    println!("murmurhash3: {} -> {}", gs.group_headers[0].guess_name(), gs.group_headers[0].hash);
+    let name = gs.group_headers[0].guess_name();
+    println!("murmurhash3: {:?} -> {}", name, gs.group_headers[0].hash);
+    assert_eq!(name.as_deref(), Some("BlockGroupEntityDescriptorV0"));
+    Ok(())
+}
+
+#[cfg(feature = "techblox")]
+#[test]
+fn techblox_hash_reverser() -> Result<(), ()> {
+    let header = EntityHeader::from_name("PilotSeatEntityDescriptorV4", 0, 0, 0);
+    let reverser = techblox::HashReverser::new().max_results(4);
+    assert_eq!(header.guess_names(&reverser), vec!["PilotSeatEntityDescriptorV4".to_string()]);
+    // custom words
+    let header = EntityHeader::from_name("FooBarEntityDescriptorV2", 0, 0, 0);
+    assert!(header.guess_names(&techblox::HashReverser::new().max_tokens(2)).is_empty());
+    let reverser = techblox::HashReverser::new().tokens(&["Foo", "Bar"]).max_tokens(2).threads(2);
+    assert_eq!(reverser.reverse(header.hash).as_deref(), Some("FooBarEntityDescriptorV2"));
+    // results do not depend on the amount of threads
+    let header = EntityHeader::from_name("WireEntityDescriptorMock", 0, 0, 0);
+    let single = techblox::HashReverser::new().threads(1).reverse_all(header.hash);
+    let multi = techblox::HashReverser::new().threads(8).reverse_all(header.hash);
+    assert_eq!(single.first().map(|s| s.as_str()), Some("WireEntityDescriptorMock"));
+    assert_eq!(single, multi);
+    // cancelled before starting
+    let cancel = techblox::CancelToken::new();
+    cancel.cancel();
+    assert!(techblox::HashReverser::new().cancel_token(cancel).reverse(header.hash).is_none());
+    // nothing can be found in no time
+    assert!(techblox::HashReverser::new().max_tokens(5).timeout(std::time::Duration::ZERO).reverse(1).is_none());
     Ok(())
 }
 
@@ -253,3 +285,206 @@ fn is_in_block_groups(id: i32, block_groups: &Vec<techblox::BlockGroupEntity>) -
     }
     false
 }
+
+#[cfg(feature = "techblox")]
+#[derive(Parsable, PartialEq, Debug)]
+struct AttributeTestStruct {
+    count: u32,
+    #[parsable(len = "count")]
+    items: Vec<u32>,
+    #[parsable(bool)]
+    flag: bool,
+    #[parsable(skip)]
+    cache: Option<u64>,
+    #[parsable(since = "2022-01-01")]
+    new_field: u8,
+}
+
+#[cfg(feature = "techblox")]
+#[derive(Parsable, PartialEq, Debug)]
+struct TupleTestStruct(u8, #[parsable(len = "0")] Vec<i32>);
+
+#[cfg(feature = "techblox")]
+#[derive(Parsable, PartialEq, Debug)]
+#[parsable(repr = "u32")]
+enum EnumTestEnum {
+    #[parsable(tag = 4)]
+    Unit,
+    #[parsable(tag = 7)]
+    Tuple(u8, #[parsable(bool)] bool),
+    Named { value: f32 },
+}
+
+#[cfg(feature = "techblox")]
+#[test]
+fn techblox_parsable_derive_attributes() -> Result<(), ()> {
+    let data = AttributeTestStruct {
+        count: 2,
+        items: vec![42, 1337],
+        flag: true,
+        cache: Some(1),
+        new_field: 3,
+    };
+    let mut buf = Vec::new();
+    assert_eq!(data.dump(&mut buf).map_err(|_| ())?, 4 + 8 + 1 + 1);
+    assert_eq!(buf, [2, 0, 0, 0, 42, 0, 0, 0, 57, 5, 0, 0, 1, 3]);
+    let parsed = AttributeTestStruct::parse(&mut buf.as_slice()).map_err(|_| ())?;
+    assert_eq!(parsed, AttributeTestStruct { cache: None, ..data });
+
+    // fields introduced in a later version are not present in older saves
+    let old_version = NaiveDate::from_ymd_opt(2021, 12, 31).unwrap();
+    let mut old_buf = Vec::new();
+    parsed.dump_versioned(&mut old_buf, &old_version).map_err(|_| ())?;
+    assert_eq!(old_buf, buf[..13]);
+    let old_parsed = AttributeTestStruct::parse_versioned(&mut old_buf.as_slice(), &old_version).map_err(|_| ())?;
+    assert_eq!(old_parsed.new_field, 0);
+    assert_eq!(old_parsed.items, parsed.items);
+    let new_version = NaiveDate::from_ymd_opt(2022, 1, 1).unwrap();
+    let new_parsed = AttributeTestStruct::parse_versioned(&mut buf.as_slice(), &new_version).map_err(|_| ())?;
+    assert_eq!(new_parsed, parsed);
+    Ok(())
+}
+
+#[cfg(feature = "techblox")]
+#[test]
+fn techblox_parsable_derive_tuple_and_enum() -> Result<(), ()> {
+    let tuple = TupleTestStruct(1, vec![-1]);
+    let mut buf = Vec::new();
+    tuple.dump(&mut buf).map_err(|_| ())?;
+    assert_eq!(buf, [1, 255, 255, 255, 255]);
+    assert_eq!(TupleTestStruct::parse(&mut buf.as_slice()).map_err(|_| ())?, tuple);
+
+    for (value, expected) in [
+        (EnumTestEnum::Unit, vec![4, 0, 0, 0]),
+        (EnumTestEnum::Tuple(9, false), vec![7, 0, 0, 0, 9, 0]),
+        (EnumTestEnum::Named { value: 1.0 }, vec![8, 0, 0, 0, 0, 0, 128, 63]),
+    ] {
+        let mut buf = Vec::new();
+        value.dump(&mut buf).map_err(|_| ())?;
+        assert_eq!(buf, expected);
+        assert_eq!(EnumTestEnum::parse(&mut buf.as_slice()).map_err(|_| ())?, value);
+    }
+    assert!(EnumTestEnum::parse(&mut [5u8, 0, 0, 0].as_slice()).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "techblox")]
+#[derive(Copy, Clone, Parsable, EntityDescriptor)]
+#[descriptor(name = "TestSeatEntityDescriptorV0", parent = block)]
+struct TestSeatEntity {
+    block: BlockEntity,
+    cam_component: blocks::SeatFollowCamComponent,
+    group_component: blocks::BlockGroupEntityComponent,
+}
+
+#[cfg(feature = "techblox")]
+#[test]
+fn techblox_entity_descriptor_derive() -> Result<(), ()> {
+    let mut in_file = File::open(GAMESAVE_PATH_ALL).map_err(|_| ())?;
+    let mut buf = Vec::new();
+    in_file.read_to_end(&mut buf).map_err(|_| ())?;
+    let gs = techblox::GameSave::parse(&mut buf.as_slice()).map_err(|_| ())?;
+    let block: &BlockEntity = gs.cube_entities[0].as_ref().as_ref();
+    let mut seat = TestSeatEntity {
+        block: *block,
+        cam_component: blocks::SeatFollowCamComponent { follow: 1 },
+        group_component: blocks::BlockGroupEntityComponent { current_block_group: 42 },
+    };
+    assert_eq!(TestSeatEntity::serialized_components(), BlockEntity::serialized_components() + 2);
+    assert_eq!(seat.components().len(), TestSeatEntity::serialized_components() as usize);
+    assert_eq!(seat.components_mut().len(), TestSeatEntity::serialized_components() as usize);
+    assert_eq!(seat.hash_name(), EntityHeader::from_name(TestSeatEntity::HASHNAME, 0, 0, 0).hash);
+    let boxed: Box<dyn Block> = Box::new(seat);
+    let as_block: &BlockEntity = boxed.as_ref().as_ref();
+    assert_eq!(as_block.pos_component.position.x, block.pos_component.position.x);
+
+    assert_eq!(blocks::EngineBlockEntity::HASHNAME, "EngineBlockEntityDescriptor");
+    // hashes are computed by the derive
+    assert_eq!(TestSeatEntity::HASH, techblox::EntityHeader::from_name(TestSeatEntity::HASHNAME, 0, 0, 0).hash);
+    assert_eq!(blocks::PilotSeatEntity::HASH, 2281299333);
+    assert_eq!(blocks::TyreEntity::HASH, 1517625162);
+    assert_eq!(BlockEntity::HASH, 1357220432);
+    assert_eq!(techblox::SerializedFlyCamEntity::HASH, 252528354);
+    // blocks using the derive are registered for lookup_hashname(...), even outside of libfj
+    let mut data = Vec::new();
+    seat.dump(&mut data).map_err(|_| ())?;
+    let parsed = blocks::lookup_hashname(TestSeatEntity::HASH, &mut data.as_slice()).map_err(|_| ())?;
+    assert_eq!(parsed.hash_name(), TestSeatEntity::HASH);
+    assert_eq!(blocks::lookup_name_by_hash(TestSeatEntity::HASH), Some(TestSeatEntity::HASHNAME));
+    for (name, hash) in [
+        (BlockEntity::HASHNAME, BlockEntity::HASH),
+        (blocks::PilotSeatEntity::HASHNAME, blocks::PilotSeatEntity::HASH),
+        (blocks::PassengerSeatEntity::HASHNAME, blocks::PassengerSeatEntity::HASH),
+        (blocks::EngineBlockEntity::HASHNAME, blocks::EngineBlockEntity::HASH),
+        (blocks::JointBlockEntity::HASHNAME, blocks::JointBlockEntity::HASH),
+        (blocks::DampedAngularSpringEntity::HASHNAME, blocks::DampedAngularSpringEntity::HASH),
+        (blocks::DampedSpringEntity::HASHNAME, blocks::DampedSpringEntity::HASH),
+        (blocks::WheelRigEntity::HASHNAME, blocks::WheelRigEntity::HASH),
+        (blocks::WheelRigSteerableEntity::HASHNAME, blocks::WheelRigSteerableEntity::HASH),
+        (blocks::TyreEntity::HASHNAME, blocks::TyreEntity::HASH),
+    ] {
+        assert_eq!(hash, techblox::EntityHeader::from_name(name, 0, 0, 0).hash);
+        assert_eq!(blocks::lookup_name_by_hash(hash), Some(name));
+    }
+    assert_eq!(techblox::SerializedFlyCamEntity::serialized_components(), 2);
+    assert_eq!(techblox::BlockGroupEntity::serialized_components(), 2);
+    assert_eq!(blocks::WheelRigSteerableEntity::serialized_components(), BlockEntity::serialized_components() + 3);
+    Ok(())
+}
+
+#[cfg(feature = "techblox")]
+static CUSTOM_PARSE_COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[cfg(feature = "techblox")]
+fn parse_counted_block(data: &mut dyn Read, version: &NaiveDate) -> std::io::Result<Box<dyn Block>> {
+    CUSTOM_PARSE_COUNT.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    Ok(Box::new(BlockEntity::parse_versioned(data, version)?))
+}
+
+#[cfg(feature = "techblox")]
+#[test]
+fn techblox_descriptor_registry() -> Result<(), ()> {
+    let mut in_file = File::open(GAMESAVE_PATH_ALL).map_err(|_| ())?;
+    let mut buf = Vec::new();
+    in_file.read_to_end(&mut buf).map_err(|_| ())?;
+    let mut registry = blocks::DescriptorRegistry::new();
+    assert!(registry.is_empty());
+    assert!(registry.register::<TestSeatEntity>(TestSeatEntity::HASHNAME).is_none());
+    assert!(registry.register_name(BlockEntity::HASHNAME, parse_counted_block).is_none());
+    assert_eq!(registry.len(), 2);
+    assert!(registry.contains(EntityHeader::from_name(BlockEntity::HASHNAME, 0, 0, 0).hash));
+    let gs = techblox::GameSave::parse_with_registry(&mut buf.as_slice(), &registry).map_err(|_| ())?;
+    assert!(CUSTOM_PARSE_COUNT.load(std::sync::atomic::Ordering::SeqCst) > 0);
+    // custom descriptors must not change the result
+    let expected = techblox::GameSave::parse(&mut buf.as_slice()).map_err(|_| ())?;
+    let mut out = Vec::new();
+    gs.dump(&mut out).map_err(|_| ())?;
+    let mut expected_out = Vec::new();
+    expected.dump(&mut expected_out).map_err(|_| ())?;
+    assert_eq!(out, expected_out);
+    assert!(registry.unregister_hash(EntityHeader::from_name(TestSeatEntity::HASHNAME, 0, 0, 0).hash).is_some());
+    assert!(registry.lookup(EntityHeader::from_name(TestSeatEntity::HASHNAME, 0, 0, 0).hash).is_none());
+    Ok(())
+}
+
+#[cfg(feature = "techblox")]
+#[test]
+fn techblox_lookup_hashname() -> Result<(), ()> {
+    let mut in_file = File::open(GAMESAVE_PATH_ALL).map_err(|_| ())?;
+    let mut buf = Vec::new();
+    in_file.read_to_end(&mut buf).map_err(|_| ())?;
+    let gs = techblox::GameSave::parse(&mut buf.as_slice()).map_err(|_| ())?;
+    let hash = gs.cube_headers[0].hash;
+    let mut data = Vec::new();
+    gs.cube_entities[0].dump_versioned(&mut data, &gs.version).map_err(|_| ())?;
+    let block = blocks::lookup_hashname_versioned(hash, &mut data.as_slice(), &gs.version).map_err(|_| ())?;
+    assert_eq!(block.hash_name(), hash);
+    // the unversioned lookup parses for the newest game version
+    let mut newest = Vec::new();
+    gs.cube_entities[0].dump(&mut newest).map_err(|_| ())?;
+    let block = blocks::lookup_hashname(hash, &mut newest.as_slice()).map_err(|_| ())?;
+    assert_eq!(block.hash_name(), hash);
+    assert!(blocks::lookup_hashname(0, &mut newest.as_slice()).is_err());
+    assert!(blocks::lookup_name_by_hash(hash).is_some());
+    Ok(())
+}